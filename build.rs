@@ -7,7 +7,14 @@ fn main() {
     config.type_attribute("BindTypeEnum", "#[derive(TryFromPrimitive)]");
     config.type_attribute("PlatformEnum", "#[derive(TryFromPrimitive)]");
     config.type_attribute("OrderStatusEnum", "#[derive(TryFromPrimitive)]");
+    config.type_attribute("OrderFailureReasonEnum", "#[derive(TryFromPrimitive)]");
     config.type_attribute("CurrencyEnum", "#[derive(TryFromPrimitive, EnumIter)]");
+    config.type_attribute("ContractStatusEnum", "#[derive(TryFromPrimitive)]");
+    config.type_attribute("BackorderStatusEnum", "#[derive(TryFromPrimitive)]");
+    config.type_attribute("BankTransferStatusEnum", "#[derive(TryFromPrimitive)]");
+    config.type_attribute("BankLedgerKindEnum", "#[derive(TryFromPrimitive)]");
+    config.type_attribute("ItemConditionEnum", "#[derive(TryFromPrimitive)]");
+    config.type_attribute("ModerationStatusEnum", "#[derive(TryFromPrimitive)]");
     config
         .compile_protos(&["./proto/common.proto"], &["./proto"])
         .unwrap();
@@ -32,4 +39,10 @@ fn main() {
     config
         .compile_protos(&["./proto/order.proto"], &["./proto"])
         .unwrap();
+    config
+        .compile_protos(&["./proto/contract.proto"], &["./proto"])
+        .unwrap();
+    config
+        .compile_protos(&["./proto/backorder.proto"], &["./proto"])
+        .unwrap();
 }
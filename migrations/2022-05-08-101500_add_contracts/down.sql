@@ -0,0 +1,2 @@
+drop index if exists contracts_colony_id_status_index;
+drop table if exists contracts;
@@ -0,0 +1,20 @@
+create table contracts
+(
+    contract_id   uuid              not null
+        constraint contracts_pk
+            primary key,
+    colony_id     uuid              not null,
+    item_code     varchar(32)       not null,
+    quantity      integer           not null,
+    reward        integer           not null,
+    currency      integer default 0 not null,
+    status        integer default 0 not null,
+    start_tick    integer           not null,
+    deadline_tick integer           not null,
+    private_key   varchar(32)       not null,
+    create_date   timestamp         not null,
+    update_date   timestamp         not null
+);
+
+create index contracts_colony_id_status_index
+    on contracts (colony_id, status);
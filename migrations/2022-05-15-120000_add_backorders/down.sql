@@ -0,0 +1,3 @@
+drop index if exists backorders_status_create_date_index;
+drop index if exists backorders_colony_id_status_index;
+drop table if exists backorders;
@@ -0,0 +1,23 @@
+create table backorders
+(
+    backorder_id uuid              not null
+        constraint backorders_pk
+            primary key,
+    colony_id    uuid              not null,
+    item_code    varchar(32)       not null,
+    quantity     integer           not null,
+    health       real              not null,
+    paid         integer           not null,
+    currency     integer default 0 not null,
+    status       integer default 0 not null,
+    order_id     uuid,
+    placed_tick  integer           not null,
+    create_date  timestamp         not null,
+    update_date  timestamp         not null
+);
+
+create index backorders_colony_id_status_index
+    on backorders (colony_id, status);
+
+create index backorders_status_create_date_index
+    on backorders (status, create_date);
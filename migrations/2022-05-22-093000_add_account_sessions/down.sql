@@ -0,0 +1,3 @@
+drop index if exists account_sessions_account_fk_index;
+drop table if exists account_sessions;
+drop index if exists accounts_username_uindex;
@@ -0,0 +1,18 @@
+create unique index accounts_username_uindex
+    on accounts (lower(username));
+
+create table account_sessions
+(
+    session_id  varchar(64) not null
+        constraint account_sessions_pk
+            primary key,
+    account_fk  int         not null
+        constraint account_sessions_accounts_account_id_fk
+            references accounts
+            on delete cascade,
+    date_added  timestamp   not null,
+    date_expire timestamp   not null
+);
+
+create index account_sessions_account_fk_index
+    on account_sessions (account_fk);
@@ -0,0 +1,2 @@
+alter table maintenance
+    drop column if exists report;
@@ -0,0 +1,2 @@
+alter table maintenance
+    add report jsonb;
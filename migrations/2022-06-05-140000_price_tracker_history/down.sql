@@ -0,0 +1,17 @@
+drop index if exists price_tracker_create_date_index;
+
+alter table price_tracker
+    drop constraint price_tracker_pk;
+
+alter table price_tracker
+    drop column if exists sell_at;
+
+alter table price_tracker
+    drop column if exists buy_at;
+
+delete from price_tracker a using price_tracker b
+    where a.item_code = b.item_code and a.value = b.value and a.create_date < b.create_date;
+
+alter table price_tracker
+    add constraint price_tracker_pk
+        primary key (item_code, value);
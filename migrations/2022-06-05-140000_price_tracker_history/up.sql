@@ -0,0 +1,15 @@
+alter table price_tracker
+    drop constraint price_tracker_pk;
+
+alter table price_tracker
+    add buy_at numeric(10, 2) default 0.0 not null;
+
+alter table price_tracker
+    add sell_at numeric(10, 2) default 0.0 not null;
+
+alter table price_tracker
+    add constraint price_tracker_pk
+        primary key (item_code, create_date);
+
+create index price_tracker_create_date_index
+    on price_tracker (create_date desc);
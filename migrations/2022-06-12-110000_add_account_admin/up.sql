@@ -0,0 +1,2 @@
+alter table accounts
+    add admin boolean default false not null;
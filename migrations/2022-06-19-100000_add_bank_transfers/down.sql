@@ -0,0 +1,3 @@
+drop index if exists bank_transfers_to_colony_id_status_index;
+drop index if exists bank_transfers_from_colony_id_index;
+drop table if exists bank_transfers;
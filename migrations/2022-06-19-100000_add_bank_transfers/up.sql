@@ -0,0 +1,19 @@
+create table bank_transfers
+(
+    transfer_id    uuid              not null
+        constraint bank_transfers_pk
+            primary key,
+    from_colony_id uuid              not null,
+    to_colony_id   uuid              not null,
+    currency       integer default 0 not null,
+    amount         integer           not null,
+    status         integer default 0 not null,
+    create_date    timestamp         not null,
+    update_date    timestamp         not null
+);
+
+create index bank_transfers_from_colony_id_index
+    on bank_transfers (from_colony_id);
+
+create index bank_transfers_to_colony_id_status_index
+    on bank_transfers (to_colony_id, status);
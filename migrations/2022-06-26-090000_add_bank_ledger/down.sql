@@ -0,0 +1,2 @@
+drop index if exists bank_ledger_colony_id_currency_entry_id_index;
+drop table if exists bank_ledger;
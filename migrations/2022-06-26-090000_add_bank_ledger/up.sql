@@ -0,0 +1,22 @@
+create table bank_ledger
+(
+    entry_id     bigserial         not null
+        constraint bank_ledger_pk
+            primary key,
+    colony_id    uuid              not null,
+    currency     integer default 0 not null,
+    amount       integer           not null,
+    balance      integer           not null,
+    kind         integer           not null,
+    reference_id uuid,
+    create_date  timestamp         not null
+);
+
+create index bank_ledger_colony_id_currency_entry_id_index
+    on bank_ledger (colony_id, currency, entry_id);
+
+-- Balances from before the ledger existed are carried over as a single entry.
+insert into bank_ledger (colony_id, currency, amount, balance, kind, reference_id, create_date)
+select colony_id, currency, balance, balance, 0, null, now()
+from bank_balances
+where balance != 0;
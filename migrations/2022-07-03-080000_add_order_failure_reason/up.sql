@@ -0,0 +1,2 @@
+alter table orders
+    add failure_reason integer;
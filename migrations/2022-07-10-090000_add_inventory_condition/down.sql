@@ -0,0 +1,5 @@
+drop index if exists inventory_parent_item_code_idx;
+
+alter table inventory
+    drop column if exists parent_item_code,
+    drop column if exists condition;
@@ -0,0 +1,6 @@
+alter table inventory
+    add condition integer default 0 not null,
+    add parent_item_code varchar(32);
+
+create index inventory_parent_item_code_idx
+    on inventory (parent_item_code);
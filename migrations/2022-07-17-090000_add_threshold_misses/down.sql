@@ -0,0 +1 @@
+drop table if exists threshold_misses;
@@ -0,0 +1,12 @@
+create table threshold_misses
+(
+    item_code     varchar(32)    not null,
+    thing_def     text           not null,
+    base_value    numeric(10, 2) not null,
+    condition     integer        not null,
+    missing_price bool           not null,
+    missing_stock bool           not null,
+    found_date    timestamp      not null,
+    constraint threshold_misses_pk
+        primary key (item_code)
+);
@@ -0,0 +1,13 @@
+create table item_blacklist
+(
+    entry_id   serial       not null
+        constraint item_blacklist_pk
+            primary key,
+    item_code  varchar(32),
+    thing_def  varchar(200),
+    stuff      varchar(200),
+    reason     text         not null,
+    date_added timestamp    not null,
+    constraint item_blacklist_has_target
+        check (item_code is not null or thing_def is not null or stuff is not null)
+);
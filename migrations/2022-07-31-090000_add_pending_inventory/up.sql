@@ -0,0 +1,21 @@
+create table pending_inventory
+(
+    version     varchar(32)       not null
+        constraint pending_inventory_pk
+            primary key,
+    item_code   varchar(32)       not null,
+    thing_def   text              not null,
+    quality     integer,
+    minified    boolean           not null,
+    base_value  numeric(10, 2)    not null,
+    stuff       text,
+    weight      numeric(10, 2)    not null,
+    votes       integer           not null,
+    status      integer default 0 not null,
+    date_added  timestamp         not null,
+    review_date timestamp,
+    reviewed_by varchar(100)
+);
+
+create index pending_inventory_status_index
+    on pending_inventory (status, votes);
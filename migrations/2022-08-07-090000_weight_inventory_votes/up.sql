@@ -0,0 +1,62 @@
+drop view if exists summary_inventory_votes;
+
+-- Votes are collapsed to one per account, using the account's best colony.
+-- The weights come from the latest api_config, the defaults here have to match
+-- ApiConfigDataInventoryVoteWeights.
+create view summary_inventory_votes as
+(
+WITH config AS (
+    SELECT config_data -> 'inventory' -> 'vote_weights' AS w
+    FROM api_config
+    ORDER BY version DESC
+    LIMIT 1
+),
+     weights AS (
+         SELECT coalesce((config.w ->> 'steam_account')::numeric, 1.0)                  AS steam_account,
+                coalesce((config.w ->> 'drm_free_account')::numeric, 0.5)               AS drm_free_account,
+                coalesce((config.w ->> 'new_account')::numeric, 0.25)                   AS new_account,
+                greatest(coalesce((config.w ->> 'trusted_account_days')::numeric, 30), 1)   AS trusted_account_days,
+                coalesce((config.w ->> 'new_colony')::numeric, 0.25)                    AS new_colony,
+                greatest(coalesce((config.w ->> 'trusted_colony_ticks')::numeric, 600000), 1) AS trusted_colony_ticks
+         FROM (SELECT 1) AS d
+                  LEFT JOIN config ON true
+     ),
+     colony_votes AS (
+         SELECT nivt.version,
+                a.account_id,
+                CASE
+                    WHEN c.used_dev_mode THEN 0
+                    ELSE (CASE WHEN a.steam_id IS NOT NULL THEN wt.steam_account ELSE wt.drm_free_account END)
+                        * (wt.new_account + (1 - wt.new_account) * least(
+                                extract(epoch FROM timezone('utc', now()) - a.date_added)::numeric / 86400
+                                / wt.trusted_account_days, 1))
+                        * (wt.new_colony + (1 - wt.new_colony) * least(c.tick / wt.trusted_colony_ticks, 1))
+                    END AS weight
+         FROM new_inventory_vote_tracker nivt
+                  INNER JOIN client_binds cb ON cb.client_bind_id = nivt.client_bind_id
+                  INNER JOIN accounts a ON a.account_id = cb.account_fk
+                  INNER JOIN colonies c ON c.colony_id = nivt.colony_id
+                  CROSS JOIN weights wt
+     ),
+     account_votes AS (
+         SELECT version, account_id, max(weight) AS weight
+         FROM colony_votes
+         GROUP BY version, account_id
+     )
+SELECT ni.item_code,
+       ni.thing_def,
+       ni.quality,
+       ni.minified,
+       ni.base_value,
+       ni.stuff,
+       ni.weight,
+       ni.version,
+       v.votes,
+       v.weighted_votes
+FROM new_inventory ni
+         LEFT JOIN (
+    SELECT av.version, count(av.account_id) AS votes, round(sum(av.weight), 2) AS weighted_votes
+    FROM account_votes av
+    GROUP BY av.version
+) AS v ON v.version = ni.version
+    );
@@ -0,0 +1,5 @@
+alter table pending_inventory
+    drop column if exists mod_name;
+
+alter table inventory
+    drop column if exists mod_name;
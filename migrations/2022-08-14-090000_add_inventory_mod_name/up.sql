@@ -0,0 +1,7 @@
+-- The mod an item came from, learned from the colonies that voted for it.
+-- Items without one are offered to every colony.
+alter table inventory
+    add column mod_name text;
+
+alter table pending_inventory
+    add column mod_name text;
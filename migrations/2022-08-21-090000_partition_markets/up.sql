@@ -0,0 +1,201 @@
+-- Every major game version gets a market of its own.
+-- Everything we have so far belongs to whichever version most colonies are on.
+create temporary table legacy_market as
+select coalesce((select substring(game_version from '^(\d+\.\d+)') as market
+                 from colonies
+                 where game_version ~ '^\d+\.\d+'
+                 group by market
+                 order by count(*) desc, market desc
+                 limit 1), '1.3') as market;
+
+drop view if exists summary_inventory_votes;
+
+alter table new_inventory_vote_tracker
+    drop constraint new_inventory_vote_hash_fk;
+
+-- Stock
+alter table inventory
+    add column market varchar(16);
+update inventory
+set market = (select market from legacy_market);
+alter table inventory
+    alter column market set not null;
+alter table inventory
+    drop constraint inventory_pk;
+alter table inventory
+    add constraint inventory_pk
+        primary key (market, item_code);
+drop index if exists inventory_version_uindex;
+create unique index inventory_version_uindex
+    on inventory (market, version);
+
+-- Votes
+alter table new_inventory
+    add column market varchar(16);
+update new_inventory
+set market = (select market from legacy_market);
+alter table new_inventory
+    alter column market set not null;
+alter table new_inventory
+    drop constraint new_inventory_pk;
+alter table new_inventory
+    add constraint new_inventory_pk
+        primary key (market, version);
+
+alter table new_inventory_vote_tracker
+    add column market varchar(16);
+update new_inventory_vote_tracker
+set market = (select market from legacy_market);
+alter table new_inventory_vote_tracker
+    alter column market set not null;
+alter table new_inventory_vote_tracker
+    drop constraint new_inventory_vote_tracker_pk;
+alter table new_inventory_vote_tracker
+    add constraint new_inventory_vote_tracker_pk
+        primary key (market, version, client_bind_id, colony_id);
+alter table new_inventory_vote_tracker
+    add constraint new_inventory_vote_hash_fk
+        foreign key (market, version) references new_inventory (market, version);
+
+alter table pending_inventory
+    add column market varchar(16);
+update pending_inventory
+set market = (select market from legacy_market);
+alter table pending_inventory
+    alter column market set not null;
+alter table pending_inventory
+    drop constraint pending_inventory_pk;
+alter table pending_inventory
+    add constraint pending_inventory_pk
+        primary key (market, version);
+
+-- Prices
+alter table price_tracker
+    add column market varchar(16);
+update price_tracker
+set market = (select market from legacy_market);
+alter table price_tracker
+    alter column market set not null;
+alter table price_tracker
+    drop constraint price_tracker_pk;
+alter table price_tracker
+    add constraint price_tracker_pk
+        primary key (market, item_code, create_date);
+
+alter table trade_statistics
+    add column market varchar(16);
+update trade_statistics
+set market = (select market from legacy_market);
+alter table trade_statistics
+    alter column market set not null;
+alter table trade_statistics
+    drop constraint trade_statistics_pk;
+alter table trade_statistics
+    add constraint trade_statistics_pk
+        primary key (market, item_code, buy, date);
+
+alter table trade_statistics_monthly
+    add column market varchar(16);
+update trade_statistics_monthly
+set market = (select market from legacy_market);
+alter table trade_statistics_monthly
+    alter column market set not null;
+alter table trade_statistics_monthly
+    drop constraint trade_statistics_monthly_pk;
+alter table trade_statistics_monthly
+    add constraint trade_statistics_monthly_pk
+        primary key (market, item_code, buy, date);
+
+-- Rebuilt on every maintenance pass
+truncate table threshold_misses;
+alter table threshold_misses
+    add column market varchar(16) not null;
+alter table threshold_misses
+    drop constraint threshold_misses_pk;
+alter table threshold_misses
+    add constraint threshold_misses_pk
+        primary key (market, item_code);
+
+-- Anything that will put stock back or take it out later
+alter table orders
+    add column market varchar(16);
+update orders
+set market = (select market from legacy_market);
+alter table orders
+    alter column market set not null;
+
+alter table contracts
+    add column market varchar(16);
+update contracts
+set market = (select market from legacy_market);
+alter table contracts
+    alter column market set not null;
+
+alter table backorders
+    add column market varchar(16);
+update backorders
+set market = (select market from legacy_market);
+alter table backorders
+    alter column market set not null;
+
+drop table legacy_market;
+
+create view summary_inventory_votes as
+(
+WITH config AS (
+    SELECT config_data -> 'inventory' -> 'vote_weights' AS w
+    FROM api_config
+    ORDER BY version DESC
+    LIMIT 1
+),
+     weights AS (
+         SELECT coalesce((config.w ->> 'steam_account')::numeric, 1.0)                  AS steam_account,
+                coalesce((config.w ->> 'drm_free_account')::numeric, 0.5)               AS drm_free_account,
+                coalesce((config.w ->> 'new_account')::numeric, 0.25)                   AS new_account,
+                greatest(coalesce((config.w ->> 'trusted_account_days')::numeric, 30), 1)   AS trusted_account_days,
+                coalesce((config.w ->> 'new_colony')::numeric, 0.25)                    AS new_colony,
+                greatest(coalesce((config.w ->> 'trusted_colony_ticks')::numeric, 600000), 1) AS trusted_colony_ticks
+         FROM (SELECT 1) AS d
+                  LEFT JOIN config ON true
+     ),
+     colony_votes AS (
+         SELECT nivt.market,
+                nivt.version,
+                a.account_id,
+                CASE
+                    WHEN c.used_dev_mode THEN 0
+                    ELSE (CASE WHEN a.steam_id IS NOT NULL THEN wt.steam_account ELSE wt.drm_free_account END)
+                        * (wt.new_account + (1 - wt.new_account) * least(
+                                extract(epoch FROM timezone('utc', now()) - a.date_added)::numeric / 86400
+                                / wt.trusted_account_days, 1))
+                        * (wt.new_colony + (1 - wt.new_colony) * least(c.tick / wt.trusted_colony_ticks, 1))
+                    END AS weight
+         FROM new_inventory_vote_tracker nivt
+                  INNER JOIN client_binds cb ON cb.client_bind_id = nivt.client_bind_id
+                  INNER JOIN accounts a ON a.account_id = cb.account_fk
+                  INNER JOIN colonies c ON c.colony_id = nivt.colony_id
+                  CROSS JOIN weights wt
+     ),
+     account_votes AS (
+         SELECT market, version, account_id, max(weight) AS weight
+         FROM colony_votes
+         GROUP BY market, version, account_id
+     )
+SELECT ni.item_code,
+       ni.thing_def,
+       ni.quality,
+       ni.minified,
+       ni.base_value,
+       ni.stuff,
+       ni.weight,
+       ni.version,
+       v.votes,
+       v.weighted_votes,
+       ni.market
+FROM new_inventory ni
+         LEFT JOIN (
+    SELECT av.market, av.version, count(av.account_id) AS votes, round(sum(av.weight), 2) AS weighted_votes
+    FROM account_votes av
+    GROUP BY av.market, av.version
+) AS v ON v.market = ni.market AND v.version = ni.version
+    );
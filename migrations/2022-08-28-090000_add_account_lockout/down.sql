@@ -0,0 +1,6 @@
+alter table accounts
+    drop column if exists last_totp_step;
+alter table accounts
+    drop column if exists locked_until;
+alter table accounts
+    drop column if exists failed_logins;
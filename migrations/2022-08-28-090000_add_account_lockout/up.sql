@@ -0,0 +1,6 @@
+alter table accounts
+    add failed_logins int default 0 not null;
+alter table accounts
+    add locked_until timestamp;
+alter table accounts
+    add last_totp_step bigint;
@@ -0,0 +1,3 @@
+drop index if exists client_binds_account_bind_fk_index;
+alter table client_binds
+    drop column if exists account_bind_fk;
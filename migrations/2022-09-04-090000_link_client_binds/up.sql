@@ -0,0 +1,10 @@
+-- The account bind a client bind was created for, so the game collects that one
+-- and not just the newest for the account.
+alter table client_binds
+    add account_bind_fk uuid
+        constraint client_binds_account_binds_bind_id_fk
+            references account_binds
+            on delete set null;
+
+create index client_binds_account_bind_fk_index
+    on client_binds (account_bind_fk);
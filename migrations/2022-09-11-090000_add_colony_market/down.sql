@@ -0,0 +1,3 @@
+drop index if exists colonies_market_index;
+alter table colonies
+    drop column if exists market;
@@ -0,0 +1,10 @@
+-- The market is worked out from the game version whenever it's sent,
+-- stored so queries don't have to parse the version again.
+alter table colonies
+    add market varchar(16);
+update colonies
+set market = substring(game_version from '^\s*(\d+\.\d+)')
+where game_version ~ '^\s*\d+\.\d+';
+
+create index colonies_market_index
+    on colonies (market);
@@ -0,0 +1,49 @@
+use actix_web::*;
+
+use crate::db::get_pg_connection;
+use crate::db::models::account::Account;
+use crate::structs::blacklist::{
+    add_blacklist_entry, get_blacklist_entries, remove_blacklist_entry, BlacklistEntryRequest,
+};
+
+pub async fn action_list(_account: Account) -> HttpResponse {
+    let conn = &get_pg_connection();
+    match get_blacklist_entries(conn) {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+pub async fn action_insert(
+    account: Account,
+    request: web::Json<BlacklistEntryRequest>,
+) -> HttpResponse {
+    let conn = &get_pg_connection();
+    match add_blacklist_entry(request.into_inner(), conn) {
+        Ok(Some(entry)) => {
+            info!(
+                "Account {} blacklisted {:?}/{:?}/{:?}, {}",
+                account.account_id, entry.item_code, entry.thing_def, entry.stuff, entry.reason
+            );
+            HttpResponse::Created().json(entry)
+        }
+        Ok(None) => HttpResponse::UnprocessableEntity().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+pub async fn action_delete(entry_id: web::Path<i32>, account: Account) -> HttpResponse {
+    let entry_id = entry_id.into_inner();
+    let conn = &get_pg_connection();
+    match remove_blacklist_entry(entry_id, conn) {
+        Ok(0) => HttpResponse::NotFound().finish(),
+        Ok(_) => {
+            info!(
+                "Account {} removed blacklist entry {}",
+                account.account_id, entry_id
+            );
+            HttpResponse::NoContent().finish()
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
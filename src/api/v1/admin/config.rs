@@ -0,0 +1,122 @@
+use actix_web::*;
+use serde_json::Value;
+
+use crate::db::get_pg_connection;
+use crate::db::models::account::Account;
+use crate::jtd::diff::diff;
+use crate::structs::admin::{
+    insert_config_version, list_config_versions, load_config_version, validate_config,
+    ConfigDiffQuery, ConfigDiffResponse, ConfigKind, ConfigVersionList,
+};
+
+pub async fn action_list(kind: web::Path<ConfigKind>, _account: Account) -> HttpResponse {
+    let conn = &get_pg_connection();
+    match list_config_versions(kind.into_inner(), conn) {
+        Ok(versions) => HttpResponse::Ok().json(ConfigVersionList {
+            latest: versions.first().copied(),
+            versions,
+        }),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+pub async fn action_get(path: web::Path<(ConfigKind, i32)>, _account: Account) -> HttpResponse {
+    let (kind, version) = path.into_inner();
+    let conn = &get_pg_connection();
+    match load_config_version(kind, Some(version), conn) {
+        Ok(Some(document)) => HttpResponse::Ok().json(document),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+pub async fn action_validate(
+    kind: web::Path<ConfigKind>,
+    _account: Account,
+    config_data: web::Json<Value>,
+) -> HttpResponse {
+    HttpResponse::Ok().json(validate_config(kind.into_inner(), &config_data))
+}
+
+pub async fn action_insert(
+    kind: web::Path<ConfigKind>,
+    account: Account,
+    config_data: web::Json<Value>,
+) -> HttpResponse {
+    let kind = kind.into_inner();
+    let validation = validate_config(kind, &config_data);
+    if !validation.valid {
+        return HttpResponse::UnprocessableEntity().json(validation);
+    }
+
+    let conn = &get_pg_connection();
+    match insert_config_version(kind, &config_data, conn) {
+        Ok(version) => {
+            info!(
+                "Account {} created {:?} config version {}",
+                account.account_id, kind, version
+            );
+            match load_config_version(kind, Some(version), conn) {
+                Ok(Some(document)) => HttpResponse::Created().json(document),
+                _ => HttpResponse::InternalServerError().finish(),
+            }
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Rolling back copies an old version to a new one, the latest version always wins.
+pub async fn action_rollback(path: web::Path<(ConfigKind, i32)>, account: Account) -> HttpResponse {
+    let (kind, version) = path.into_inner();
+    let conn = &get_pg_connection();
+
+    let document = match load_config_version(kind, Some(version), conn) {
+        Ok(Some(document)) => document,
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+
+    // The schema may have changed since this version was saved.
+    let validation = validate_config(kind, &document.config_data);
+    if !validation.valid {
+        return HttpResponse::UnprocessableEntity().json(validation);
+    }
+
+    match insert_config_version(kind, &document.config_data, conn) {
+        Ok(new_version) => {
+            info!(
+                "Account {} rolled back {:?} config to version {} as version {}",
+                account.account_id, kind, version, new_version
+            );
+            match load_config_version(kind, Some(new_version), conn) {
+                Ok(Some(document)) => HttpResponse::Created().json(document),
+                _ => HttpResponse::InternalServerError().finish(),
+            }
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+pub async fn action_diff(
+    kind: web::Path<ConfigKind>,
+    _account: Account,
+    query: web::Query<ConfigDiffQuery>,
+) -> HttpResponse {
+    let kind = kind.into_inner();
+    let conn = &get_pg_connection();
+
+    let (from, to) = match (
+        load_config_version(kind, Some(query.from), conn),
+        load_config_version(kind, query.to, conn),
+    ) {
+        (Ok(Some(from)), Ok(Some(to))) => (from, to),
+        (Ok(_), Ok(_)) => return HttpResponse::NotFound().finish(),
+        _ => return HttpResponse::InternalServerError().finish(),
+    };
+
+    HttpResponse::Ok().json(ConfigDiffResponse {
+        from: from.version,
+        to: to.version,
+        changes: diff(&from.config_data, &to.config_data),
+    })
+}
@@ -0,0 +1,42 @@
+use actix_web::*;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::db::get_pg_connection;
+use crate::db::models::account::Account;
+use crate::db::models::maintenance::{Maintenance, MaintenanceReport};
+use crate::structs::threshold_miss::get_threshold_misses;
+
+#[derive(Serialize)]
+pub struct MaintenanceStatus {
+    pub in_progress: bool,
+    pub node_name: Option<String>,
+    pub start_time: Option<NaiveDateTime>,
+    pub execution_time: Option<NaiveDateTime>,
+    pub report: Option<MaintenanceReport>,
+}
+
+pub async fn action_status(_account: Account) -> HttpResponse {
+    use crate::db::schema::maintenance as schema;
+    let conn = &get_pg_connection();
+    match schema::table.first::<Maintenance>(conn).optional() {
+        Ok(Some(m)) => HttpResponse::Ok().json(MaintenanceStatus {
+            in_progress: m.in_progress,
+            node_name: m.node_name,
+            start_time: m.start_time,
+            execution_time: m.execution_time,
+            report: m.report,
+        }),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Items the last maintenance run couldn't price or restock
+pub async fn action_threshold_misses(_account: Account) -> HttpResponse {
+    let conn = &get_pg_connection();
+    match get_threshold_misses(conn) {
+        Ok(misses) => HttpResponse::Ok().json(misses),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
@@ -0,0 +1,56 @@
+use actix_web::{web, Scope};
+
+use crate::structs::account::AdminGuard;
+
+mod blacklist;
+mod config;
+mod maintenance;
+mod moderation;
+mod stats;
+
+/// Everything here is served even while the API is forced offline,
+/// see `ADMIN_PATH_PREFIX`.
+pub fn config() -> Scope {
+    web::scope("/admin")
+        .guard(AdminGuard())
+        .route("/config/{kind}/", web::get().to(config::action_list))
+        .route("/config/{kind}/", web::post().to(config::action_insert))
+        .route(
+            "/config/{kind}/validate",
+            web::post().to(config::action_validate),
+        )
+        .route("/config/{kind}/diff", web::get().to(config::action_diff))
+        .route(
+            "/config/{kind}/{version}",
+            web::get().to(config::action_get),
+        )
+        .route(
+            "/config/{kind}/{version}/rollback",
+            web::post().to(config::action_rollback),
+        )
+        .route("/maintenance", web::get().to(maintenance::action_status))
+        .route(
+            "/maintenance/threshold-misses",
+            web::get().to(maintenance::action_threshold_misses),
+        )
+        .route("/blacklist", web::get().to(blacklist::action_list))
+        .route("/blacklist", web::post().to(blacklist::action_insert))
+        .route(
+            "/blacklist/{entry_id}",
+            web::delete().to(blacklist::action_delete),
+        )
+        .route("/moderation", web::get().to(moderation::action_list))
+        .route(
+            "/moderation/{market}/{version}",
+            web::patch().to(moderation::action_set_base_value),
+        )
+        .route(
+            "/moderation/{market}/{version}/approve",
+            web::post().to(moderation::action_approve),
+        )
+        .route(
+            "/moderation/{market}/{version}/reject",
+            web::post().to(moderation::action_reject),
+        )
+        .route("/stats/mods", web::get().to(stats::action_mods))
+}
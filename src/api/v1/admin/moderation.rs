@@ -0,0 +1,83 @@
+use actix_web::*;
+use bigdecimal::BigDecimal;
+
+use crate::db::get_pg_connection;
+use crate::db::models::account::Account;
+use crate::packets::inventory::ModerationStatusEnum;
+use crate::structs::pending_inventory::{
+    approve_pending_inventory, get_pending_inventory, reject_pending_inventory,
+    set_pending_base_value,
+};
+
+#[derive(Deserialize)]
+pub struct ModerationQuery {
+    /// Pending if not given
+    pub status: Option<ModerationStatusEnum>,
+}
+
+#[derive(Deserialize)]
+pub struct BaseValueUpdate {
+    pub base_value: f32,
+}
+
+fn reviewer(account: &Account) -> String {
+    format!("account:{}", account.account_id)
+}
+
+pub async fn action_list(query: web::Query<ModerationQuery>, _account: Account) -> HttpResponse {
+    let conn = &get_pg_connection();
+    let status = query.status.unwrap_or(ModerationStatusEnum::Pending);
+    match get_pending_inventory(status, conn) {
+        Ok(items) => HttpResponse::Ok().json(items),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+pub async fn action_set_base_value(
+    path: web::Path<(String, String)>,
+    _account: Account,
+    update: web::Json<BaseValueUpdate>,
+) -> HttpResponse {
+    if !(update.base_value > 0f32) {
+        return HttpResponse::UnprocessableEntity().finish();
+    }
+    let (market, version) = path.into_inner();
+    let conn = &get_pg_connection();
+    match set_pending_base_value(&market, &version, BigDecimal::from(update.base_value), conn) {
+        Ok(Some(item)) => HttpResponse::Ok().json(item),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+pub async fn action_approve(path: web::Path<(String, String)>, account: Account) -> HttpResponse {
+    let (market, version) = path.into_inner();
+    let conn = &get_pg_connection();
+    match approve_pending_inventory(&market, &version, &reviewer(&account), conn) {
+        Ok(Some(item)) => {
+            info!(
+                "Account {} approved {} ({}) for {}",
+                account.account_id, item.thing_def, item.item_code, item.market
+            );
+            HttpResponse::Ok().json(item)
+        }
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+pub async fn action_reject(path: web::Path<(String, String)>, account: Account) -> HttpResponse {
+    let (market, version) = path.into_inner();
+    let conn = &get_pg_connection();
+    match reject_pending_inventory(&market, &version, &reviewer(&account), conn) {
+        Ok(Some(item)) => {
+            info!(
+                "Account {} rejected {} ({}) for {}",
+                account.account_id, item.thing_def, item.item_code, item.market
+            );
+            HttpResponse::Ok().json(item)
+        }
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
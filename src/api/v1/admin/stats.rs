@@ -0,0 +1,14 @@
+use actix_web::*;
+
+use crate::db::get_pg_connection;
+use crate::db::models::account::Account;
+use crate::structs::colony_mods::get_mod_statistics;
+
+/// How many colonies have each mod loaded, most popular first
+pub async fn action_mods(_account: Account) -> HttpResponse {
+    let conn = &get_pg_connection();
+    match get_mod_statistics(conn) {
+        Ok(stats) => HttpResponse::Ok().json(stats),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
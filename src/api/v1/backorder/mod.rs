@@ -1 +1,132 @@
+use crate::request_helpers::*;
+use actix_web::*;
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use diesel::prelude::*;
+use itertools::Itertools;
 
+use crate::crypto::parse_uuid;
+use crate::db::get_pg_connection;
+use crate::db::models::backorder::Backorder;
+use crate::db::models::bind::ClientBind;
+use crate::packets::backorder::{
+    BackorderCancelReply, BackorderCancelRequest, BackorderData, BackorderListReply,
+    BackorderListRequest, BackorderStatusEnum,
+};
+use crate::packets::bank::BankLedgerKindEnum;
+use crate::structs::backorder::get_backorders_for_colony;
+use crate::structs::bank_balance::get_bank_balance;
+use crate::structs::bank_ledger::apply_ledger_entry;
+use crate::structs::binds::ClientIdGuard;
+use crate::structs::colony::validate_ownership_and_fetch;
+use crate::structs::general::DbPkLoadable;
+
+pub fn config() -> Scope {
+    web::scope("/backorder")
+        .guard(guard::Header("content-type", "application/protobuf"))
+        .guard(ClientIdGuard())
+        .route("/", web::post().to(action_list))
+        .route("/cancel", web::post().to(action_cancel))
+}
+
+pub async fn action_list(
+    _req: HttpRequest,
+    bind: ClientBind,
+    packet: ProtoBuf<BackorderListRequest>,
+) -> Result<HttpResponse> {
+    let colony = match validate_ownership_and_fetch(None, Some(&packet.colony_id), &bind) {
+        None => {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+        Some(value) => value,
+    };
+
+    let conn = &get_pg_connection();
+    HttpResponse::Ok().protobuf(BackorderListReply {
+        backorders: get_backorders_for_colony(colony.colony_id, packet.any, conn)
+            .into_iter()
+            .map_into::<BackorderData>()
+            .collect_vec(),
+    })
+}
+
+pub async fn action_cancel(
+    _req: HttpRequest,
+    bind: ClientBind,
+    packet: ProtoBuf<BackorderCancelRequest>,
+) -> Result<HttpResponse> {
+    let colony = match validate_ownership_and_fetch(None, Some(&packet.colony_id), &bind) {
+        None => {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+        Some(value) => value,
+    };
+
+    let backorder_id = match parse_uuid(&*packet.backorder_id) {
+        Ok(uuid) => uuid,
+        Err(_) => return Ok(HttpResponse::BadRequest().finish()),
+    };
+
+    let mut backorder = match Backorder::load_pk(&backorder_id) {
+        Ok(b) if b.colony_id == colony.colony_id => b,
+        _ => return Ok(HttpResponse::BadRequest().finish()),
+    };
+
+    let conn = &get_pg_connection();
+    match conn
+        .build_transaction()
+        .read_committed()
+        .run::<_, diesel::result::Error, _>(|| {
+            // Only flip it if it's still pending, it may have been filled
+            // by maintenance since we loaded it.
+            backorder.status = BackorderStatusEnum::Cancelled.into();
+            backorder.update_date = Utc::now().naive_utc();
+            let updated = diesel::update(
+                crate::db::schema::backorders::table
+                    .filter(crate::db::schema::backorders::backorder_id.eq(backorder_id))
+                    .filter(
+                        crate::db::schema::backorders::status
+                            .eq(i32::from(BackorderStatusEnum::Pending)),
+                    ),
+            )
+            .set(&backorder)
+            .execute(conn)?;
+
+            if updated != 1 {
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+
+            let mut bank_balance = match get_bank_balance(colony.colony_id, backorder.currency, conn)
+            {
+                Err(_) => {
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+                Ok(v) => v,
+            };
+
+            if apply_ledger_entry(
+                &mut bank_balance,
+                backorder.paid,
+                BankLedgerKindEnum::Refund,
+                Some(backorder.backorder_id),
+                conn,
+            )
+            .is_err()
+            {
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+
+            Ok(bank_balance.balance)
+        }) {
+        Ok(balance) => HttpResponse::Ok().protobuf(BackorderCancelReply {
+            success: true,
+            refunded: backorder.paid,
+            balance,
+        }),
+        Err(_) => HttpResponse::Ok().protobuf(BackorderCancelReply {
+            success: false,
+            refunded: 0,
+            balance: 0,
+        }),
+    }
+}
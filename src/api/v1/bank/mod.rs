@@ -6,20 +6,40 @@ use actix_web::{web, HttpResponse};
 use diesel::prelude::*;
 use strum::IntoEnumIterator;
 
+use crate::crypto::{generate_v4_uuid, parse_uuid};
 use crate::db::get_pg_connection;
-use crate::db::models::bank::BankBalance;
+use crate::db::models::bank::BankTransfer;
 use crate::db::models::bind::ClientBind;
-use crate::packets::bank::{BankDataReply, BankGetRequest, BankWithdrawReply, BankWithdrawRequest};
+use crate::db::models::colony::Colony;
+use crate::packets::bank::{
+    BankDataReply, BankExchangeReply, BankExchangeRequest, BankGetRequest, BankHistoryReply,
+    BankHistoryRequest, BankLedgerEntryData, BankLedgerKindEnum, BankTransferCancelReply,
+    BankTransferCancelRequest, BankTransferConfirmReply, BankTransferConfirmRequest,
+    BankTransferData, BankTransferListReply, BankTransferListRequest, BankTransferReply,
+    BankTransferRequest, BankWithdrawReply, BankWithdrawRequest,
+};
 use crate::packets::common::CurrencyEnum;
 use crate::packets::order::{OrderItem, OrderRequestStatus, OrderStatusReply};
+use crate::structs::api_config::LockedApiConfig;
 use crate::structs::bank_balance::get_bank_balance;
+use crate::structs::bank_ledger::{apply_ledger_entry, get_ledger_for_colony};
+use crate::structs::bank_transfer::{
+    can_receive_transfers, cancel_transfer, confirm_transfer, create_transfer,
+    get_transfers_for_colony, same_account,
+};
 use crate::structs::binds::ClientIdGuard;
 use crate::structs::colony::validate_ownership_and_fetch;
-use crate::structs::inventory::SILVER_ITEM;
+use crate::structs::currency::{
+    currency_item, get_exchange_rate, get_exchange_rates, stock_currency_item,
+};
+use crate::structs::general::DbPkLoadable;
+use crate::structs::market::is_market_live;
 use crate::structs::order;
 use crate::structs::order::{OrderManifest, OrderStats};
 use crate::traits::numerical::CanRound;
-use bigdecimal::BigDecimal;
+use actix_web::web::Data;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use itertools::Itertools;
 use std::convert::TryFrom;
 
 pub fn config() -> Scope {
@@ -31,10 +51,16 @@ pub fn config() -> Scope {
         .guard(ClientIdGuard())
         .route("/", web::post().to(action_get))
         .route("/withdraw", web::post().to(action_withdraw))
+        .route("/exchange", web::post().to(action_exchange))
+        .route("/history", web::post().to(action_history))
+        .route("/transfer", web::post().to(action_transfer))
+        .route("/transfer/confirm", web::post().to(action_transfer_confirm))
+        .route("/transfer/cancel", web::post().to(action_transfer_cancel))
+        .route("/transfer/list", web::post().to(action_transfer_list))
 }
 
 pub async fn action_get(
-    _req: HttpRequest,
+    req: HttpRequest,
     bind: ClientBind,
     packet: ProtoBuf<BankGetRequest>,
 ) -> Result<HttpResponse> {
@@ -57,11 +83,19 @@ pub async fn action_get(
             }
         };
     }
-    HttpResponse::Ok().protobuf(BankDataReply { balance: bank_data })
+    let exchange_rates = {
+        let lock = req.app_data::<Data<LockedApiConfig>>().unwrap().read();
+        let config = lock.as_ref().unwrap();
+        get_exchange_rates(&config.config_data.currency)
+    };
+    HttpResponse::Ok().protobuf(BankDataReply {
+        balance: bank_data,
+        exchange_rates,
+    })
 }
 
 pub async fn action_withdraw(
-    _req: HttpRequest,
+    req: HttpRequest,
     bind: ClientBind,
     packet: ProtoBuf<BankWithdrawRequest>,
 ) -> Result<HttpResponse> {
@@ -72,7 +106,33 @@ pub async fn action_withdraw(
         Some(value) => value,
     };
 
+    // The currency is delivered from the colony's market like any other order
+    let market = match colony.market() {
+        Some(m) => m,
+        None => return Ok(HttpResponse::BadRequest().finish()),
+    };
+
+    let live = {
+        let lock = req.app_data::<Data<LockedApiConfig>>().unwrap().read();
+        let config = lock.as_ref().unwrap();
+        is_market_live(&config.config_data.markets, &market)
+    };
+    if !live {
+        return Ok(HttpResponse::UnprocessableEntity().finish());
+    }
+
+    // Each currency is paid out as its own item
+    let currency = match CurrencyEnum::try_from(packet.currency) {
+        Ok(currency) => currency,
+        Err(_) => return Ok(HttpResponse::BadRequest().finish()),
+    };
+    let item = currency_item(currency);
+
     let conn = &get_pg_connection();
+    if stock_currency_item(currency, &market, conn).is_err() {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+
     match conn
         .build_transaction()
         .read_committed()
@@ -84,9 +144,17 @@ pub async fn action_withdraw(
                 }
             };
 
+            let order_id = generate_v4_uuid();
             if packet.amount > 0 && packet.amount <= bank_balance.balance {
-                bank_balance.balance -= packet.amount;
-                if bank_balance.save_changes::<BankBalance>(&**conn).is_err() {
+                if apply_ledger_entry(
+                    &mut bank_balance,
+                    packet.amount * -1,
+                    BankLedgerKindEnum::Withdrawal,
+                    Some(order_id),
+                    conn,
+                )
+                .is_err()
+                {
                     return Err(diesel::result::Error::RollbackTransaction);
                 }
             } else {
@@ -94,29 +162,31 @@ pub async fn action_withdraw(
             };
 
             let mut order_stats = OrderStats::default();
-            let silver = &SILVER_ITEM;
-            let mut oi_silver = OrderItem {
-                item_code: silver.item_code.clone(),
+            let mut oi_currency = OrderItem {
+                item_code: item.item_code.clone(),
                 quantity: 0,
                 health: 100f32,
             };
 
-            let silver_amount: BigDecimal = packet.amount.into();
-            order_stats.total_buy_cost += &silver_amount;
+            let currency_amount: BigDecimal = packet.amount.into();
+            order_stats.total_buy_cost += &currency_amount;
             order_stats.total_buy_cost = order_stats.total_buy_cost.round_2dp();
-            order_stats.total_buy_weight += (&silver.weight * &silver_amount).round_2dp();
-            oi_silver.quantity = packet.amount;
+            order_stats.total_buy_item_cost = Some(order_stats.total_buy_cost.clone());
+            order_stats.total_buy_weight += (&item.weight * &currency_amount).round_2dp();
+            oi_currency.quantity = packet.amount;
 
             let manifest = OrderManifest {
                 wts: vec![],
-                wtb: vec![oi_silver],
+                wtb: vec![oi_currency],
                 balance_adjustment: packet.amount * -1,
                 currency: CurrencyEnum::try_from(bank_balance.currency).unwrap(),
             };
 
             let order = match order::create_order(
+                order_id,
                 &order_stats,
                 &colony,
+                &market,
                 manifest,
                 colony.tick,
                 Some(colony.tick),
@@ -147,3 +217,287 @@ pub async fn action_withdraw(
         }),
     }
 }
+
+pub async fn action_exchange(
+    req: HttpRequest,
+    bind: ClientBind,
+    packet: ProtoBuf<BankExchangeRequest>,
+) -> Result<HttpResponse> {
+    let colony = match validate_ownership_and_fetch(None, Some(&packet.colony_id), &bind) {
+        None => {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+        Some(value) => value,
+    };
+
+    let (from_currency, to_currency) = match (
+        CurrencyEnum::try_from(packet.from_currency),
+        CurrencyEnum::try_from(packet.to_currency),
+    ) {
+        (Ok(from), Ok(to)) if from != to && packet.amount > 0 => (from, to),
+        _ => return Ok(HttpResponse::BadRequest().finish()),
+    };
+
+    let (from_rate, to_rate, fee) = {
+        let lock = req.app_data::<Data<LockedApiConfig>>().unwrap().read();
+        let config = &lock.as_ref().unwrap().config_data.currency;
+        (
+            get_exchange_rate(config, from_currency),
+            get_exchange_rate(config, to_currency),
+            BigDecimal::from(config.exchange_fee_pct.max(0f32).min(1f32)),
+        )
+    };
+    let (from_rate, to_rate) = match (from_rate, to_rate) {
+        (Some(f), Some(t)) => (f, t),
+        _ => return Ok(HttpResponse::BadRequest().finish()),
+    };
+
+    // Whatever is left over after rounding down is kept as part of the fee.
+    let amount = BigDecimal::from(packet.amount);
+    let received = match (&amount * &from_rate * (BigDecimal::from(1) - fee) / &to_rate)
+        .with_scale(0)
+        .to_i32()
+    {
+        Some(r) if r > 0 => r,
+        _ => return Ok(HttpResponse::BadRequest().finish()),
+    };
+
+    let conn = &get_pg_connection();
+    match conn
+        .build_transaction()
+        .read_committed()
+        .run::<_, diesel::result::Error, _>(|| {
+            let mut from_balance =
+                get_bank_balance(colony.colony_id, from_currency.into(), conn)
+                    .map_err(|_| diesel::result::Error::RollbackTransaction)?;
+            let mut to_balance = get_bank_balance(colony.colony_id, to_currency.into(), conn)
+                .map_err(|_| diesel::result::Error::RollbackTransaction)?;
+
+            if packet.amount > from_balance.balance {
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+
+            // Both halves share a reference so they can be matched up
+            let exchange_id = Some(generate_v4_uuid());
+            apply_ledger_entry(
+                &mut from_balance,
+                packet.amount * -1,
+                BankLedgerKindEnum::Exchange,
+                exchange_id,
+                conn,
+            )
+            .map_err(|_| diesel::result::Error::RollbackTransaction)?;
+            apply_ledger_entry(
+                &mut to_balance,
+                received,
+                BankLedgerKindEnum::Exchange,
+                exchange_id,
+                conn,
+            )
+            .map_err(|_| diesel::result::Error::RollbackTransaction)?;
+
+            let mut balance = HashMap::<i32, i32>::new();
+            balance.insert(from_balance.currency, from_balance.balance);
+            balance.insert(to_balance.currency, to_balance.balance);
+            Ok(balance)
+        }) {
+        Ok(balance) => HttpResponse::Ok().protobuf(BankExchangeReply {
+            success: true,
+            received,
+            balance,
+        }),
+        Err(_) => HttpResponse::Ok().protobuf(BankExchangeReply {
+            success: false,
+            received: 0,
+            balance: HashMap::new(),
+        }),
+    }
+}
+
+/// Move money to another colony, it's paid in immediately if the colony is on
+/// the same account, otherwise the recipient has to accept it first.
+pub async fn action_transfer(
+    req: HttpRequest,
+    bind: ClientBind,
+    packet: ProtoBuf<BankTransferRequest>,
+) -> Result<HttpResponse> {
+    let colony = match validate_ownership_and_fetch(None, Some(&packet.colony_id), &bind) {
+        None => {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+        Some(value) => value,
+    };
+
+    let recipient = match parse_uuid(&*packet.to_colony_id).map(|id| Colony::load_pk(&id)) {
+        Ok(Ok(c)) => c,
+        _ => return Ok(HttpResponse::BadRequest().finish()),
+    };
+
+    // Don't leave money sitting with a colony nobody is going to play again
+    let stale_colony_days = {
+        let lock = req.app_data::<Data<LockedApiConfig>>().unwrap().read();
+        let config = lock.as_ref().unwrap();
+        config.config_data.orders.stale_colony_days
+    };
+    if !can_receive_transfers(&recipient, stale_colony_days) {
+        return Ok(HttpResponse::UnprocessableEntity().finish());
+    }
+
+    let confirmed = same_account(&colony, &recipient);
+
+    let conn = &get_pg_connection();
+    match conn
+        .build_transaction()
+        .read_committed()
+        .run::<_, diesel::result::Error, _>(|| {
+            create_transfer(
+                &colony,
+                &recipient,
+                packet.currency,
+                packet.amount,
+                confirmed,
+                conn,
+            )
+            .map_err(|_| diesel::result::Error::RollbackTransaction)
+        }) {
+        Ok((transfer, balance)) => HttpResponse::Ok().protobuf(BankTransferReply {
+            success: true,
+            data: Some(transfer.into()),
+            balance,
+        }),
+        Err(_) => HttpResponse::Ok().protobuf(BankTransferReply {
+            success: false,
+            data: None,
+            balance: 0,
+        }),
+    }
+}
+
+pub async fn action_transfer_confirm(
+    _req: HttpRequest,
+    bind: ClientBind,
+    packet: ProtoBuf<BankTransferConfirmRequest>,
+) -> Result<HttpResponse> {
+    let colony = match validate_ownership_and_fetch(None, Some(&packet.colony_id), &bind) {
+        None => {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+        Some(value) => value,
+    };
+
+    // Only the recipient gets to decide
+    let mut transfer = match parse_uuid(&*packet.transfer_id).map(|id| BankTransfer::load_pk(&id))
+    {
+        Ok(Ok(t)) if t.to_colony_id == colony.colony_id => t,
+        _ => return Ok(HttpResponse::BadRequest().finish()),
+    };
+
+    let conn = &get_pg_connection();
+    match conn
+        .build_transaction()
+        .read_committed()
+        .run::<_, diesel::result::Error, _>(|| {
+            confirm_transfer(&mut transfer, packet.accept, conn)
+                .map_err(|_| diesel::result::Error::RollbackTransaction)
+        }) {
+        Ok(balance) => HttpResponse::Ok().protobuf(BankTransferConfirmReply {
+            success: true,
+            balance,
+        }),
+        Err(_) => HttpResponse::Ok().protobuf(BankTransferConfirmReply {
+            success: false,
+            balance: 0,
+        }),
+    }
+}
+
+/// The sender can take the money back as long as the recipient hasn't accepted it yet
+pub async fn action_transfer_cancel(
+    _req: HttpRequest,
+    bind: ClientBind,
+    packet: ProtoBuf<BankTransferCancelRequest>,
+) -> Result<HttpResponse> {
+    let colony = match validate_ownership_and_fetch(None, Some(&packet.colony_id), &bind) {
+        None => {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+        Some(value) => value,
+    };
+
+    let mut transfer = match parse_uuid(&*packet.transfer_id).map(|id| BankTransfer::load_pk(&id))
+    {
+        Ok(Ok(t)) if t.from_colony_id == colony.colony_id => t,
+        _ => return Ok(HttpResponse::BadRequest().finish()),
+    };
+
+    let conn = &get_pg_connection();
+    match conn
+        .build_transaction()
+        .read_committed()
+        .run::<_, diesel::result::Error, _>(|| {
+            cancel_transfer(&mut transfer, conn)
+                .map_err(|_| diesel::result::Error::RollbackTransaction)
+        }) {
+        Ok(balance) => HttpResponse::Ok().protobuf(BankTransferCancelReply {
+            success: true,
+            balance,
+        }),
+        Err(_) => HttpResponse::Ok().protobuf(BankTransferCancelReply {
+            success: false,
+            balance: 0,
+        }),
+    }
+}
+
+pub async fn action_transfer_list(
+    _req: HttpRequest,
+    bind: ClientBind,
+    packet: ProtoBuf<BankTransferListRequest>,
+) -> Result<HttpResponse> {
+    let colony = match validate_ownership_and_fetch(None, Some(&packet.colony_id), &bind) {
+        None => {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+        Some(value) => value,
+    };
+
+    let conn = &get_pg_connection();
+    HttpResponse::Ok().protobuf(BankTransferListReply {
+        transfers: get_transfers_for_colony(colony.colony_id, packet.any, conn)
+            .into_iter()
+            .map_into::<BankTransferData>()
+            .collect_vec(),
+    })
+}
+
+/// Ledger entries for one of the colony's balances, newest first
+pub async fn action_history(
+    _req: HttpRequest,
+    bind: ClientBind,
+    packet: ProtoBuf<BankHistoryRequest>,
+) -> Result<HttpResponse> {
+    let colony = match validate_ownership_and_fetch(None, Some(&packet.colony_id), &bind) {
+        None => {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+        Some(value) => value,
+    };
+
+    if CurrencyEnum::try_from(packet.currency).is_err() {
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+
+    let conn = &get_pg_connection();
+    HttpResponse::Ok().protobuf(BankHistoryReply {
+        entries: get_ledger_for_colony(
+            colony.colony_id,
+            packet.currency,
+            packet.before_entry_id,
+            packet.limit as i64,
+            conn,
+        )
+        .into_iter()
+        .map_into::<BankLedgerEntryData>()
+        .collect_vec(),
+    })
+}
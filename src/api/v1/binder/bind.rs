@@ -50,8 +50,9 @@ pub async fn action_bind_request(proto_msg: ProtoBuf<BindRequest>) -> Result<Htt
         //
         // DRM-Free Processing
         //
-        // Generate a Bind ID and send it, the client will then kick them to the Web UI to complete
-        // OAUTH or Steam sign in, create the account and verify the bind ID
+        // Generate a Bind ID and send it, the player then logs in to their account
+        // and claims it via /player/{player_id}/bind, the game collects the Client Bind ID
+        // when it next polls bind_confirm.
         if let Ok(bind) = generate_account_bind_id(None) {
             HttpResponse::Ok().protobuf(BindReply {
                 bind_id: bind.bind_id.to_string(),
@@ -51,14 +51,15 @@ pub async fn action_confirm_bind(proto_msg: ProtoBuf<ConfirmBindRequest>) -> Res
                         bind_data.confirm();
                     }
                 } else {
-                    // Non-steam bind handling, has it been confirmed by the Web UI?
-                    if bind_data.confirmed && bind_data.account_fk.is_some() {
+                    // Non-steam bind handling, has it been claimed by a logged in account?
+                    if bind_data.account_fk.is_some() {
                         let client_bind_data = get_non_steam_client_bind_id(&bind_data);
                         if client_bind_data.is_ok() {
                             client_bind_id = client_bind_data.unwrap().client_bind_id.to_string();
+                            bind_data.confirm();
                         } else {
-                            // Bind was marked as confirmed but there was no client bind ID set,
-                            // Something went horribly wrong (most likely Web UI side)
+                            // Bind was claimed but there was no client bind ID set,
+                            // Something went horribly wrong
                             return bad_bind_response;
                         }
                     }
@@ -10,6 +10,7 @@ use crate::db::schema::colonies as schema;
 use crate::db::{get_pg_connection, insert_db_object};
 
 use crate::packets::colony::{ColonyCreateRequest, ColonyData, PlatformEnum};
+use crate::structs::market::market_for_game_version;
 use uuid::Uuid;
 
 pub async fn action_create(
@@ -20,7 +21,7 @@ pub async fn action_create(
     if let Some(mut incoming) = request.0.data {
         if incoming.name.is_empty()
             || incoming.faction_name.is_empty()
-            || incoming.game_version.is_empty()
+            || market_for_game_version(&incoming.game_version).is_none()
             || incoming.tick <= 0
             || incoming.map_id < 0
             || PlatformEnum::try_from(incoming.platform).is_err()
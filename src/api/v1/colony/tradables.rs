@@ -4,13 +4,16 @@ use actix_web::{HttpRequest, HttpResponse, Result};
 use diesel::prelude::*;
 use uuid::Uuid;
 
+use crate::cache::blacklist::get_blacklist;
+use crate::cache::inventory::invalidate_colony_tradables;
 use crate::db::models::bind::ClientBind;
 use crate::db::{get_pg_connection, Ppc};
 
+use crate::structs::blacklist::Blacklist;
 use crate::structs::colony::validate_ownership_and_fetch;
 
 use crate::packets::colony::ColonyTradableSetRequest;
-use crate::traits::item::{HasItemCode, HasThingDef};
+use crate::traits::item::{HasItemCode, HasStuff, HasThingDef};
 
 use crate::db::models::inventory_staging::ColonyInventoryStaging;
 use diesel::dsl::sql;
@@ -27,7 +30,21 @@ pub async fn action_post(
     if let Some(colony) = validate_ownership_and_fetch(None, Some(&packet.0.colony_id), &bind) {
         let mut incoming = packet.0;
 
-        // TODO: Load blacklisted ThingDefs
+        // Votes are taken for markets that aren't live yet, so they have stock when they open.
+        let market = match colony.market() {
+            Some(m) => m,
+            None => return Ok(HttpResponse::BadRequest().finish()),
+        };
+
+        let blacklist = match get_blacklist(&get_pg_connection()) {
+            Ok(b) => b,
+            Err(e) => {
+                error!("Unable to load the item blacklist, {}", e);
+                return Ok(HttpResponse::ServiceUnavailable()
+                    .insert_header(("retry-after", "5"))
+                    .finish());
+            }
+        };
 
         // Map incoming data to rows
         let timer = Timer::new();
@@ -35,7 +52,7 @@ pub async fn action_post(
             .item
             .drain(..)
             .map(|item| ColonyInventoryStaging::from_with_uuid(item, colony.colony_id))
-            .filter(|item| filter_bad_things(item))
+            .filter(|item| filter_bad_things(item, &blacklist))
             .collect();
         debug!("Filter/Transform to inventory done, took {}", timer.took());
 
@@ -44,7 +61,19 @@ pub async fn action_post(
         let client_bind_id = colony.client_bind_fk.clone();
         let colony_id = colony.colony_id.clone();
 
-        if upsert_new_inventory(new_inventory, client_bind_id, colony_id, merge_tradables).is_ok() {
+        if upsert_new_inventory(
+            new_inventory,
+            client_bind_id,
+            colony_id,
+            market,
+            merge_tradables,
+        )
+        .is_ok()
+        {
+            // The tradables list is rewritten with the final packet.
+            if merge_tradables {
+                invalidate_colony_tradables(&colony_id);
+            }
             Ok(HttpResponse::Ok().finish())
         } else {
             // Deal with the fact a big insert might be blocked by running transactions
@@ -62,19 +91,19 @@ pub async fn action_post(
     }
 }
 
-// TODO: Add Database-backed blacklist
-fn filter_bad_things<T>(ct: T) -> bool
+fn filter_bad_things<T>(ct: T, blacklist: &Blacklist) -> bool
     where
-        T: HasItemCode + HasThingDef,
+        T: HasItemCode + HasThingDef + HasStuff,
 {
-    use crate::structs::inventory::SILVER_ITEM;
-    *ct.get_item_code() != SILVER_ITEM.item_code
+    use crate::structs::currency::is_currency_item;
+    !is_currency_item(ct.get_item_code()) && !blacklist.contains(&ct)
 }
 
 fn upsert_new_inventory(
     mut to_create: Vec<ColonyInventoryStaging>,
     client_id: Uuid,
     colony_id: Uuid,
+    market: String,
     merge: bool,
 ) -> Result<(), ()> {
     use crate::db::schema::colony_inventory_staging as cis_schema;
@@ -144,6 +173,7 @@ fn upsert_new_inventory(
                     cis_schema::stuff,
                     cis_schema::weight,
                     sql_now,
+                    sql("").bind::<diesel::sql_types::Text, _>(market.clone()),
                 ))
                 .filter(cis_schema::colony_id.eq(colony_id));
 
@@ -170,8 +200,11 @@ fn upsert_new_inventory(
             if let Err(e) = diesel::delete(cis_schema::table)
                 .filter(cis_schema::colony_id.eq(colony_id))
                 .filter(
-                    cis_schema::version
-                        .eq_any(inventory_schema::table.select(inventory_schema::version)),
+                    cis_schema::version.eq_any(
+                        inventory_schema::table
+                            .filter(inventory_schema::market.eq(&market))
+                            .select(inventory_schema::version),
+                    ),
                 )
                 .execute(conn)
             {
@@ -192,8 +225,9 @@ fn upsert_new_inventory(
                     new_inventory_schema::stuff,
                     new_inventory_schema::weight,
                     new_inventory_schema::date_added,
+                    new_inventory_schema::market,
                 ))
-                .on_conflict(new_inventory_schema::version)
+                .on_conflict((new_inventory_schema::market, new_inventory_schema::version))
                 .do_nothing()
                 .execute(conn)
             {
@@ -209,6 +243,7 @@ fn upsert_new_inventory(
                     sql("").bind::<diesel::sql_types::Uuid, _>(client_id),
                     cis_schema::version,
                     cis_schema::colony_id,
+                    sql("").bind::<diesel::sql_types::Text, _>(market.clone()),
                 ))
                 .filter(cis_schema::colony_id.eq(colony_id));
 
@@ -218,10 +253,12 @@ fn upsert_new_inventory(
                     vote_schema::client_bind_id,
                     vote_schema::version,
                     vote_schema::colony_id,
+                    vote_schema::market,
                 ))
                 .on_conflict((
-                    vote_schema::client_bind_id,
+                    vote_schema::market,
                     vote_schema::version,
+                    vote_schema::client_bind_id,
                     vote_schema::colony_id,
                 ))
                 .do_nothing()
@@ -6,11 +6,13 @@ use actix_web::*;
 use chrono::Utc;
 use diesel::SaveChangesDsl;
 
+use crate::cache::invalidate_colony;
 use crate::db::get_pg_connection;
 use crate::db::models::bind::ClientBind;
 use crate::db::models::colony::Colony;
 use crate::packets::colony::{ColonyData, ColonyUpdateRequest};
 use crate::structs::colony::{validate_ownership_and_fetch, Anticheat};
+use crate::structs::market::market_for_game_version;
 //use http_api_problem::*;
 
 pub async fn action_update(
@@ -64,12 +66,15 @@ pub async fn action_update(
                 warn!("Marked colony {} as a cheater", &colony.colony_id);
                 colony.used_dev_mode = true;
             }
-            if !incoming.game_version.is_empty() {
-                colony.game_version = incoming.game_version
+            // The colony would be shut out of every market with a version we can't read
+            if let Some(market) = market_for_game_version(&incoming.game_version) {
+                colony.game_version = incoming.game_version;
+                colony.market = Some(market);
             };
             colony.update_date = Utc::now().naive_utc();
             let conn = get_pg_connection();
             if let Ok(result) = colony.save_changes::<Colony>(conn.deref()) {
+                invalidate_colony(&result.colony_id);
                 HttpResponse::Ok().protobuf(ColonyData::from(result))
             } else {
                 error!("Couldn't save changes to colony {}", &bind.client_bind_id);
@@ -1,91 +1,329 @@
-// use crate::request_helpers::*;
-// use actix_web::*;
-// use actix_web::{web, HttpResponse};
-// use chrono::{Duration, Utc};
-// use diesel::{sql_query, ExpressionMethods, RunQueryDsl};
-// use itertools::Itertools;
-// use uuid::Uuid;
-//
-// use crate::crypto::{generate_random_alphanum_string, generate_v4_uuid, parse_uuid};
-// use crate::db::models::bind::ClientBind;
-// use crate::db::models::colony_tradable::ColonyTradables;
-// use crate::db::models::inventory::Inventory;
-// use crate::db::models::inventory_promise::InventoryPromise;
-// use crate::db::{get_pg_connection, Ppc};
-// use crate::packets::inventory::{
-//     ActivatePromiseReply, ActivatePromiseRequest, InventoryReply, InventoryRequest,
-// };
-// use crate::packets::tradable::Tradable;
-// use crate::structs::api_config::LockedApiConfig;
-// use crate::structs::bank_balance::get_bank_balance;
-// use crate::structs::binds::ClientIdGuard;
-// use crate::structs::colony::validate_ownership_and_fetch;
-// use crate::structs::general::DbPkLoadable;
-// use crate::traits::item::MakeTradable;
-// use actix_web::web::Data;
-//
-// pub fn config() -> Scope {
-//     web::scope("/contracts")
-//         .guard(guard::Header("content-type", "application/protobuf"))
-//         .guard(ClientIdGuard())
-//         .route("/", web::post().to(action_post))
-// }
-//
-// pub async fn action_post(
-//     req: HttpRequest,
-//     bind: ClientBind,
-//     packet: ProtoBuf<InventoryRequest>,
-// ) -> Result<HttpResponse> {
-//     let colony = match validate_ownership_and_fetch(None, Some(&packet.colony_id), &bind) {
-//         None => {
-//             return Ok(HttpResponse::BadRequest().finish());
-//         }
-//         Some(value) => value,
-//     };
-//
-//     // Load the relevant colony data
-//     let colony_tradables = ColonyTradables::load_pk(&colony.colony_id);
-//
-//     // Do we have a tradables list for this Colony?
-//     if colony_tradables.is_err() {
-//         return Ok(HttpResponse::UnprocessableEntity().finish());
-//     }
-//
-//     // Unwrap data and get current time
-//     let colony_tradables = colony_tradables.unwrap();
-//
-//     let now = Utc::now().naive_utc();
-//
-//     // Make sure they updated the tradables recently.
-//     if (colony_tradables.update_date - now).num_minutes() > 5 {
-//         return Ok(HttpResponse::UnprocessableEntity().finish());
-//     }
-//
-//     let conn = &get_pg_connection();
-//
-//     // Create new promise
-//     // TODO: Configuration options for expiry times
-//     let promise = generate_promise(colony.colony_id, conn);
-//
-//     // Load tradable data from Inventory table
-//     let tradables = get_inventory_for_colony(colony.colony_id, conn, promise.private_key.clone());
-//
-//     let bank_balance = if let Ok(balance) = get_bank_balance(colony.colony_id, 0, conn) {
-//         balance
-//     } else {
-//         return Ok(HttpResponse::InternalServerError().finish());
-//     };
-//
-//     let lock = req.app_data::<Data<LockedApiConfig>>().unwrap().read();
-//     let config = lock.as_ref().unwrap();
-//
-//     // TODO: Configuration options for charges
-//     HttpResponse::Ok().protobuf(InventoryReply {
-//         items: tradables,
-//         inventory_promise_id: promise.promise_id.to_string(),
-//         inventory_promise_expires: promise.expiry_date.timestamp(),
-//         collection_charge_per_kg: config.config_data.delivery.collect_cost_per_kg as i32,
-//         delivery_charge_per_kg: config.config_data.delivery.delivery_cost_per_kg as i32,
-//         account_balance: bank_balance.balance,
-//     })
-// }
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::iter::FromIterator;
+use std::ops::Deref;
+use std::thread::spawn;
+
+use crate::request_helpers::*;
+use actix_web::web::Data;
+use actix_web::*;
+use actix_web::{web, HttpResponse};
+use bigdecimal::BigDecimal;
+use chrono::Utc;
+use diesel::prelude::*;
+use itertools::Itertools;
+
+use crate::cache::invalidate_colony;
+use crate::crypto::generate_v4_uuid;
+use crate::db::models::bind::ClientBind;
+use crate::db::models::colony::Colony;
+use crate::db::models::contract::Contract;
+use crate::db::models::inventory::Inventory;
+use crate::db::{get_pg_connection, Ppc};
+use crate::packets::common::CurrencyEnum;
+use crate::packets::contract::{
+    ContractListReply, ContractListRequest, ContractStatusEnum, ContractUpdateReply,
+    ContractUpdateRequest,
+};
+use crate::packets::order::OrderItem;
+use crate::stats::order::update_trade_stats_for_order;
+use crate::structs::api_config::LockedApiConfig;
+use crate::structs::bank_balance::{get_bank_balance, update_bank};
+use crate::structs::binds::ClientIdGuard;
+use crate::structs::colony::validate_ownership_and_fetch;
+use crate::structs::contract::{
+    generate_offers, get_contracts_for_colony, load_signed_contract, make_contract_data,
+    sync_colony_tick,
+};
+use crate::structs::inventory::{get_inventory, update_stock};
+use crate::structs::order::{create_order, invalidate_order_inventory, OrderManifest};
+
+pub fn config() -> Scope {
+    web::scope("/contracts")
+        .guard(guard::Header("content-type", "application/protobuf"))
+        .guard(ClientIdGuard())
+        .route("/", web::post().to(action_list))
+        .route("/accept", web::post().to(action_accept))
+        .route("/fulfil", web::post().to(action_fulfil))
+}
+
+/// Move the colony forward to the tick in the request and expire any contracts
+/// that have passed their deadline, both in one transaction.
+fn commit_colony_tick(colony: &mut Colony, tick: i32, conn: &Ppc) -> Result<(), ()> {
+    conn.build_transaction()
+        .read_committed()
+        .run::<_, diesel::result::Error, _>(|| {
+            sync_colony_tick(colony, tick, conn)
+                .map_err(|_| diesel::result::Error::RollbackTransaction)
+        })
+        .map_err(|_| ())?;
+    invalidate_colony(&colony.colony_id);
+    Ok(())
+}
+
+pub async fn action_list(
+    req: HttpRequest,
+    bind: ClientBind,
+    packet: ProtoBuf<ContractListRequest>,
+) -> Result<HttpResponse> {
+    let mut colony = match validate_ownership_and_fetch(None, Some(&packet.colony_id), &bind) {
+        None => {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+        Some(value) => value,
+    };
+
+    let market = match colony.market() {
+        Some(m) => m,
+        None => return Ok(HttpResponse::BadRequest().finish()),
+    };
+
+    let conn = &get_pg_connection();
+    if commit_colony_tick(&mut colony, packet.colony_tick, conn).is_err() {
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+
+    let mut contracts = get_contracts_for_colony(colony.colony_id, packet.any, conn);
+
+    // Top up the offers so there's always something on the board.
+    let offers = {
+        let lock = req.app_data::<Data<LockedApiConfig>>().unwrap().read();
+        let config = lock.as_ref().unwrap();
+        generate_offers(
+            &colony,
+            &market,
+            &contracts,
+            &config.config_data.contracts,
+            conn,
+        )
+    };
+    contracts.extend(offers);
+
+    // Older contracts can be for a market the colony has since moved on from
+    let inventory: HashMap<String, HashMap<String, Inventory>> = contracts
+        .iter()
+        .map(|c| (&c.market, &c.item_code))
+        .into_group_map()
+        .into_iter()
+        .map(|(market, items)| {
+            (
+                market.clone(),
+                get_inventory(HashSet::from_iter(items), market, conn),
+            )
+        })
+        .collect();
+
+    HttpResponse::Ok().protobuf(ContractListReply {
+        contracts: contracts
+            .into_iter()
+            .filter_map(|c| {
+                let item = inventory.get(&c.market)?.get(&c.item_code)?;
+                Some(make_contract_data(c, item))
+            })
+            .collect_vec(),
+    })
+}
+
+pub async fn action_accept(
+    _req: HttpRequest,
+    bind: ClientBind,
+    packet: ProtoBuf<ContractUpdateRequest>,
+) -> Result<HttpResponse> {
+    let mut colony = match validate_ownership_and_fetch(None, Some(&packet.colony_id), &bind) {
+        None => {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+        Some(value) => value,
+    };
+
+    let conn = &get_pg_connection();
+    if commit_colony_tick(&mut colony, packet.colony_tick, conn).is_err() {
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+
+    let mut contract = match load_signed_contract(&packet.contract_id, colony.colony_id) {
+        Ok(c) => c,
+        Err(_) => return Ok(HttpResponse::Forbidden().finish()),
+    };
+
+    // Only offers can be accepted, anything else has already been dealt with.
+    if contract.status != i32::from(ContractStatusEnum::Offered) {
+        return HttpResponse::Ok().protobuf(ContractUpdateReply {
+            success: false,
+            data: None,
+            balance: 0,
+        });
+    }
+
+    contract.status = ContractStatusEnum::Accepted.into();
+    contract.update_date = Utc::now().naive_utc();
+    let contract = match contract.save_changes::<Contract>(conn.deref()) {
+        Ok(c) => c,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+
+    let inventory = get_inventory(
+        HashSet::from_iter(vec![&contract.item_code]),
+        &contract.market,
+        conn,
+    );
+    let data = inventory
+        .get(&contract.item_code)
+        .map(|item| make_contract_data(contract, item));
+
+    HttpResponse::Ok().protobuf(ContractUpdateReply {
+        success: true,
+        data,
+        balance: 0,
+    })
+}
+
+pub async fn action_fulfil(
+    _req: HttpRequest,
+    bind: ClientBind,
+    packet: ProtoBuf<ContractUpdateRequest>,
+) -> Result<HttpResponse> {
+    let mut colony = match validate_ownership_and_fetch(None, Some(&packet.colony_id), &bind) {
+        None => {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+        Some(value) => value,
+    };
+
+    let conn = &get_pg_connection();
+    // Any contract past its deadline is expired here, so it can't be fulfilled below.
+    if commit_colony_tick(&mut colony, packet.colony_tick, conn).is_err() {
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+
+    let mut contract = match load_signed_contract(&packet.contract_id, colony.colony_id) {
+        Ok(c) => c,
+        Err(_) => return Ok(HttpResponse::Forbidden().finish()),
+    };
+
+    let failed = ContractUpdateReply {
+        success: false,
+        data: None,
+        balance: 0,
+    };
+
+    if contract.status != i32::from(ContractStatusEnum::Accepted) {
+        return HttpResponse::Ok().protobuf(failed);
+    }
+
+    let mut db_inventory = get_inventory(
+        HashSet::from_iter(vec![&contract.item_code]),
+        &contract.market,
+        conn,
+    );
+    let item = match db_inventory.get(&contract.item_code) {
+        Some(item) => item.clone(),
+        None => return HttpResponse::Ok().protobuf(failed),
+    };
+
+    match conn
+        .build_transaction()
+        .read_committed()
+        .run::<_, diesel::result::Error, _>(|| {
+            let wts = vec![OrderItem {
+                item_code: contract.item_code.clone(),
+                quantity: contract.quantity,
+                health: 100f32,
+            }];
+
+            // The goods go into stock like any other sale
+            let (mut os, _) = match update_stock(&wts, &vec![], &mut db_inventory, conn) {
+                Err(_) => {
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+                Ok(v) => v,
+            };
+
+            // But the colony is paid the agreed reward instead of the market price,
+            // we also cover the collection costs.
+            os.total_sell_cost = BigDecimal::from(contract.reward);
+            os.total_buy_cost = BigDecimal::default();
+
+            let mut bank_balance = match get_bank_balance(colony.colony_id, contract.currency, conn)
+            {
+                Err(_) => {
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+                Ok(v) => v,
+            };
+
+            // Nothing is refunded so the exchange rate isn't used.
+            let order_id = generate_v4_uuid();
+            let (_, balance_adjustment) = match update_bank(
+                order_id,
+                &os,
+                &db_inventory,
+                0,
+                None,
+                &BigDecimal::from(1),
+                &mut bank_balance,
+                conn,
+            ) {
+                Err(_) => {
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+                Ok(v) => v,
+            };
+
+            // Record it as a sell-only order so it can be rolled back and
+            // counts towards the trade stats.
+            let currency = match CurrencyEnum::try_from(contract.currency) {
+                Err(_) => {
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+                Ok(v) => v,
+            };
+            let manifest = OrderManifest {
+                wts,
+                wtb: vec![],
+                balance_adjustment,
+                currency,
+            };
+
+            let order = match create_order(
+                order_id,
+                &os,
+                &colony,
+                &contract.market,
+                manifest,
+                colony.tick,
+                None,
+                conn,
+            ) {
+                Err(_) => {
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+                Ok(v) => v,
+            };
+
+            contract.status = ContractStatusEnum::Fulfilled.into();
+            contract.update_date = Utc::now().naive_utc();
+            let contract = match contract.save_changes::<Contract>(conn.deref()) {
+                Err(_) => {
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+                Ok(v) => v,
+            };
+
+            Ok((order, contract, bank_balance.balance))
+        }) {
+        Ok((order, contract, balance)) => {
+            invalidate_order_inventory(&order);
+            // Trade stats must be updated after the transaction is committed.
+            let order_id = order.order_id;
+            spawn(move || update_trade_stats_for_order(order_id));
+
+            HttpResponse::Ok().protobuf(ContractUpdateReply {
+                success: true,
+                data: Some(make_contract_data(contract, &item)),
+                balance,
+            })
+        }
+        Err(_) => HttpResponse::Ok().protobuf(failed),
+    }
+}
@@ -6,25 +6,29 @@ use diesel::{sql_query, ExpressionMethods, RunQueryDsl};
 use itertools::Itertools;
 use uuid::Uuid;
 
-use crate::crypto::{generate_random_alphanum_string, generate_v4_uuid, parse_uuid};
+use crate::cache::blacklist::get_blacklist;
+use crate::cache::inventory::{get_colony_inventory, get_colony_tradables};
+use crate::crypto::{generate_secure_token, generate_v4_uuid, parse_uuid};
 use crate::db::models::bind::ClientBind;
-use crate::db::models::colony_tradable::ColonyTradables;
 use crate::db::models::inventory::Inventory;
 use crate::db::models::inventory_promise::InventoryPromise;
 use crate::db::{get_pg_connection, Ppc};
 use crate::packets::inventory::{
     ActivatePromiseReply, ActivatePromiseRequest, GeneratePromiseReply, GeneratePromiseRequest,
-    InventoryReply, InventoryRequest,
+    InventoryReply, InventoryRequest, PriceHistoryReply, PriceHistoryRequest, PricePoint,
 };
 use crate::packets::tradable::Tradable;
 use crate::structs::api_config::LockedApiConfig;
 use crate::structs::bank_balance::get_bank_balance;
 use crate::structs::binds::ClientIdGuard;
+use crate::structs::blacklist::Blacklist;
 use crate::structs::colony::validate_ownership_and_fetch;
-use crate::structs::general::DbPkLoadable;
+use crate::structs::colony_mods::{get_loaded_mods, is_mod_loaded};
 use crate::structs::inventory_promise::{
     get_promise_for_colony,
 };
+use crate::structs::market::is_market_live;
+use crate::structs::price_tracker::get_price_history;
 use crate::traits::item::MakeTradable;
 use actix_web::web::Data;
 
@@ -35,8 +39,12 @@ pub fn config() -> Scope {
         .route("/", web::post().to(action_post))
         .route("/activate", web::post().to(action_activate))
         .route("/promise", web::post().to(action_make_promise))
+        .route("/history", web::post().to(action_history))
 }
 
+/// Longest price history a colony can ask for in one go
+const MAX_HISTORY_DAYS: i32 = 90;
+
 pub async fn action_make_promise(
     _: HttpRequest,
     bind: ClientBind,
@@ -83,6 +91,54 @@ pub async fn action_activate(
     )
 }
 
+pub async fn action_history(
+    _: HttpRequest,
+    bind: ClientBind,
+    packet: ProtoBuf<PriceHistoryRequest>,
+) -> Result<HttpResponse> {
+    use itsdangerous::{default_builder, Signer};
+
+    let colony = match validate_ownership_and_fetch(None, Some(&packet.colony_id), &bind) {
+        None => {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+        Some(value) => value,
+    };
+
+    let market = match colony.market() {
+        Some(m) => m,
+        None => return Ok(HttpResponse::BadRequest().finish()),
+    };
+
+    // Item codes are signed with the promise, so they must have fetched the inventory recently.
+    let promise = match get_promise_for_colony(colony.colony_id) {
+        Ok(p) => p,
+        Err(_) => {
+            return Ok(HttpResponse::UnprocessableEntity().finish());
+        }
+    };
+
+    let signer = default_builder(promise.private_key).build();
+    let item_code = match signer.unsign(&*packet.item_code) {
+        Ok(value) => String::from(value),
+        Err(_) => {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+    };
+
+    let days = packet.days.max(1).min(MAX_HISTORY_DAYS);
+    let since = Utc::now().naive_utc() - Duration::days(days as i64);
+
+    let conn = &get_pg_connection();
+    HttpResponse::Ok().protobuf(PriceHistoryReply {
+        item_code: packet.item_code.clone(),
+        history: get_price_history(&market, &item_code, since, conn)
+            .into_iter()
+            .map_into::<PricePoint>()
+            .collect_vec(),
+    })
+}
+
 pub async fn action_post(
     req: HttpRequest,
     bind: ClientBind,
@@ -95,8 +151,21 @@ pub async fn action_post(
         Some(value) => value,
     };
 
+    let market = match colony.market() {
+        Some(m) => m,
+        None => return Ok(HttpResponse::BadRequest().finish()),
+    };
+    let live = {
+        let lock = req.app_data::<Data<LockedApiConfig>>().unwrap().read();
+        let config = lock.as_ref().unwrap();
+        is_market_live(&config.config_data.markets, &market)
+    };
+    if !live {
+        return Ok(HttpResponse::UnprocessableEntity().finish());
+    }
+
     // Load the relevant colony data
-    let colony_tradables = ColonyTradables::load_pk(&colony.colony_id);
+    let colony_tradables = get_colony_tradables(&colony.colony_id);
 
     // Do we have a tradables list for this Colony?
     if colony_tradables.is_err() {
@@ -129,7 +198,8 @@ pub async fn action_post(
     };
 
     // Load tradable data from Inventory table
-    let tradables = get_inventory_for_colony(colony.colony_id, conn, promise.private_key.clone());
+    let tradables =
+        get_inventory_for_colony(colony.colony_id, &market, conn, promise.private_key.clone());
 
     let bank_balance = if let Ok(balance) = get_bank_balance(colony.colony_id, 0, conn) {
         balance
@@ -157,7 +227,7 @@ fn generate_promise(colony_id: Uuid, conn: &Ppc) -> InventoryPromise {
     let promise = InventoryPromise {
         colony_id,
         promise_id: generate_v4_uuid(),
-        private_key: generate_random_alphanum_string(32),
+        private_key: generate_secure_token(32),
         expiry_date: Utc::now().naive_utc() + Duration::minutes(5),
         activated: false,
     };
@@ -192,12 +262,17 @@ fn activate_promise(
         .map_err(|_| "Failed to find provided promise ID".to_owned())
 }
 
-fn get_inventory_for_colony(colony_id: Uuid, conn: &Ppc, secret_key: String) -> Vec<Tradable> {
+fn get_inventory_for_colony(
+    colony_id: Uuid,
+    market: &str,
+    conn: &Ppc,
+    secret_key: String,
+) -> Vec<Tradable> {
     use itsdangerous::default_builder;
     let inventory_query = "SELECT j.*
     FROM colony_tradables as cto \
     LEFT JOIN LATERAL ( \
-    select ct.code as item_code, \
+    select i.item_code, \
     thing_def, \
     quality, \
     quantity, \
@@ -207,9 +282,14 @@ fn get_inventory_for_colony(colony_id: Uuid, conn: &Ppc, secret_key: String) ->
     sell_at, \
     stuff, \
     weight, \
-    version \
+    version, \
+    condition, \
+    parent_item_code, \
+    mod_name, \
+    market \
     from jsonb_array_elements_text(cto.tradables) as ct(code) \
-    INNER JOIN inventory i on i.item_code = ct.code \
+    INNER JOIN inventory i on (i.item_code = ct.code or i.parent_item_code = ct.code) \
+    AND i.market = $2 \
     ) j on true \
     WHERE colony_id = $1";
 
@@ -217,21 +297,40 @@ fn get_inventory_for_colony(colony_id: Uuid, conn: &Ppc, secret_key: String) ->
     // it won't show in the UI since we only iterate rows from the server
     // Saves having to combine two lists on the client side.
 
-    let inventory: Vec<Inventory> = match sql_query(inventory_query)
-        .bind::<diesel::sql_types::Uuid, _>(colony_id)
-        .get_results(conn)
-    {
-        Err(e) => {
-            warn!("Suspicious error when getting inventory, {}", e);
-            Vec::new()
-        }
-        Ok(data) => data,
+    // Try the cache first, fall back to the database if it's unavailable.
+    let inventory: Vec<Inventory> = match get_colony_inventory(&colony_id, market, conn) {
+        Some(data) => data,
+        None => match sql_query(inventory_query)
+            .bind::<diesel::sql_types::Uuid, _>(colony_id)
+            .bind::<diesel::sql_types::Text, _>(market)
+            .get_results(conn)
+        {
+            Err(e) => {
+                warn!("Suspicious error when getting inventory, {}", e);
+                Vec::new()
+            }
+            Ok(data) => data,
+        },
     };
 
+    // Stock from before an item was blacklisted stays in the table, just don't offer it.
+    let blacklist = get_blacklist(conn).unwrap_or_else(|e| {
+        warn!("Unable to load the item blacklist, {}", e);
+        Blacklist::default()
+    });
+
+    // Items from mods the colony doesn't have would only clutter the list.
+    let loaded_mods = get_loaded_mods(&colony_id, conn).unwrap_or_else(|e| {
+        warn!("Unable to load mods for colony {}, {}", colony_id, e);
+        None
+    });
+
     // Create a signer using the default builder, and an arbitrary secret key.
     let signer = default_builder(secret_key).build();
     inventory
         .into_iter()
+        .filter(|inv| !blacklist.contains_inventory(inv))
+        .filter(|inv| is_mod_loaded(&loaded_mods, inv))
         .map(|inv| inv.make_tradable(&signer))
         .collect_vec()
 }
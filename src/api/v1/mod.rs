@@ -1,5 +1,6 @@
 use actix_web::{web, Scope};
 
+pub mod admin;
 pub mod backorder;
 pub mod bank;
 pub mod binder;
@@ -20,4 +21,7 @@ pub fn config() -> Scope {
         .service(player::config())
         .service(binder::config())
         .service(bank::config())
+        .service(contracts::config())
+        .service(backorder::config())
+        .service(admin::config())
 }
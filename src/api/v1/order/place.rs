@@ -9,24 +9,32 @@ use itsdangerous::default_builder;
 
 use crate::db::models::bind::ClientBind;
 
-use crate::crypto::parse_uuid;
+use crate::cache::config::get_latest_stock_config;
+use crate::cache::inventory::bump_inventory_generation;
+use crate::crypto::{generate_v4_uuid, parse_uuid};
 use crate::db::get_pg_connection;
 use crate::packets::common::CurrencyEnum;
 use crate::packets::order::{
     OrderReply, OrderRequest, OrderRequestStatus, OrderStatusEnum, OrderStatusReply,
 };
+use crate::routines::market::get_item_override;
 use crate::stats::order::update_trade_stats_for_order;
-use crate::structs::bank_balance::get_bank_balance;
+use crate::structs::backorder::create_backorders;
+use crate::structs::bank_balance::{get_bank_balance, refund_value};
+use crate::structs::api_config::LockedApiConfig;
 use crate::structs::colony::validate_ownership_and_fetch;
+use crate::structs::currency::get_exchange_rate;
 use crate::structs::inventory::get_inventory;
+use crate::structs::market::is_market_live;
 use crate::structs::order::OrderManifest;
 use crate::structs::{bank_balance, inventory, inventory_promise, order};
 use crate::traits::item::ValidateItemSignature;
 use crate::traits::numerical::CanRound;
-use bigdecimal::ToPrimitive;
+use actix_web::web::Data;
+use bigdecimal::{BigDecimal, ToPrimitive};
 
 pub async fn action_post(
-    _req: HttpRequest,
+    req: HttpRequest,
     bind: ClientBind,
     packet: ProtoBuf<OrderRequest>,
 ) -> Result<HttpResponse> {
@@ -41,6 +49,19 @@ pub async fn action_post(
         return Ok(HttpResponse::Forbidden().finish());
     }
 
+    let market = match colony.market() {
+        Some(m) => m,
+        None => return Ok(HttpResponse::BadRequest().finish()),
+    };
+    let live = {
+        let lock = req.app_data::<Data<LockedApiConfig>>().unwrap().read();
+        let config = lock.as_ref().unwrap();
+        is_market_live(&config.config_data.markets, &market)
+    };
+    if !live {
+        return Ok(HttpResponse::UnprocessableEntity().finish());
+    }
+
     let promise_id = match parse_uuid(&*packet.inventory_promise_id) {
         Ok(uuid) => uuid,
         Err(_) => return Ok(HttpResponse::Gone().finish()),
@@ -64,9 +85,17 @@ pub async fn action_post(
     let signer = default_builder(promise.private_key).build();
     let mut wts = packet.0.want_to_sell;
     let mut wtb = packet.0.want_to_buy;
-    let mut inventory_wanted = HashSet::<&String>::with_capacity(wts.len() + wtb.len());
-    let currency = CurrencyEnum::try_from(packet.0.currency).unwrap();
+    let currency = match CurrencyEnum::try_from(packet.0.currency) {
+        Ok(c) => c,
+        Err(_) => return Ok(HttpResponse::BadRequest().finish()),
+    };
     let additional_funds = packet.0.additional_funds;
+    let allow_backorder = packet.0.allow_backorder;
+
+    // What we sell is priced by its stock line, the client doesn't get a say in its condition.
+    for item in wtb.iter_mut() {
+        item.health = 100f32;
+    }
 
     // We can't be sent things that aren't in our inventory,
     // Trying to do so causes the validation routine to fail here
@@ -75,17 +104,72 @@ pub async fn action_post(
         if item.validate_item_code(&signer).is_err() {
             return Ok(HttpResponse::Forbidden().finish());
         }
-        inventory_wanted.insert(&item.item_code);
+        if !inventory::is_valid_health(item.health) {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
     }
 
-    // Now fetch all the inventory rows related to the items WTS/WTB
     let conn = &get_pg_connection();
-    let mut db_inventory = get_inventory(inventory_wanted, conn);
+    let stock_config = match get_latest_stock_config(conn) {
+        Ok(c) => c.config_data,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    };
+
+    // Some items we won't take off their hands at all.
+    if !stock_config.overrides.is_empty() && !wts.is_empty() {
+        let offered = get_inventory(
+            wts.iter().map(|item| &item.item_code).collect(),
+            &market,
+            conn,
+        );
+        if offered.values().any(|item| {
+            get_item_override(&stock_config, item).map_or(false, |o| o.never_buy == Some(true))
+        }) {
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+    }
+
+    // Stock is priced in UTC, if they're paying with something else it needs an exchange rate.
+    let rate = {
+        let lock = req.app_data::<Data<LockedApiConfig>>().unwrap().read();
+        let config = lock.as_ref().unwrap();
+        get_exchange_rate(&config.config_data.currency, currency)
+    };
+    let rate = match rate {
+        Some(r) => r,
+        None => return Ok(HttpResponse::BadRequest().finish()),
+    };
+
+    // The promise was for another market, the colony has changed game version since.
+    let inventory_wanted: HashSet<&String> = wts
+        .iter()
+        .chain(wtb.iter())
+        .map(|item| &item.item_code)
+        .collect();
+    let wanted_count = inventory_wanted.len();
+    if get_inventory(inventory_wanted, &market, conn).len() != wanted_count {
+        return Ok(HttpResponse::Gone().finish());
+    }
 
     match conn
         .build_transaction()
         .read_committed()
         .run::<_, diesel::result::Error, _>(|| {
+            // Worn out items go into the used stock line rather than being sold on as new.
+            let created_used_lines =
+                inventory::route_used_items(&mut wts, &market, &stock_config.condition, conn)
+                    .map_err(|_| diesel::result::Error::RollbackTransaction)?;
+
+            // Now fetch all the inventory rows related to the items WTS/WTB
+            let mut db_inventory = get_inventory(
+                wts.iter()
+                    .chain(wtb.iter())
+                    .map(|item| &item.item_code)
+                    .collect(),
+                &market,
+                conn,
+            );
+
             let (os, out_of_stock) =
                 match inventory::update_stock(&wts, &wtb, &mut db_inventory, conn) {
                     Err(_) => {
@@ -93,6 +177,7 @@ pub async fn action_post(
                     }
                     Ok(v) => v,
                 };
+            let os = os.in_currency(&rate);
 
             let mut bank_balance = match get_bank_balance(colony.colony_id, currency.into(), conn) {
                 Err(_) => {
@@ -101,11 +186,23 @@ pub async fn action_post(
                 Ok(v) => v,
             };
 
+            // If they asked for it, anything out of stock is paid for now and
+            // delivered later instead of being refunded.
+            let backorder_cost = if allow_backorder {
+                out_of_stock
+                    .iter()
+                    .fold(BigDecimal::default(), |total, item| {
+                        total + refund_value(db_inventory.get(&item.item_code).unwrap(), item, &rate)
+                    })
+            } else {
+                BigDecimal::default()
+            };
+
             // Check if their bank balance will be positive after the transaction,
             // If not reject.
             if bank_balance.balance as f32
                 + additional_funds as f32
-                + (&os.total_sell_cost - &os.total_buy_cost)
+                + (&os.total_sell_cost - &os.total_buy_cost - &backorder_cost)
                 .round_2dp()
                 .to_f32()
                 .unwrap()
@@ -115,15 +212,19 @@ pub async fn action_post(
                 return Err(diesel::result::Error::RollbackTransaction);
             }
 
+            // Generated up front so the ledger entries can point at the order
+            let order_id = generate_v4_uuid();
             let (refunded, balance_adjustment) = match bank_balance::update_bank(
+                order_id,
                 &os,
                 &db_inventory,
                 additional_funds,
-                if out_of_stock.is_empty() {
+                if out_of_stock.is_empty() || allow_backorder {
                     None
                 } else {
                     Some(&out_of_stock)
                 },
+                &rate,
                 &mut bank_balance,
                 conn,
             ) {
@@ -133,6 +234,25 @@ pub async fn action_post(
                 Ok(v) => v,
             };
 
+            let (backordered, unavailable) = if allow_backorder && !out_of_stock.is_empty() {
+                if create_backorders(
+                    &colony,
+                    &market,
+                    &out_of_stock,
+                    &db_inventory,
+                    &rate,
+                    &mut bank_balance,
+                    conn,
+                )
+                .is_err()
+                {
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+                (out_of_stock, vec![])
+            } else {
+                (vec![], out_of_stock)
+            };
+
             let manifest = OrderManifest {
                 wts,
                 wtb,
@@ -140,7 +260,16 @@ pub async fn action_post(
                 currency,
             };
 
-            let order = match order::create_order(&os, &colony, manifest, colony.tick, None, conn) {
+            let order = match order::create_order(
+                order_id,
+                &os,
+                &colony,
+                &market,
+                manifest,
+                colony.tick,
+                None,
+                conn,
+            ) {
                 Err(_) => {
                     return Err(diesel::result::Error::RollbackTransaction);
                 }
@@ -156,20 +285,27 @@ pub async fn action_post(
 
             Ok((
                 order,
+                created_used_lines,
                 HttpResponse::Ok().protobuf(OrderReply {
                     data: reply,
-                    status: if out_of_stock.len() > 0 {
+                    status: if unavailable.len() > 0 {
                         OrderRequestStatus::AcceptedPartial.into()
                     } else {
                         OrderRequestStatus::AcceptedAll.into()
                     },
-                    unavailable: out_of_stock,
+                    unavailable,
                     refunded,
                     balance: bank_balance.balance,
+                    backordered,
                 }),
             ))
         }) {
-        Ok((order, response)) => {
+        Ok((order, created_used_lines, response)) => {
+            // Cached inventory lists won't know about the new lines
+            if created_used_lines {
+                bump_inventory_generation();
+            }
+            order::invalidate_order_inventory(&order);
             // Need to update trade stats for sell only orders
             // But we need to do it after the transaction is committed.
             if order.status == i32::from(OrderStatusEnum::Delivered) {
@@ -184,6 +320,7 @@ pub async fn action_post(
             unavailable: vec![],
             refunded: 0,
             balance: 0,
+            backordered: vec![],
         }),
     }
 }
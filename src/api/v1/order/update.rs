@@ -8,20 +8,26 @@ use diesel::SaveChangesDsl;
 use diesel::{ExpressionMethods, QueryDsl};
 //use http_api_problem::*;
 
+use crate::cache::invalidate_colony;
 use crate::crypto::parse_uuid;
 use crate::db::get_pg_connection;
 use crate::db::models::bind::ClientBind;
-use crate::db::models::colony::Colony;
 use crate::db::models::order::{Order, OrderNoManifest};
 use crate::db::schema::orders as schema;
-use crate::packets::order::{OrderStatusEnum, OrderStatusReply, OrderUpdateRequest};
+use crate::packets::order::{
+    OrderFailureReasonEnum, OrderStatusEnum, OrderStatusReply, OrderUpdateRequest,
+};
 use crate::stats::order::update_trade_stats_for_order;
+use crate::structs::api_config::LockedApiConfig;
 use crate::structs::colony::validate_ownership_and_fetch;
+use crate::structs::contract::sync_colony_tick;
 use crate::structs::general::ONE_HOUR_TICKS;
+use crate::structs::order::{cancel_order, fail_order, invalidate_order_inventory};
+use actix_web::web::Data;
 use std::thread::spawn;
 
 pub async fn action_update(
-    _req: HttpRequest,
+    req: HttpRequest,
     bind: ClientBind,
     packet: ProtoBuf<OrderUpdateRequest>,
 ) -> Result<HttpResponse> {
@@ -41,6 +47,8 @@ pub async fn action_update(
                 schema::order_stats,
                 schema::create_date,
                 schema::update_date,
+                schema::failure_reason,
+                schema::market,
             ))
             .filter(schema::colony_id.eq(&colony.colony_id))
             .filter(schema::order_id.eq(order_id))
@@ -55,17 +63,6 @@ pub async fn action_update(
             Ok(v) => v,
         };
 
-        if packet.colony_tick < colony.tick {
-            return Ok(HttpResponse::BadRequest().finish());
-            // return Ok(HttpApiProblem::new(StatusCode::BAD_REQUEST)
-            //     .title("Problem updating order")
-            //     .detail("Colony tick de-sync, do a Colony Update first.")
-            //     .instance(format!("{}", order_id))
-            //     .to_actix_response());
-        } else {
-            colony.tick = packet.colony_tick;
-        }
-
         // Parse the int32 into an Enum, Easier to work with.
         let status_enum = match OrderStatusEnum::try_from(packet.status) {
             Ok(v) => v,
@@ -74,17 +71,27 @@ pub async fn action_update(
             }
         };
 
-        // Enter state machine here
-        order.status = match OrderStatusEnum::try_from(order.status).unwrap() {
-            // If the status is Placed, the only option is OutForDelivery
+        // Enter state machine here, cancelling or failing the order also
+        // gives back the stock and settles up with the bank.
+        let mut restocking_fee_pct = None;
+        let mut failure = None;
+        match OrderStatusEnum::try_from(order.status).unwrap() {
+            // If the status is Placed, it can go OutForDelivery or be Cancelled
             OrderStatusEnum::Placed => {
                 match status_enum {
+                    OrderStatusEnum::Cancelled => {
+                        // Only until the delivery window opens
+                        if packet.colony_tick > (order.end_tick - (ONE_HOUR_TICKS * 6)) {
+                            return Ok(HttpResponse::BadRequest().finish());
+                        }
+                        let lock = req.app_data::<Data<LockedApiConfig>>().unwrap().read();
+                        let config = lock.as_ref().unwrap();
+                        restocking_fee_pct = Some(config.config_data.orders.restocking_fee_pct);
+                    }
                     OrderStatusEnum::OutForDelivery => {
                         // Don't all allow status change until
                         // at least 6 in-game hours before delivery
-                        if colony.tick > (order.end_tick - (ONE_HOUR_TICKS * 6)) {
-                            status_enum.into()
-                        } else {
+                        if packet.colony_tick <= (order.end_tick - (ONE_HOUR_TICKS * 6)) {
                             return Ok(HttpResponse::BadRequest().finish());
                         }
                     }
@@ -95,14 +102,17 @@ pub async fn action_update(
             }
             // If it's OutForDelivery, only two statuses, Failed and Delivered
             OrderStatusEnum::OutForDelivery => match status_enum {
-                OrderStatusEnum::Delivered => {
-                    // Update trade stats in a separate thread
-                    spawn(move || update_trade_stats_for_order(order_id.clone()));
-                    status_enum.into()
-                }
+                OrderStatusEnum::Delivered => {}
                 OrderStatusEnum::Failed => {
-                    // Rollback the order?
-                    status_enum.into()
+                    let reason = match OrderFailureReasonEnum::try_from(packet.failure_reason) {
+                        Ok(v) => v,
+                        Err(_) => {
+                            return Ok(HttpResponse::BadRequest().finish());
+                        }
+                    };
+                    let lock = req.app_data::<Data<LockedApiConfig>>().unwrap().read();
+                    let config = lock.as_ref().unwrap();
+                    failure = Some((reason, config.config_data.orders.insurance_pct));
                 }
                 _ => {
                     return Ok(HttpResponse::BadRequest().finish());
@@ -114,15 +124,57 @@ pub async fn action_update(
             }
         };
 
-        // Save the new tick value for the Colony.
-        if colony.save_changes::<Colony>(conn.deref()).is_err() {
-            return Ok(HttpResponse::InternalServerError().finish());
-        };
+        // The new tick, the status and anything given back all land together or not at all.
+        let closed = match conn
+            .build_transaction()
+            .read_committed()
+            .run::<_, diesel::result::Error, _>(|| {
+                // A tick behind the colony's is a de-sync, do a Colony Update first.
+                sync_colony_tick(&mut colony, packet.colony_tick, conn)
+                    .map_err(|_| diesel::result::Error::RollbackTransaction)?;
+                let closed = if let Some(fee_pct) = restocking_fee_pct {
+                    Some(
+                        cancel_order(order_id, fee_pct, conn)
+                            .map_err(|_| diesel::result::Error::RollbackTransaction)?
+                            .0,
+                    )
+                } else if let Some((reason, insurance_pct)) = failure {
+                    Some(
+                        fail_order(order_id, reason, insurance_pct, conn)
+                            .map_err(|_| diesel::result::Error::RollbackTransaction)?
+                            .0,
+                    )
+                } else {
+                    None
+                };
+                if let Some(closed) = closed.as_ref() {
+                    order.failure_reason = closed.failure_reason;
+                    order.update_date = closed.update_date;
+                }
+                order.status = status_enum.into();
 
-        // Save the new status.
-        if order.save_changes::<Order>(conn.deref()).is_err() {
-            return Ok(HttpResponse::InternalServerError().finish());
+                // Save the new status.
+                order.save_changes::<Order>(conn.deref())?;
+                Ok(closed)
+            }) {
+            Ok(closed) => closed,
+            // The tick is behind, or the order couldn't be cancelled or failed as it has moved on
+            Err(diesel::result::Error::RollbackTransaction) => {
+                return Ok(HttpResponse::BadRequest().finish());
+            }
+            Err(_) => {
+                return Ok(HttpResponse::InternalServerError().finish());
+            }
         };
+        invalidate_colony(&colony.colony_id);
+        if let Some(closed) = closed.as_ref() {
+            invalidate_order_inventory(closed);
+        }
+
+        // Update trade stats in a separate thread, once the delivery is committed.
+        if status_enum == OrderStatusEnum::Delivered {
+            spawn(move || update_trade_stats_for_order(order_id.clone()));
+        }
 
         HttpResponse::Ok().protobuf(OrderStatusReply::from(order))
     } else {
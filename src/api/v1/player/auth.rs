@@ -1,5 +1,80 @@
-use actix_web::{HttpRequest, HttpResponse};
+use actix_web::*;
 
-pub async fn action(_req: HttpRequest) -> HttpResponse {
-    HttpResponse::InternalServerError().finish()
+use crate::crypto::verify_password;
+use crate::db::models::account::Account;
+use crate::structs::account::{
+    accept_totp, clear_failed_logins, create_session, delete_session, find_account_for_username,
+    get_session_token, is_locked, record_failed_login,
+};
+use crate::structs::player::{AuthRequest, AuthResponse, AuthResponseCode};
+
+fn failed_auth(code: AuthResponseCode) -> HttpResponse {
+    HttpResponse::Ok().json(AuthResponse {
+        player_id: 0,
+        token_id: String::new(),
+        token_expires: 0,
+        auth_response_code: code as u8,
+    })
+}
+
+/// Same as `failed_auth` but the attempt counts towards locking the account
+fn failed_login(account: &Account, code: AuthResponseCode) -> HttpResponse {
+    match record_failed_login(account) {
+        Ok(_) => failed_auth(code),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+pub async fn action(request: web::Json<AuthRequest>) -> HttpResponse {
+    let account = match find_account_for_username(&request.username) {
+        Some(a) if a.active => a,
+        _ => return failed_auth(AuthResponseCode::InvalidCredentials),
+    };
+
+    if is_locked(&account) {
+        return failed_auth(AuthResponseCode::Locked);
+    }
+
+    // Steam accounts don't have a password, they can't log in this way.
+    if !account
+        .password
+        .as_ref()
+        .map_or(false, |hash| verify_password(&request.password, hash))
+    {
+        return failed_login(&account, AuthResponseCode::InvalidCredentials);
+    }
+
+    if let Some(secret) = &account.mfa_code {
+        match &request.mfa_code {
+            None => return failed_auth(AuthResponseCode::MfaRequired),
+            Some(code) => match accept_totp(&account, secret, code) {
+                Ok(true) => {}
+                Ok(false) => return failed_login(&account, AuthResponseCode::MfaInvalid),
+                Err(_) => return HttpResponse::InternalServerError().finish(),
+            },
+        }
+    }
+
+    if clear_failed_logins(&account).is_err() {
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    match create_session(&account) {
+        Ok(session) => HttpResponse::Ok().json(AuthResponse {
+            player_id: account.account_id,
+            token_id: session.session_id,
+            token_expires: session.date_expire.timestamp(),
+            auth_response_code: AuthResponseCode::Ok as u8,
+        }),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+pub async fn action_logout(req: HttpRequest, _account: Account) -> HttpResponse {
+    if let Some(token) = get_session_token(req.headers()) {
+        if delete_session(token).is_err() {
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+    HttpResponse::Ok().finish()
 }
@@ -0,0 +1,43 @@
+use actix_web::*;
+use chrono::Utc;
+
+use crate::db::models::account::Account;
+use crate::db::models::bind::AccountBind;
+use crate::db::parse_and_load_uuid_pk;
+use crate::structs::binds::{account_bind_valid, claim_account_bind};
+use crate::structs::player::{ClaimBindRequest, ClaimBindResponse};
+
+pub async fn action_claim(
+    player_id: web::Path<i32>,
+    account: Account,
+    request: web::Json<ClaimBindRequest>,
+) -> HttpResponse {
+    if !super::is_own_account(player_id.into_inner(), &account) {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let current_time = Utc::now().naive_utc();
+    let bind_data = match parse_and_load_uuid_pk::<AccountBind>(&*request.bind_id) {
+        Ok(b) => b,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    // Expired or already collected by the game
+    if !account_bind_valid(&current_time, &bind_data) {
+        return HttpResponse::Gone().finish();
+    }
+
+    // Steam binds confirm themselves, and a bind can only be claimed once.
+    if bind_data.steam_id.is_some() || bind_data.account_fk.is_some() {
+        return HttpResponse::Conflict().finish();
+    }
+
+    match claim_account_bind(&account, &bind_data) {
+        Ok(_) => HttpResponse::Ok().json(ClaimBindResponse {
+            bind_id: bind_data.bind_id.to_string(),
+            confirmed: false,
+            ttl: (bind_data.date_expire - current_time).num_seconds(),
+        }),
+        Err(_) => HttpResponse::Conflict().finish(),
+    }
+}
@@ -1,5 +1,30 @@
 use actix_web::*;
 
-pub async fn action(_req: HttpRequest) -> Result<HttpResponse> {
-    Ok(HttpResponse::Ok().finish())
+use crate::structs::account::create_account_for_username;
+use crate::structs::player::{NewPlayerRequest, NewPlayerResponse};
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+pub fn valid_username(username: &str) -> bool {
+    (3..=32).contains(&username.len())
+        && username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+pub async fn action(request: web::Json<NewPlayerRequest>) -> Result<HttpResponse> {
+    let request = request.into_inner();
+    if !valid_username(&request.username) || request.password.len() < MIN_PASSWORD_LENGTH {
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+
+    match create_account_for_username(&request.username, &request.password, request.e_mail) {
+        Ok(account) => Ok(HttpResponse::Ok().json(NewPlayerResponse {
+            player_id: account.account_id,
+        })),
+        Err(e) => {
+            debug!("Unable to create account {}, {}", &request.username, e);
+            Ok(HttpResponse::Conflict().finish())
+        }
+    }
 }
@@ -1,5 +1,11 @@
-use actix_web::web;
+use actix_web::*;
 
-pub async fn action(info: web::Path<String>) -> String {
-    format!("Get {}", info)
+use crate::db::models::account::Account;
+use crate::structs::player::PlayerProfile;
+
+pub async fn action(player_id: web::Path<i32>, account: Account) -> HttpResponse {
+    if !super::is_own_account(player_id.into_inner(), &account) {
+        return HttpResponse::Forbidden().finish();
+    }
+    HttpResponse::Ok().json(PlayerProfile::from(account))
 }
@@ -0,0 +1,89 @@
+use actix_web::*;
+use diesel::prelude::*;
+
+use crate::crypto::generate_totp_secret;
+use crate::db::get_pg_connection;
+use crate::db::models::account::Account;
+use crate::db::schema::accounts as schema;
+use crate::structs::account::{accept_totp, is_locked, record_failed_login};
+use crate::structs::player::{MfaSecretResponse, MfaUpdateRequest, PlayerProfile};
+
+/// Generate a new secret, it isn't stored until the player proves
+/// they've added it to their authenticator by enabling MFA with it.
+pub async fn action_secret(player_id: web::Path<i32>, account: Account) -> HttpResponse {
+    if !super::is_own_account(player_id.into_inner(), &account) {
+        return HttpResponse::Forbidden().finish();
+    }
+    HttpResponse::Ok().json(MfaSecretResponse {
+        secret: generate_totp_secret(),
+    })
+}
+
+pub async fn action_enable(
+    player_id: web::Path<i32>,
+    account: Account,
+    request: web::Json<MfaUpdateRequest>,
+) -> HttpResponse {
+    if !super::is_own_account(player_id.into_inner(), &account) {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    if account.mfa_code.is_some() {
+        return HttpResponse::Conflict().finish();
+    }
+
+    let secret = match &request.secret {
+        Some(s) => s,
+        None => return HttpResponse::BadRequest().finish(),
+    };
+
+    match check_code(&account, secret, &request.code) {
+        Ok(_) => set_mfa_code(&account, Some(secret.clone())),
+        Err(response) => response,
+    }
+}
+
+pub async fn action_disable(
+    player_id: web::Path<i32>,
+    account: Account,
+    request: web::Json<MfaUpdateRequest>,
+) -> HttpResponse {
+    if !super::is_own_account(player_id.into_inner(), &account) {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    match &account.mfa_code {
+        Some(secret) => match check_code(&account, secret, &request.code) {
+            Ok(_) => set_mfa_code(&account, None),
+            Err(response) => response,
+        },
+        None => HttpResponse::Conflict().finish(),
+    }
+}
+
+/// Wrong codes count towards the same lockout as logins,
+/// so a stolen session can't be used to guess them either.
+fn check_code(account: &Account, secret: &str, code: &str) -> Result<(), HttpResponse> {
+    if is_locked(account) {
+        return Err(HttpResponse::TooManyRequests().finish());
+    }
+    match accept_totp(account, secret, code) {
+        Ok(true) => Ok(()),
+        Ok(false) => match record_failed_login(account) {
+            Ok(_) => Err(HttpResponse::BadRequest().finish()),
+            Err(_) => Err(HttpResponse::InternalServerError().finish()),
+        },
+        Err(_) => Err(HttpResponse::InternalServerError().finish()),
+    }
+}
+
+fn set_mfa_code(account: &Account, secret: Option<String>) -> HttpResponse {
+    let conn = get_pg_connection();
+    match diesel::update(account)
+        .set(schema::mfa_code.eq(secret))
+        .get_result::<Account>(&conn)
+    {
+        Ok(updated) => HttpResponse::Ok().json(PlayerProfile::from(updated)),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
@@ -1,13 +1,49 @@
 use actix_web::{web, Scope};
 
+use crate::structs::account::SessionGuard;
+
 mod auth;
+mod bind;
 mod create;
 mod get;
+mod mfa;
 mod update;
 
 pub fn config() -> Scope {
     web::scope("/player")
-        .route("/{player_id}/", web::get().to(get::action))
-        .route("/{player_id}/", web::patch().to(update::action))
         .route("/", web::post().to(create::action))
+        .route("/auth", web::post().to(auth::action))
+        .route(
+            "/logout",
+            web::post().guard(SessionGuard()).to(auth::action_logout),
+        )
+        .route(
+            "/{player_id}/",
+            web::get().guard(SessionGuard()).to(get::action),
+        )
+        .route(
+            "/{player_id}/",
+            web::patch().guard(SessionGuard()).to(update::action),
+        )
+        .route(
+            "/{player_id}/bind",
+            web::post().guard(SessionGuard()).to(bind::action_claim),
+        )
+        .route(
+            "/{player_id}/mfa/secret",
+            web::post().guard(SessionGuard()).to(mfa::action_secret),
+        )
+        .route(
+            "/{player_id}/mfa",
+            web::put().guard(SessionGuard()).to(mfa::action_enable),
+        )
+        .route(
+            "/{player_id}/mfa",
+            web::delete().guard(SessionGuard()).to(mfa::action_disable),
+        )
+}
+
+/// Players can only see and change their own account
+fn is_own_account(player_id: i32, account: &crate::db::models::account::Account) -> bool {
+    player_id == account.account_id
 }
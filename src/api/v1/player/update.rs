@@ -1,5 +1,81 @@
-use actix_web::web;
+use actix_web::*;
+use diesel::prelude::*;
 
-pub async fn action(info: web::Path<String>) -> String {
-    format!("Patch {}", info)
+use crate::cache::bind::invalidate_account_client_binds;
+use crate::crypto::{hash_password, verify_password};
+use crate::db::get_pg_connection;
+use crate::db::models::account::Account;
+use crate::db::schema::accounts as schema;
+use crate::structs::account::{
+    delete_other_sessions, get_session_token, is_locked, record_failed_login,
+};
+use crate::structs::player::{PlayerProfile, UpdatePlayerRequest};
+
+use super::create::MIN_PASSWORD_LENGTH;
+
+pub async fn action(
+    req: HttpRequest,
+    player_id: web::Path<i32>,
+    account: Account,
+    request: web::Json<UpdatePlayerRequest>,
+) -> HttpResponse {
+    if !super::is_own_account(player_id.into_inner(), &account) {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    if is_locked(&account) {
+        return HttpResponse::TooManyRequests().finish();
+    }
+
+    // Changes always need the current password, a stolen session isn't enough.
+    if !account
+        .password
+        .as_ref()
+        .map_or(false, |hash| verify_password(&request.current_password, hash))
+    {
+        return match record_failed_login(&account) {
+            Ok(_) => HttpResponse::Forbidden().finish(),
+            Err(_) => HttpResponse::InternalServerError().finish(),
+        };
+    }
+
+    let password = match &request.password {
+        Some(p) if p.len() < MIN_PASSWORD_LENGTH => {
+            return HttpResponse::BadRequest().finish();
+        }
+        Some(p) => hash_password(p),
+        None => account.password.clone().unwrap(),
+    };
+
+    let e_mail = match &request.e_mail {
+        Some(e) if e.is_empty() => None,
+        Some(e) => Some(e.clone()),
+        None => account.e_mail.clone(),
+    };
+
+    let conn = get_pg_connection();
+    match conn.transaction::<_, diesel::result::Error, _>(|| {
+        let updated = diesel::update(&account)
+            .set((schema::password.eq(password), schema::e_mail.eq(e_mail)))
+            .get_result::<Account>(&conn)?;
+        // A new password logs out every other session, in case one of them was stolen
+        if request.password.is_some() {
+            delete_other_sessions(&account, get_session_token(req.headers()), &conn)?;
+        }
+        Ok(updated)
+    }) {
+        Ok(updated) => {
+            // The game clients are checked again too, not just the sessions
+            if request.password.is_some() {
+                if let Err(e) = invalidate_account_client_binds(account.account_id, &conn) {
+                    warn!(
+                        "Failed to drop cached client binds for account {}, {}",
+                        account.account_id, e
+                    );
+                }
+            }
+            HttpResponse::Ok().json(PlayerProfile::from(updated))
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
 }
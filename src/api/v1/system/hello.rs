@@ -6,19 +6,34 @@ use semver_parser::version::parse;
 use semver_parser::version::Version;
 
 use crate::packets::hello::{HelloReply, HelloRequest};
+use crate::structs::api_config::API_CONFIG_ARC;
 use crate::structs::general::{CLIENT_MAX_VERSION, CLIENT_MIN_VERSION, SERVER_VERSION};
+use crate::structs::market::market_for_game_version;
 
 #[cfg(test)]
 use actix_web::http::StatusCode;
 
 pub async fn action_protobuf(proto_msg: ProtoBuf<HelloRequest>) -> Result<HttpResponse> {
-    let client_version: String = proto_msg.0.client_version;
+    let HelloRequest {
+        client_version,
+        game_version,
+    } = proto_msg.0;
     match check_client_version(client_version) {
-        Ok(reply) => HttpResponse::Ok().protobuf(reply),
+        Ok(mut reply) => {
+            announce_markets(&mut reply, &game_version);
+            HttpResponse::Ok().protobuf(reply)
+        }
         Err(error) => Ok(error),
     }
 }
 
+fn announce_markets(reply: &mut HelloReply, game_version: &str) {
+    if let Some(config) = API_CONFIG_ARC.read().as_ref() {
+        reply.markets = config.config_data.markets.live.clone();
+    }
+    reply.market = market_for_game_version(game_version).unwrap_or_default();
+}
+
 fn check_client_version(client_version: String) -> Result<HelloReply, HttpResponse> {
     let client_version = match parse(&*client_version) {
         Ok(ver) => ver,
@@ -31,6 +46,7 @@ fn check_client_version(client_version: String) -> Result<HelloReply, HttpRespon
     }
     Ok(HelloReply {
         server_version: SERVER_VERSION.to_string(),
+        ..Default::default()
     })
 }
 
@@ -44,16 +60,25 @@ async fn test_hello_request_ok_versions() {
     let tests = vec![
         HelloRequest {
             client_version: CLIENT_MIN_VERSION.to_string(),
+            ..Default::default()
         },
         HelloRequest {
             client_version: CLIENT_MAX_VERSION.to_string(),
+            ..Default::default()
         },
         HelloRequest {
             client_version: min_minor_plus_1.to_string(),
+            ..Default::default()
+        },
+        HelloRequest {
+            client_version: CLIENT_MIN_VERSION.to_string(),
+            game_version: "1.3.3200 rev726".to_string(),
         },
     ];
 
     for hello_request in tests {
+        let game_version = hello_request.game_version.clone();
+
         // Encode Protobuf
         let mut buf = Vec::new();
         hello_request
@@ -65,7 +90,11 @@ async fn test_hello_request_ok_versions() {
         assert_eq!(resp, StatusCode::OK);
         if let Some(protobuf_bytes) = protobuf_bytes {
             let hello_response: HelloReply = HelloReply::decode(protobuf_bytes.as_slice()).unwrap();
-            assert_eq!(hello_response.server_version, SERVER_VERSION.to_string())
+            assert_eq!(hello_response.server_version, SERVER_VERSION.to_string());
+            assert_eq!(
+                hello_response.market,
+                market_for_game_version(&game_version).unwrap_or_default()
+            );
         } else {
             assert!(true, "Didn't get a response body");
         }
@@ -83,9 +112,11 @@ async fn test_hello_request_bad_versions() {
     let tests = vec![
         HelloRequest {
             client_version: min_major_minus_1.to_string(),
+            ..Default::default()
         },
         HelloRequest {
             client_version: max_major_plus_1.to_string(),
+            ..Default::default()
         },
     ];
 
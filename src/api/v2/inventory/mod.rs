@@ -6,9 +6,10 @@ use diesel::{sql_query, ExpressionMethods, RunQueryDsl};
 use itertools::Itertools;
 use uuid::Uuid;
 
-use crate::crypto::{generate_random_alphanum_string, generate_v4_uuid, parse_uuid};
+use crate::cache::blacklist::get_blacklist;
+use crate::cache::inventory::{get_colony_inventory, get_colony_tradables};
+use crate::crypto::{generate_secure_token, generate_v4_uuid, parse_uuid};
 use crate::db::models::bind::ClientBind;
-use crate::db::models::colony_tradable::ColonyTradables;
 use crate::db::models::inventory::Inventory;
 use crate::db::models::inventory_promise::InventoryPromise;
 use crate::db::{get_pg_connection, Ppc};
@@ -19,8 +20,10 @@ use crate::packets::tradable::Tradable;
 use crate::structs::api_config::LockedApiConfig;
 use crate::structs::bank_balance::get_bank_balance;
 use crate::structs::binds::ClientIdGuard;
+use crate::structs::blacklist::Blacklist;
 use crate::structs::colony::validate_ownership_and_fetch;
-use crate::structs::general::DbPkLoadable;
+use crate::structs::colony_mods::{get_loaded_mods, is_mod_loaded};
+use crate::structs::market::is_market_live;
 use crate::traits::item::MakeTradable;
 use actix_web::web::Data;
 
@@ -43,8 +46,21 @@ pub async fn batch(
         Some(value) => value,
     };
 
+    let market = match colony.market() {
+        Some(m) => m,
+        None => return Ok(HttpResponse::BadRequest().finish()),
+    };
+    let live = {
+        let lock = req.app_data::<Data<LockedApiConfig>>().unwrap().read();
+        let config = lock.as_ref().unwrap();
+        is_market_live(&config.config_data.markets, &market)
+    };
+    if !live {
+        return Ok(HttpResponse::UnprocessableEntity().finish());
+    }
+
     // Load the relevant colony data
-    let colony_tradables = ColonyTradables::load_pk(&colony.colony_id);
+    let colony_tradables = get_colony_tradables(&colony.colony_id);
 
     let now = Utc::now().naive_utc();
 
@@ -54,7 +70,8 @@ pub async fn batch(
     let promise = generate_promise(colony.colony_id, conn);
 
     // Load tradable data from Inventory table
-    let tradables = get_inventory_for_colony(colony.colony_id, conn, promise.private_key.clone());
+    let tradables =
+        get_inventory_for_colony(colony.colony_id, &market, conn, promise.private_key.clone());
 
     let bank_balance = if let Ok(balance) = get_bank_balance(colony.colony_id, 0, conn) {
         balance
@@ -82,7 +99,7 @@ fn generate_promise(colony_id: Uuid, conn: &Ppc) -> InventoryPromise {
     let promise = InventoryPromise {
         colony_id,
         promise_id: generate_v4_uuid(),
-        private_key: generate_random_alphanum_string(32),
+        private_key: generate_secure_token(32),
         expiry_date: Utc::now().naive_utc() + Duration::minutes(5),
         activated: false,
     };
@@ -98,12 +115,17 @@ fn generate_promise(colony_id: Uuid, conn: &Ppc) -> InventoryPromise {
     promise
 }
 
-fn get_inventory_for_colony(colony_id: Uuid, conn: &Ppc, secret_key: String) -> Vec<Tradable> {
+fn get_inventory_for_colony(
+    colony_id: Uuid,
+    market: &str,
+    conn: &Ppc,
+    secret_key: String,
+) -> Vec<Tradable> {
     use itsdangerous::default_builder;
     let inventory_query = "SELECT j.*
     FROM colony_tradables as cto \
     LEFT JOIN LATERAL ( \
-    select ct.code as item_code, \
+    select i.item_code, \
     thing_def, \
     quality, \
     quantity, \
@@ -113,9 +135,14 @@ fn get_inventory_for_colony(colony_id: Uuid, conn: &Ppc, secret_key: String) ->
     sell_at, \
     stuff, \
     weight, \
-    version \
+    version, \
+    condition, \
+    parent_item_code, \
+    mod_name, \
+    market \
     from jsonb_array_elements_text(cto.tradables) as ct(code) \
-    INNER JOIN inventory i on i.item_code = ct.code \
+    INNER JOIN inventory i on (i.item_code = ct.code or i.parent_item_code = ct.code) \
+    AND i.market = $2 \
     ) j on true \
     WHERE colony_id = $1";
 
@@ -123,21 +150,40 @@ fn get_inventory_for_colony(colony_id: Uuid, conn: &Ppc, secret_key: String) ->
     // it won't show in the UI since we only iterate rows from the server
     // Saves having to combine two lists on the client side.
 
-    let inventory: Vec<Inventory> = match sql_query(inventory_query)
-        .bind::<diesel::sql_types::Uuid, _>(colony_id)
-        .get_results(conn)
-    {
-        Err(e) => {
-            warn!("Suspicious error when getting inventory, {}", e);
-            Vec::new()
-        }
-        Ok(data) => data,
+    // Try the cache first, fall back to the database if it's unavailable.
+    let inventory: Vec<Inventory> = match get_colony_inventory(&colony_id, market, conn) {
+        Some(data) => data,
+        None => match sql_query(inventory_query)
+            .bind::<diesel::sql_types::Uuid, _>(colony_id)
+            .bind::<diesel::sql_types::Text, _>(market)
+            .get_results(conn)
+        {
+            Err(e) => {
+                warn!("Suspicious error when getting inventory, {}", e);
+                Vec::new()
+            }
+            Ok(data) => data,
+        },
     };
 
+    // Stock from before an item was blacklisted stays in the table, just don't offer it.
+    let blacklist = get_blacklist(conn).unwrap_or_else(|e| {
+        warn!("Unable to load the item blacklist, {}", e);
+        Blacklist::default()
+    });
+
+    // Items from mods the colony doesn't have would only clutter the list.
+    let loaded_mods = get_loaded_mods(&colony_id, conn).unwrap_or_else(|e| {
+        warn!("Unable to load mods for colony {}, {}", colony_id, e);
+        None
+    });
+
     // Create a signer using the default builder, and an arbitrary secret key.
     let signer = default_builder(secret_key).build();
     inventory
         .into_iter()
+        .filter(|inv| !blacklist.contains_inventory(inv))
+        .filter(|inv| is_mod_loaded(&loaded_mods, inv))
         .map(|inv| inv.make_tradable(&signer))
         .collect_vec()
 }
@@ -2,6 +2,769 @@
 extern crate log;
 extern crate clap;
 
-fn main() {
-    info!("Hello")
+use std::convert::TryFrom;
+use std::fs;
+use std::process::exit;
+use std::str::FromStr;
+
+use chrono::Utc;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use diesel::prelude::*;
+use itertools::Itertools;
+use serde_json::Value;
+use steamid_ng::SteamID;
+use strum::IntoEnumIterator;
+
+use deepfreeze::cache::bind::invalidate_account_client_binds;
+use deepfreeze::cache::config::{get_latest_api_config, get_latest_stock_config};
+use deepfreeze::cache::init_cache;
+use deepfreeze::config::load_config;
+use deepfreeze::crypto::parse_uuid;
+use deepfreeze::db::get_pg_connection;
+use deepfreeze::db::models::bank::BankBalance;
+use deepfreeze::db::models::blocked_steam_accounts::BlockedSteamAccount;
+use deepfreeze::db::models::order::Order;
+use deepfreeze::jtd::stock_config::StockConfig;
+use deepfreeze::packets::bank::BankLedgerKindEnum;
+use deepfreeze::packets::common::CurrencyEnum;
+use deepfreeze::packets::order::{OrderFailureReasonEnum, OrderStatusEnum};
+use deepfreeze::routines::market::perform_maintenance;
+use deepfreeze::routines::market::simulate::simulate_market;
+use deepfreeze::structs::account::find_account_for_username;
+use deepfreeze::structs::admin::{
+    insert_config_version, load_config_version, validate_config, ConfigKind,
+};
+use deepfreeze::structs::bank_balance::get_bank_balance;
+use deepfreeze::structs::bank_ledger::{apply_ledger_entry, reconcile_bank_balances};
+use deepfreeze::structs::currency::currency_key;
+use deepfreeze::structs::general::SERVER_VERSION;
+use deepfreeze::structs::order::invalidate_order_inventory;
+use deepfreeze::traits::item::Rollback;
+use deepfreeze::traits::numerical::CanRound;
+
+/// Matches `BindErrorReason::SteamIdBlocked` in `get_steam_id_block_reason`
+const BLOCK_REASON_BLOCKED: i32 = 3;
+
+type CommandResult = Result<(), String>;
+
+#[tokio::main]
+async fn main() {
+    let version = SERVER_VERSION.to_string();
+    let kind_arg = Arg::with_name("kind")
+        .help("Which configuration to use")
+        .required(true)
+        .possible_values(&["api", "stock"]);
+
+    let matches = App::new("configure")
+        .version(&*version)
+        .about("Operator tools for the ISE API Server")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("config")
+                .about("Import and export api_config and stock_config versions")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("export")
+                        .about("Print a configuration version as JSON")
+                        .arg(kind_arg.clone())
+                        .arg(
+                            Arg::with_name("version")
+                                .help("Configuration version, defaults to the latest"),
+                        )
+                        .arg(
+                            Arg::with_name("output")
+                                .long("output")
+                                .short("o")
+                                .takes_value(true)
+                                .help("Write to a file instead of stdout"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("import")
+                        .about("Validate a JSON file and insert it as the next version")
+                        .arg(kind_arg.clone())
+                        .arg(Arg::with_name("file").required(true)),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("offline")
+                .about("Take the API offline, or bring it back online")
+                .arg(
+                    Arg::with_name("state")
+                        .required(true)
+                        .possible_values(&["on", "off"]),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("steam")
+                .about("Manage blocked Steam accounts")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("block")
+                        .arg(
+                            Arg::with_name("steam_id")
+                                .required(true)
+                                .help("SteamID64 or Steam3 ID"),
+                        )
+                        .arg(
+                            Arg::with_name("reason")
+                                .long("reason")
+                                .takes_value(true)
+                                .help("Reason code, defaults to 3 (blocked)"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("unblock").arg(
+                        Arg::with_name("steam_id")
+                            .required(true)
+                            .help("SteamID64 or Steam3 ID"),
+                    ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("maintenance")
+                .about("Database maintenance")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(SubCommand::with_name("run").about("Run maintenance now on this node"))
+                .subcommand(
+                    SubCommand::with_name("simulate")
+                        .about(
+                            "Show what maintenance would do to prices and stock, nothing is saved",
+                        )
+                        .arg(
+                            Arg::with_name("market")
+                                .long("market")
+                                .takes_value(true)
+                                .required(true)
+                                .help("Market to simulate, the major game version e.g. 1.3"),
+                        )
+                        .arg(
+                            Arg::with_name("file")
+                                .long("file")
+                                .takes_value(true)
+                                .help("Stock configuration to try, defaults to the latest version"),
+                        )
+                        .arg(
+                            Arg::with_name("days")
+                                .long("days")
+                                .takes_value(true)
+                                .default_value("1"),
+                        )
+                        .arg(
+                            Arg::with_name("seed")
+                                .long("seed")
+                                .takes_value(true)
+                                .help("RNG seed, use the same one to compare configurations"),
+                        )
+                        .arg(
+                            Arg::with_name("all")
+                                .long("all")
+                                .help("List every item, not just the ones that change"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("colony")
+                .about("Inspect a colony")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("orders").arg(Arg::with_name("colony_id").required(true)),
+                )
+                .subcommand(
+                    SubCommand::with_name("balance")
+                        .arg(Arg::with_name("colony_id").required(true)),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("bank")
+                .about("Manage colony bank balances")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("adjust")
+                        .about("Add to or take from a balance, recorded on the ledger")
+                        .arg(Arg::with_name("colony_id").required(true))
+                        .arg(
+                            Arg::with_name("currency")
+                                .required(true)
+                                .possible_values(&["utc", "gold"]),
+                        )
+                        .arg(
+                            Arg::with_name("amount")
+                                .required(true)
+                                .allow_hyphen_values(true)
+                                .help("Negative to take money away"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("reconcile")
+                        .about("List balances that don't match their ledger"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("order")
+                .about("Manage orders")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("reverse")
+                        .about("Undo an order's stock and bank changes")
+                        .arg(Arg::with_name("order_id").required(true)),
+                )
+                .subcommand(
+                    SubCommand::with_name("failures")
+                        .about("Count failed deliveries by reason")
+                        .arg(
+                            Arg::with_name("days")
+                                .long("days")
+                                .takes_value(true)
+                                .default_value("30"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("account")
+                .about("Manage player accounts")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("admin")
+                        .about("Grant access to the admin API")
+                        .arg(Arg::with_name("username").required(true))
+                        .arg(
+                            Arg::with_name("revoke")
+                                .long("revoke")
+                                .help("Remove access instead"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("deactivate")
+                        .about("Stop an account and its game clients using the API")
+                        .arg(Arg::with_name("username").required(true))
+                        .arg(
+                            Arg::with_name("reactivate")
+                                .long("reactivate")
+                                .help("Allow the account back in instead"),
+                        ),
+                ),
+        )
+        .get_matches();
+
+    let settings = match load_config() {
+        Ok(settings) => settings,
+        Err(e) => {
+            println!("Unable to parse settings: {}", e);
+            exit(1);
+        }
+    };
+
+    simple_logger::SimpleLogger::new()
+        .with_utc_timestamps()
+        .with_level(
+            log::Level::from_str(&*settings.logging.level)
+                .unwrap()
+                .to_level_filter(),
+        )
+        .init()
+        .expect("Unable to start logging!");
+
+    // Anything we change must be dropped from the cache too.
+    if let Err(e) = init_cache(&settings.redis.connection_string) {
+        warn!("Unable to create Redis cache client: {}", e);
+    }
+
+    let result = match matches.subcommand() {
+        ("config", Some(m)) => match m.subcommand() {
+            ("export", Some(m)) => config_export(m),
+            ("import", Some(m)) => config_import(m),
+            _ => unreachable!(),
+        },
+        ("offline", Some(m)) => set_offline(m.value_of("state") == Some("on")),
+        ("steam", Some(m)) => match m.subcommand() {
+            ("block", Some(m)) => steam_block(m),
+            ("unblock", Some(m)) => steam_unblock(m),
+            _ => unreachable!(),
+        },
+        ("maintenance", Some(m)) => match m.subcommand() {
+            ("run", Some(_)) => run_maintenance().await,
+            ("simulate", Some(m)) => simulate_maintenance(m),
+            _ => unreachable!(),
+        },
+        ("colony", Some(m)) => match m.subcommand() {
+            ("orders", Some(m)) => colony_orders(m),
+            ("balance", Some(m)) => colony_balance(m),
+            _ => unreachable!(),
+        },
+        ("bank", Some(m)) => match m.subcommand() {
+            ("adjust", Some(m)) => bank_adjust(m),
+            ("reconcile", Some(_)) => bank_reconcile(),
+            _ => unreachable!(),
+        },
+        ("order", Some(m)) => match m.subcommand() {
+            ("reverse", Some(m)) => order_reverse(m),
+            ("failures", Some(m)) => order_failures(m),
+            _ => unreachable!(),
+        },
+        ("account", Some(m)) => match m.subcommand() {
+            ("admin", Some(m)) => account_admin(m),
+            ("deactivate", Some(m)) => account_deactivate(m),
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+}
+
+fn get_kind(matches: &ArgMatches) -> ConfigKind {
+    match matches.value_of("kind") {
+        Some("stock") => ConfigKind::Stock,
+        _ => ConfigKind::Api,
+    }
+}
+
+fn config_export(matches: &ArgMatches) -> CommandResult {
+    let version = match matches.value_of("version") {
+        Some(v) => Some(i32::from_str(v).map_err(|_| format!("Invalid version {}", v))?),
+        None => None,
+    };
+
+    let conn = &get_pg_connection();
+    let document = load_config_version(get_kind(matches), version, conn)
+        .map_err(|e| format!("{}", e))?
+        .ok_or("Configuration version not found")?;
+
+    let data = serde_json::to_string_pretty(&document.config_data).unwrap();
+    match matches.value_of("output") {
+        Some(path) => {
+            fs::write(path, data).map_err(|e| format!("Unable to write {}, {}", path, e))?;
+            println!("Exported version {} to {}", document.version, path);
+        }
+        None => println!("{}", data),
+    }
+    Ok(())
+}
+
+fn config_import(matches: &ArgMatches) -> CommandResult {
+    let path = matches.value_of("file").unwrap();
+    let data = fs::read_to_string(path).map_err(|e| format!("Unable to read {}, {}", path, e))?;
+    let config_data: Value =
+        serde_json::from_str(&data).map_err(|e| format!("{} is not valid JSON, {}", path, e))?;
+    insert_validated_config(get_kind(matches), &config_data)
+}
+
+fn insert_validated_config(kind: ConfigKind, config_data: &Value) -> CommandResult {
+    let validation = validate_config(kind, config_data);
+    if !validation.valid {
+        for error in validation.errors {
+            eprintln!(
+                "Invalid value at {} (schema {})",
+                error.instance_path, error.schema_path
+            );
+        }
+        if let Some(message) = validation.message {
+            eprintln!("{}", message);
+        }
+        return Err("Configuration failed validation".to_owned());
+    }
+
+    let conn = &get_pg_connection();
+    let version = insert_config_version(kind, config_data, conn).map_err(|e| format!("{}", e))?;
+    println!("Inserted {:?} config version {}", kind, version);
+    Ok(())
+}
+
+fn set_offline(offline: bool) -> CommandResult {
+    let conn = &get_pg_connection();
+    let mut document = load_config_version(ConfigKind::Api, None, conn)
+        .map_err(|e| format!("{}", e))?
+        .ok_or("There is no API configuration yet, import one first")?;
+
+    document.config_data["api"]["force_offline"] = Value::Bool(offline);
+    insert_validated_config(ConfigKind::Api, &document.config_data)?;
+    println!(
+        "The API will go {} within a minute",
+        if offline { "offline" } else { "online" }
+    );
+    Ok(())
+}
+
+/// Steam IDs are stored as SteamID64
+fn parse_steam_id(value: &str) -> Result<String, String> {
+    let steam_id = match u64::from_str(value) {
+        Ok(id) => SteamID::from(id),
+        Err(_) => SteamID::from_steam3(value).map_err(|_| format!("Invalid Steam ID {}", value))?,
+    };
+    Ok(u64::from(steam_id).to_string())
+}
+
+fn steam_block(matches: &ArgMatches) -> CommandResult {
+    use deepfreeze::db::schema::blocked_steam_accounts as schema;
+    let steam_id = parse_steam_id(matches.value_of("steam_id").unwrap())?;
+    let reason = match matches.value_of("reason") {
+        Some(r) => i32::from_str(r).map_err(|_| format!("Invalid reason {}", r))?,
+        None => BLOCK_REASON_BLOCKED,
+    };
+
+    let conn = &get_pg_connection();
+    diesel::insert_into(schema::table)
+        .values(BlockedSteamAccount {
+            steam_id: steam_id.clone(),
+            reason,
+            date_added: Utc::now().naive_utc(),
+        })
+        .on_conflict(schema::steam_id)
+        .do_update()
+        .set(schema::reason.eq(reason))
+        .execute(conn)
+        .map_err(|e| format!("{}", e))?;
+
+    println!("Blocked Steam ID {} with reason {}", steam_id, reason);
+    Ok(())
+}
+
+fn steam_unblock(matches: &ArgMatches) -> CommandResult {
+    use deepfreeze::db::schema::blocked_steam_accounts as schema;
+    let steam_id = parse_steam_id(matches.value_of("steam_id").unwrap())?;
+
+    let conn = &get_pg_connection();
+    let count = diesel::delete(schema::table.filter(schema::steam_id.eq(&steam_id)))
+        .execute(conn)
+        .map_err(|e| format!("{}", e))?;
+
+    if count == 0 {
+        return Err(format!("Steam ID {} is not blocked", steam_id));
+    }
+    println!("Unblocked Steam ID {}", steam_id);
+    Ok(())
+}
+
+async fn run_maintenance() -> CommandResult {
+    let api_config = {
+        let conn = &get_pg_connection();
+        get_latest_api_config(conn).map_err(|e| format!("Unable to load API config, {}", e))?
+    };
+    perform_maintenance(&api_config).await;
+    Ok(())
+}
+
+fn simulate_maintenance(matches: &ArgMatches) -> CommandResult {
+    let market = matches.value_of("market").unwrap();
+    let days = matches.value_of("days").unwrap();
+    let days = u32::from_str(days).map_err(|_| format!("Invalid number of days {}", days))?;
+    let seed = match matches.value_of("seed") {
+        Some(s) => u64::from_str(s).map_err(|_| format!("Invalid seed {}", s))?,
+        None => rand::random::<u64>(),
+    };
+
+    let conn = &get_pg_connection();
+    let config: StockConfig = match matches.value_of("file") {
+        Some(path) => {
+            let data =
+                fs::read_to_string(path).map_err(|e| format!("Unable to read {}, {}", path, e))?;
+            let config_data: Value = serde_json::from_str(&data)
+                .map_err(|e| format!("{} is not valid JSON, {}", path, e))?;
+            let validation = validate_config(ConfigKind::Stock, &config_data);
+            if !validation.valid {
+                for error in validation.errors {
+                    eprintln!(
+                        "Invalid value at {} (schema {})",
+                        error.instance_path, error.schema_path
+                    );
+                }
+                if let Some(message) = validation.message {
+                    eprintln!("{}", message);
+                }
+                return Err("Configuration failed validation".to_owned());
+            }
+            serde_json::from_value(config_data).map_err(|e| format!("{}", e))?
+        }
+        None => {
+            get_latest_stock_config(conn)
+                .map_err(|e| format!("Unable to load stock config, {}", e))?
+                .config_data
+        }
+    };
+
+    let report =
+        simulate_market(&config, market, days, seed, conn).map_err(|e| format!("{}", e))?;
+
+    println!(
+        "{:<32}  {:<32}  {:>8}  {:>8}  {:>10}  {:>10}  {:>10}  {:>10}",
+        "Item", "ThingDef", "Qty", "New Qty", "Buy", "New Buy", "Sell", "New Sell"
+    );
+    for item in report.items.iter() {
+        if !matches.is_present("all") && !item.changed() {
+            continue;
+        }
+        println!(
+            "{:<32}  {:<32}  {:>8}  {:>8}  {:>10}  {:>10}  {:>10}  {:>10}",
+            item.item_code,
+            item.thing_def,
+            item.quantity_before,
+            item.quantity_after,
+            item.buy_at_before,
+            item.buy_at_after,
+            item.sell_at_before,
+            item.sell_at_after
+        );
+    }
+
+    println!(
+        "Simulated {} days over {} items with seed {}, {} changed",
+        report.days,
+        report.items.len(),
+        seed,
+        report.items.iter().filter(|i| i.changed()).count()
+    );
+    println!(
+        "{} items have no pricing threshold, {} have no restock threshold",
+        report.no_price_threshold, report.no_stock_threshold
+    );
+    println!(
+        "Stock value at our sell price {} -> {} ({})",
+        report.stock_value_before.round_2dp(),
+        report.stock_value_after.round_2dp(),
+        (&report.stock_value_after - &report.stock_value_before).round_2dp()
+    );
+    println!(
+        "Stock value at our buy price {} -> {} ({})",
+        report.buy_value_before.round_2dp(),
+        report.buy_value_after.round_2dp(),
+        (&report.buy_value_after - &report.buy_value_before).round_2dp()
+    );
+    Ok(())
+}
+
+fn get_colony_id(matches: &ArgMatches) -> Result<uuid::Uuid, String> {
+    let value = matches.value_of("colony_id").unwrap();
+    parse_uuid(value).map_err(|_| format!("Invalid colony ID {}", value))
+}
+
+fn colony_orders(matches: &ArgMatches) -> CommandResult {
+    use deepfreeze::db::schema::orders as schema;
+    let colony_id = get_colony_id(matches)?;
+
+    let conn = &get_pg_connection();
+    let orders: Vec<Order> = schema::table
+        .filter(schema::colony_id.eq(colony_id))
+        .order(schema::create_date.asc())
+        .get_results(conn)
+        .map_err(|e| format!("{}", e))?;
+
+    println!(
+        "{:<36}  {:<14}  {:>10}  {:>10}  {:>8}  {:>8}  {:>10}  {}",
+        "Order", "Status", "Start", "End", "WTS", "WTB", "Balance", "Created"
+    );
+    for order in orders.iter() {
+        let status = OrderStatusEnum::try_from(order.status)
+            .map(|s| format!("{:?}", s))
+            .unwrap_or_else(|_| order.status.to_string());
+        println!(
+            "{:<36}  {:<14}  {:>10}  {:>10}  {:>8}  {:>8}  {:>10}  {}",
+            order.order_id,
+            status,
+            order.start_tick,
+            order.end_tick,
+            order.manifest.wts.len(),
+            order.manifest.wtb.len(),
+            order.manifest.balance_adjustment,
+            order.create_date
+        );
+    }
+    println!("{} orders", orders.len());
+    Ok(())
+}
+
+fn colony_balance(matches: &ArgMatches) -> CommandResult {
+    use deepfreeze::db::schema::bank_balances as schema;
+    let colony_id = get_colony_id(matches)?;
+
+    let conn = &get_pg_connection();
+    let balances: Vec<BankBalance> = schema::table
+        .filter(schema::colony_id.eq(colony_id))
+        .order(schema::currency.asc())
+        .get_results(conn)
+        .map_err(|e| format!("{}", e))?;
+
+    if balances.is_empty() {
+        println!("Colony {} has no bank balances", colony_id);
+    }
+    for balance in balances {
+        let currency = CurrencyEnum::try_from(balance.currency)
+            .map(|c| format!("{:?}", c))
+            .unwrap_or_else(|_| balance.currency.to_string());
+        println!("{:<8} {:>10}", currency, balance.balance);
+    }
+    Ok(())
+}
+
+fn bank_adjust(matches: &ArgMatches) -> CommandResult {
+    let colony_id = get_colony_id(matches)?;
+    let currency = CurrencyEnum::iter()
+        .find(|c| currency_key(*c) == matches.value_of("currency").unwrap())
+        .unwrap();
+    let value = matches.value_of("amount").unwrap();
+    let amount = i32::from_str(value).map_err(|_| format!("Invalid amount {}", value))?;
+
+    let conn = &get_pg_connection();
+    let balance = conn
+        .build_transaction()
+        .read_committed()
+        .run::<_, diesel::result::Error, _>(|| {
+            let mut bank_balance = get_bank_balance(colony_id, currency.into(), conn)
+                .map_err(|_| diesel::result::Error::RollbackTransaction)?;
+            apply_ledger_entry(
+                &mut bank_balance,
+                amount,
+                BankLedgerKindEnum::AdminAdjustment,
+                None,
+                conn,
+            )
+            .map_err(|_| diesel::result::Error::RollbackTransaction)?;
+            Ok(bank_balance.balance)
+        })
+        .map_err(|e| format!("Failed to adjust balance, {}", e))?;
+
+    println!(
+        "Colony {} {:?} balance is now {}",
+        colony_id, currency, balance
+    );
+    Ok(())
+}
+
+fn bank_reconcile() -> CommandResult {
+    let conn = &get_pg_connection();
+    let mismatches = reconcile_bank_balances(conn).map_err(|e| format!("{}", e))?;
+
+    println!(
+        "{:<36}  {:<8}  {:>10}  {:>10}",
+        "Colony", "Currency", "Balance", "Ledger"
+    );
+    for m in mismatches.iter() {
+        println!(
+            "{:<36}  {:<8}  {:>10}  {:>10}",
+            m.colony_id, m.currency, m.balance, m.ledger_total
+        );
+    }
+    println!("{} mismatched balances", mismatches.len());
+    Ok(())
+}
+
+fn order_reverse(matches: &ArgMatches) -> CommandResult {
+    use deepfreeze::db::schema::orders as schema;
+    let value = matches.value_of("order_id").unwrap();
+    let order_id = parse_uuid(value).map_err(|_| format!("Invalid order ID {}", value))?;
+
+    let conn = &get_pg_connection();
+    let order = conn
+        .build_transaction()
+        .read_committed()
+        .run::<_, diesel::result::Error, _>(|| {
+            // Locked and checked here so the colony can't move it on at the same time
+            let mut order: Order = schema::table.find(order_id).for_update().get_result(conn)?;
+            if order.status == i32::from(OrderStatusEnum::Reversed)
+                || order.status == i32::from(OrderStatusEnum::Failed)
+                || order.status == i32::from(OrderStatusEnum::Cancelled)
+                || order.status == i32::from(OrderStatusEnum::Expired)
+            {
+                return Ok(Err(
+                    "Order has already been reversed, failed, cancelled or expired".to_owned(),
+                ));
+            }
+            order
+                .rollback(conn)
+                .map_err(|_| diesel::result::Error::RollbackTransaction)?;
+            Ok(Ok(order))
+        })
+        .map_err(|e| match e {
+            diesel::NotFound => "Order not found".to_owned(),
+            e => format!("Failed to reverse order, {}", e),
+        })??;
+    invalidate_order_inventory(&order);
+
+    println!("Reversed order {}", order_id);
+    Ok(())
+}
+
+fn order_failures(matches: &ArgMatches) -> CommandResult {
+    use deepfreeze::db::schema::orders as schema;
+    let value = matches.value_of("days").unwrap();
+    let days = i64::from_str(value).map_err(|_| format!("Invalid number of days {}", value))?;
+    let since = Utc::now().naive_utc() - chrono::Duration::days(days);
+
+    let conn = &get_pg_connection();
+    let reasons: Vec<Option<i32>> = schema::table
+        .select(schema::failure_reason)
+        .filter(schema::status.eq(i32::from(OrderStatusEnum::Failed)))
+        .filter(schema::update_date.ge(since))
+        .get_results(conn)
+        .map_err(|e| format!("{}", e))?;
+
+    // Orders that failed before the reason was recorded don't have one
+    let counts = reasons
+        .iter()
+        .map(|r| {
+            r.map(|r| {
+                OrderFailureReasonEnum::try_from(r)
+                    .map(|r| format!("{:?}", r))
+                    .unwrap_or_else(|_| r.to_string())
+            })
+            .unwrap_or_else(|| "Not recorded".to_owned())
+        })
+        .counts();
+
+    println!("{:<14}  {:>8}", "Reason", "Orders");
+    for (reason, count) in counts.iter().sorted_by_key(|(_, c)| std::cmp::Reverse(**c)) {
+        println!("{:<14}  {:>8}", reason, count);
+    }
+    println!("{} failed orders in the last {} days", reasons.len(), days);
+    Ok(())
+}
+
+fn account_admin(matches: &ArgMatches) -> CommandResult {
+    use deepfreeze::db::schema::accounts as schema;
+    let username = matches.value_of("username").unwrap();
+    let admin = !matches.is_present("revoke");
+    let account = find_account_for_username(username)
+        .ok_or_else(|| format!("Account {} not found", username))?;
+
+    let conn = &get_pg_connection();
+    diesel::update(&account)
+        .set(schema::admin.eq(admin))
+        .execute(conn)
+        .map_err(|e| format!("{}", e))?;
+
+    println!(
+        "{} account {}",
+        if admin {
+            "Granted admin to"
+        } else {
+            "Revoked admin from"
+        },
+        username
+    );
+    Ok(())
+}
+
+fn account_deactivate(matches: &ArgMatches) -> CommandResult {
+    use deepfreeze::db::schema::accounts as schema;
+    let username = matches.value_of("username").unwrap();
+    let active = matches.is_present("reactivate");
+    let account = find_account_for_username(username)
+        .ok_or_else(|| format!("Account {} not found", username))?;
+
+    let conn = &get_pg_connection();
+    diesel::update(&account)
+        .set(schema::active.eq(active))
+        .execute(conn)
+        .map_err(|e| format!("{}", e))?;
+    invalidate_account_client_binds(account.account_id, conn).map_err(|e| format!("{}", e))?;
+
+    println!(
+        "{} account {}",
+        if active { "Reactivated" } else { "Deactivated" },
+        username
+    );
+    Ok(())
 }
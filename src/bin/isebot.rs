@@ -1,7 +1,19 @@
+use bigdecimal::BigDecimal;
 use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use deepfreeze::cache::init_cache;
 use deepfreeze::config::{load_config, CONFIG};
 use deepfreeze::db::get_pg_connection;
 use deepfreeze::db::models::colony::Colony;
+use deepfreeze::packets::inventory::ModerationStatusEnum;
+use deepfreeze::structs::blacklist::{
+    add_blacklist_entry, get_blacklist_entries, remove_blacklist_entry, BlacklistEntryRequest,
+};
+use deepfreeze::structs::colony_mods::get_mod_statistics;
+use deepfreeze::structs::pending_inventory::{
+    approve_pending_inventory, get_pending_inventory, reject_pending_inventory,
+    set_pending_base_value,
+};
+use deepfreeze::structs::threshold_miss;
 use diesel::dsl::{count, sum};
 use diesel::prelude::*;
 use diesel::QueryDsl;
@@ -9,7 +21,7 @@ use serenity::async_trait;
 use serenity::client::{parse_token, Client, Context, EventHandler};
 use serenity::framework::standard::{
     macros::{command, group},
-    CommandResult, StandardFramework,
+    Args, CommandResult, StandardFramework,
 };
 use serenity::model::channel::Message;
 use std::process::exit;
@@ -37,6 +49,11 @@ async fn main() {
         .init()
         .expect("Unable to start logging!");
 
+    // So blacklist changes are picked up by the servers straight away.
+    if let Err(e) = init_cache(&settings.redis.connection_string) {
+        log::warn!("Unable to create Redis cache client: {}", e);
+    }
+
     let token_data = parse_token(&settings.discord.auth_token).expect("Invalid auth token");
 
     let bot_user_id = token_data.bot_user_id;
@@ -66,7 +83,16 @@ async fn main() {
     get_orders_today,
     get_orders_total,
     get_total_stock,
-    get_online_colonies
+    get_online_colonies,
+    get_threshold_misses,
+    get_blacklist,
+    add_blacklist,
+    remove_blacklist,
+    get_pending,
+    approve_pending,
+    reject_pending,
+    set_pending_value,
+    get_top_mods
 )]
 struct General;
 
@@ -209,3 +235,319 @@ async fn get_total_stock(ctx: &Context, msg: &Message) -> CommandResult {
 
     Ok(())
 }
+
+#[command("thresholdmisses")]
+async fn get_threshold_misses(ctx: &Context, msg: &Message) -> CommandResult {
+    let conn = &mut get_pg_connection();
+
+    let misses =
+        threshold_miss::get_threshold_misses(conn).expect("Failed to read threshold misses");
+
+    if misses.is_empty() {
+        msg.reply(ctx, "Every item is covered by the stock configuration")
+            .await?;
+        return Ok(());
+    }
+
+    let missing_price = misses.iter().filter(|m| m.missing_price).count();
+    let missing_stock = misses.iter().filter(|m| m.missing_stock).count();
+    let mut reply = format!(
+        "{} items have no pricing threshold and {} have no restock threshold",
+        missing_price, missing_stock
+    );
+    for miss in misses.iter().take(10) {
+        reply.push_str(&*format!(
+            "\n{} {} ({}) worth {}",
+            miss.market, miss.thing_def, miss.item_code, miss.base_value
+        ));
+    }
+    if misses.len() > 10 {
+        reply.push_str(&*format!("\n...and {} more", misses.len() - 10));
+    }
+
+    msg.reply(ctx, reply).await?;
+
+    Ok(())
+}
+
+fn is_bot_admin(msg: &Message) -> bool {
+    CONFIG.get().map_or(false, |c| {
+        c.discord.admin_user_ids.contains(&msg.author.id.0)
+    })
+}
+
+#[command("blacklist")]
+async fn get_blacklist(ctx: &Context, msg: &Message) -> CommandResult {
+    let conn = &mut get_pg_connection();
+
+    let entries = get_blacklist_entries(conn).expect("Failed to read the item blacklist");
+
+    if entries.is_empty() {
+        msg.reply(ctx, "Nothing is blacklisted").await?;
+        return Ok(());
+    }
+
+    let mut reply = format!("{} blacklist entries:", entries.len());
+    for entry in entries.iter().take(10) {
+        reply.push_str(&*format!(
+            "\n#{} item_code={} thing_def={} stuff={}, {}",
+            entry.entry_id,
+            entry.item_code.as_deref().unwrap_or("*"),
+            entry.thing_def.as_deref().unwrap_or("*"),
+            entry.stuff.as_deref().unwrap_or("*"),
+            entry.reason
+        ));
+    }
+    if entries.len() > 10 {
+        reply.push_str(&*format!("\n...and {} more", entries.len() - 10));
+    }
+
+    msg.reply(ctx, reply).await?;
+
+    Ok(())
+}
+
+#[command("blacklistadd")]
+async fn add_blacklist(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    if !is_bot_admin(msg) {
+        msg.reply(ctx, "You're not allowed to change the blacklist")
+            .await?;
+        return Ok(());
+    }
+
+    let request = match args.rest().parse::<BlacklistEntryRequest>() {
+        Ok(r) => r,
+        Err(e) => {
+            msg.reply(
+                ctx,
+                format!(
+                    "{}, e.g. blacklistadd thing_def=Exploit_* stuff=Gold duplicates gold",
+                    e
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let conn = &mut get_pg_connection();
+    match add_blacklist_entry(request, conn).expect("Failed to add blacklist entry") {
+        Some(entry) => {
+            log::info!(
+                "{} added blacklist entry {}",
+                msg.author.name,
+                entry.entry_id
+            );
+            msg.reply(ctx, format!("Added blacklist entry #{}", entry.entry_id))
+                .await?;
+        }
+        None => {
+            msg.reply(ctx, "That entry would match everything").await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[command("blacklistremove")]
+async fn remove_blacklist(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    if !is_bot_admin(msg) {
+        msg.reply(ctx, "You're not allowed to change the blacklist")
+            .await?;
+        return Ok(());
+    }
+
+    let entry_id = match args.single::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            msg.reply(ctx, "Which entry? e.g. blacklistremove 3")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let conn = &mut get_pg_connection();
+    let removed = remove_blacklist_entry(entry_id, conn).expect("Failed to remove blacklist entry");
+
+    if removed > 0 {
+        log::info!("{} removed blacklist entry {}", msg.author.name, entry_id);
+        msg.reply(ctx, format!("Removed blacklist entry #{}", entry_id))
+            .await?;
+    } else {
+        msg.reply(ctx, format!("There's no blacklist entry #{}", entry_id))
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[command("pending")]
+async fn get_pending(ctx: &Context, msg: &Message) -> CommandResult {
+    let conn = &mut get_pg_connection();
+
+    let pending = get_pending_inventory(ModerationStatusEnum::Pending, conn)
+        .expect("Failed to read the moderation queue");
+
+    if pending.is_empty() {
+        msg.reply(ctx, "Nothing is waiting to be approved").await?;
+        return Ok(());
+    }
+
+    let mut reply = format!("{} items are waiting to be approved:", pending.len());
+    for item in pending.iter().take(10) {
+        reply.push_str(&*format!(
+            "\n{} {} {} worth {} with {} votes",
+            item.market, item.version, item.thing_def, item.base_value, item.votes
+        ));
+    }
+    if pending.len() > 10 {
+        reply.push_str(&*format!("\n...and {} more", pending.len() - 10));
+    }
+
+    msg.reply(ctx, reply).await?;
+
+    Ok(())
+}
+
+#[command("approve")]
+async fn approve_pending(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    if !is_bot_admin(msg) {
+        msg.reply(ctx, "You're not allowed to approve items")
+            .await?;
+        return Ok(());
+    }
+
+    let (market, version) = match (args.single::<String>(), args.single::<String>()) {
+        (Ok(m), Ok(v)) => (m, v),
+        _ => {
+            msg.reply(ctx, "Which item? e.g. approve <market> <version>")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let conn = &mut get_pg_connection();
+    let reviewer = format!("discord:{}", msg.author.name);
+    match approve_pending_inventory(&market, &version, &reviewer, conn)
+        .expect("Failed to approve item")
+    {
+        Some(item) => {
+            msg.reply(
+                ctx,
+                format!(
+                    "Added {} to the {} inventory at {}",
+                    item.thing_def, item.market, item.base_value
+                ),
+            )
+            .await?;
+        }
+        None => {
+            msg.reply(ctx, format!("{} isn't waiting to be approved", version))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[command("reject")]
+async fn reject_pending(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    if !is_bot_admin(msg) {
+        msg.reply(ctx, "You're not allowed to reject items").await?;
+        return Ok(());
+    }
+
+    let (market, version) = match (args.single::<String>(), args.single::<String>()) {
+        (Ok(m), Ok(v)) => (m, v),
+        _ => {
+            msg.reply(ctx, "Which item? e.g. reject <market> <version>")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let conn = &mut get_pg_connection();
+    let reviewer = format!("discord:{}", msg.author.name);
+    match reject_pending_inventory(&market, &version, &reviewer, conn)
+        .expect("Failed to reject item")
+    {
+        Some(item) => {
+            msg.reply(ctx, format!("Rejected {}", item.thing_def))
+                .await?;
+        }
+        None => {
+            msg.reply(ctx, format!("{} isn't waiting to be approved", version))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[command("setvalue")]
+async fn set_pending_value(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    if !is_bot_admin(msg) {
+        msg.reply(ctx, "You're not allowed to change items").await?;
+        return Ok(());
+    }
+
+    let (market, version, base_value) = match (
+        args.single::<String>(),
+        args.single::<String>(),
+        args.single::<f32>(),
+    ) {
+        (Ok(m), Ok(v), Ok(b)) if b > 0f32 => (m, v, b),
+        _ => {
+            msg.reply(ctx, "e.g. setvalue <market> <version> <base value>")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let conn = &mut get_pg_connection();
+    match set_pending_base_value(&market, &version, BigDecimal::from(base_value), conn)
+        .expect("Failed to update item")
+    {
+        Some(item) => {
+            msg.reply(
+                ctx,
+                format!("{} is now worth {}", item.thing_def, item.base_value),
+            )
+            .await?;
+        }
+        None => {
+            msg.reply(ctx, format!("{} isn't waiting to be approved", version))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[command("topmods")]
+async fn get_top_mods(ctx: &Context, msg: &Message) -> CommandResult {
+    let conn = &mut get_pg_connection();
+
+    let stats = get_mod_statistics(conn).expect("Failed to read colony mods");
+
+    if stats.mods.is_empty() {
+        msg.reply(ctx, "No colonies have sent a mod list").await?;
+        return Ok(());
+    }
+
+    let mut reply = format!(
+        "{} colonies use {} different mods, the most popular are:",
+        stats.colonies,
+        stats.mods.len()
+    );
+    for m in stats.mods.iter().take(10) {
+        reply.push_str(&*format!(
+            "\n{} loaded by {} colonies, {} items",
+            m.mod_name, m.colonies, m.items
+        ));
+    }
+
+    msg.reply(ctx, reply).await?;
+
+    Ok(())
+}
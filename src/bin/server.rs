@@ -13,14 +13,14 @@ use log::Level;
 use tokio::spawn;
 
 use deepfreeze::api;
+use deepfreeze::cache::{init_cache, ping_cache};
 use deepfreeze::config::load_config;
-use deepfreeze::db::create_redis_connection_pool;
 use deepfreeze::db::get_pg_connection;
 use deepfreeze::db::models::api_config::ApiConfig;
-use deepfreeze::db::RS_POOL;
 use deepfreeze::decompress_payload::DecompressPayload;
 use deepfreeze::jtd::api_config::structure::{ApiConfigData, ApiConfigDataApi};
 use deepfreeze::request_helpers::ProtoBufConfig;
+use deepfreeze::routines::orders::poll_stale_orders;
 use deepfreeze::routines::system::poll_api_online_status;
 use deepfreeze::structs::api_config::{ApiConfigStatus, API_CONFIG_ARC};
 use deepfreeze::structs::general::SERVER_VERSION;
@@ -42,9 +42,10 @@ async fn main() -> std::io::Result<()> {
     };
 
     println!("Connecting to Redis...");
-    RS_POOL
-        .set(create_redis_connection_pool(&settings.redis.connection_string).await)
-        .expect("Error creating Redis Pool");
+    init_cache(&settings.redis.connection_string).expect("Error creating Redis cache client");
+    if !ping_cache() {
+        println!("Redis isn't responding, everything will be loaded from Postgres until it is");
+    }
 
     println!(
         "    _________ ______
@@ -81,9 +82,14 @@ async fn main() -> std::io::Result<()> {
                 // Always start offline until we pickup config from server.
                 force_offline: true,
             },
+            bank: Default::default(),
+            contracts: Default::default(),
+            currency: Default::default(),
             delivery: Default::default(),
             inventory: Default::default(),
             maintenance: Default::default(),
+            markets: Default::default(),
+            orders: Default::default(),
         },
     });
 
@@ -95,6 +101,9 @@ async fn main() -> std::io::Result<()> {
     let config_lock = Arc::clone(&API_CONFIG_ARC);
     spawn(poll_api_online_status(config_lock));
 
+    info!("Start closing stale orders");
+    spawn(poll_stale_orders(Arc::clone(&API_CONFIG_ARC)));
+
     let app_data = web::Data::new(settings);
     let app_state = web::Data::new(Arc::clone(&API_CONFIG_ARC));
 
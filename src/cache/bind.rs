@@ -0,0 +1,44 @@
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::cache::{delete, get_or_load, make_key};
+use crate::db::models::bind::ClientBind;
+use crate::db::Ppc;
+use crate::structs::binds::load_active_client_bind;
+
+/// Checked on every request from the game, kept short so an account or bind
+/// changed by hand in the database still stops working soon after.
+const CLIENT_BIND_TTL: usize = 30;
+
+fn client_bind_key(client_bind_id: &Uuid) -> String {
+    make_key(&["client_bind", &*client_bind_id.to_string()])
+}
+
+/// A confirmed Client Bind of an active account, misses aren't cached
+/// so a bind works as soon as it's confirmed.
+pub fn get_active_client_bind(client_bind_id: &Uuid) -> QueryResult<ClientBind> {
+    get_or_load(&client_bind_key(client_bind_id), CLIENT_BIND_TTL, || {
+        load_active_client_bind(client_bind_id)
+    })
+}
+
+/// Call this after a Client Bind is confirmed or deleted
+pub fn invalidate_client_bind(client_bind_id: &Uuid) {
+    delete(&[client_bind_key(client_bind_id)]);
+}
+
+/// Call this after an account is deactivated, changes its password or has its sessions revoked
+pub fn invalidate_account_client_binds(account_id: i32, conn: &Ppc) -> QueryResult<()> {
+    use crate::db::schema::client_binds as schema;
+    let client_bind_ids: Vec<Uuid> = schema::table
+        .filter(schema::account_fk.eq(account_id))
+        .select(schema::client_bind_id)
+        .load(conn)?;
+    delete(
+        &client_bind_ids
+            .iter()
+            .map(client_bind_key)
+            .collect::<Vec<String>>(),
+    );
+    Ok(())
+}
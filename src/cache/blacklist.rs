@@ -0,0 +1,27 @@
+use diesel::prelude::*;
+
+use crate::cache::{delete, get_or_load, make_key};
+use crate::db::Ppc;
+use crate::structs::blacklist::Blacklist;
+
+/// Checked against every item we're sent or serve, but only changed by hand.
+const BLACKLIST_TTL: usize = 60;
+
+fn blacklist_key() -> String {
+    make_key(&["item_blacklist"])
+}
+
+pub fn get_blacklist(conn: &Ppc) -> QueryResult<Blacklist> {
+    use crate::db::schema::item_blacklist as schema;
+    get_or_load(&blacklist_key(), BLACKLIST_TTL, || {
+        schema::table
+            .order(schema::entry_id.asc())
+            .load(conn)
+            .map(|entries| Blacklist { entries })
+    })
+}
+
+/// Call this after adding or removing an entry
+pub fn invalidate_blacklist() {
+    delete(&[blacklist_key()]);
+}
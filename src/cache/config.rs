@@ -0,0 +1,42 @@
+use diesel::prelude::*;
+
+use crate::cache::{delete, get_or_load, make_key};
+use crate::db::models::api_config::ApiConfig;
+use crate::db::models::stock_config::StockConfigRow;
+use crate::db::Ppc;
+
+/// Configuration is polled often by every node, but rarely changes.
+const CONFIG_TTL: usize = 30;
+
+fn api_config_key() -> String {
+    make_key(&["api_config", "latest"])
+}
+
+fn stock_config_key() -> String {
+    make_key(&["stock_config", "latest"])
+}
+
+/// Get the newest API configuration
+pub fn get_latest_api_config(conn: &Ppc) -> Result<ApiConfig, diesel::result::Error> {
+    use crate::db::schema::api_config as schema;
+    get_or_load(&api_config_key(), CONFIG_TTL, || {
+        schema::table
+            .order_by(schema::version.desc())
+            .first::<ApiConfig>(conn)
+    })
+}
+
+/// Get the newest Stock configuration
+pub fn get_latest_stock_config(conn: &Ppc) -> Result<StockConfigRow, diesel::result::Error> {
+    use crate::db::schema::stock_config as schema;
+    get_or_load(&stock_config_key(), CONFIG_TTL, || {
+        schema::table
+            .order_by(schema::version.desc())
+            .first::<StockConfigRow>(conn)
+    })
+}
+
+/// Call this after inserting a new configuration version
+pub fn invalidate_config() {
+    delete(&[api_config_key(), stock_config_key()]);
+}
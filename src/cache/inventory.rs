@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use bb8_redis::redis::Commands;
+use diesel::dsl::any;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::cache::{delete, get_many, get_or_load, make_key, set_many, with_connection};
+use crate::db::models::colony_tradable::ColonyTradables;
+use crate::db::models::inventory::Inventory;
+use crate::db::Ppc;
+use crate::structs::general::DbPkLoadable;
+
+/// Stock levels change with every order, keep them short-lived
+/// in case an invalidation is missed.
+const INVENTORY_TTL: usize = 60;
+const TRADABLES_TTL: usize = 600;
+
+fn generation_key() -> String {
+    make_key(&["inventory", "generation"])
+}
+
+fn tradables_key(colony_id: &Uuid) -> String {
+    make_key(&["colony_tradables", &*colony_id.to_string()])
+}
+
+/// Inventory keys include the generation, bumping it invalidates every row at once.
+fn inventory_key(generation: i64, market: &str, item_code: &str) -> String {
+    make_key(&["inventory", &*generation.to_string(), market, item_code])
+}
+
+fn used_lines_key(generation: i64, market: &str) -> String {
+    make_key(&["inventory", &*generation.to_string(), market, "used_lines"])
+}
+
+fn get_generation() -> Option<i64> {
+    with_connection(|con| con.get::<_, Option<i64>>(generation_key())).map(|g| g.unwrap_or(0))
+}
+
+/// Invalidate all cached inventory, maintenance calls this after it has rewritten prices.
+pub fn bump_inventory_generation() {
+    with_connection(|con| con.incr::<_, _, i64>(generation_key(), 1));
+}
+
+/// Invalidate rows that have had their stock changed
+pub fn invalidate_inventory<'a, I>(market: &str, item_codes: I)
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    if let Some(generation) = get_generation() {
+        delete(
+            &item_codes
+                .into_iter()
+                .map(|code| inventory_key(generation, market, code))
+                .collect::<Vec<String>>(),
+        );
+    }
+}
+
+/// Map of item code to the code of its used stock line, for every item that has one.
+/// Creating a used line bumps the generation so this gets reloaded.
+fn get_used_lines(
+    generation: i64,
+    market: &str,
+    conn: &Ppc,
+) -> Result<HashMap<String, String>, ()> {
+    use crate::db::schema::inventory as schema;
+
+    get_or_load(&used_lines_key(generation, market), INVENTORY_TTL, || {
+        schema::table
+            .select((schema::parent_item_code, schema::item_code))
+            .filter(schema::market.eq(market))
+            .filter(schema::parent_item_code.is_not_null())
+            .load::<(Option<String>, String)>(conn)
+            .map(|rows| {
+                rows.into_iter()
+                    .filter_map(|(parent, code)| parent.map(|p| (p, code)))
+                    .collect()
+            })
+            .map_err(|_| ())
+    })
+}
+
+pub fn get_colony_tradables(colony_id: &Uuid) -> Result<ColonyTradables, ()> {
+    get_or_load(&tradables_key(colony_id), TRADABLES_TTL, || {
+        ColonyTradables::load_pk(colony_id)
+    })
+}
+
+pub fn invalidate_colony_tradables(colony_id: &Uuid) {
+    delete(&[tradables_key(colony_id)]);
+}
+
+/// Load the market's inventory rows for the items a colony can trade, in the same order as
+/// its tradables list. Rows are read from the cache where possible.
+/// Returns None if the cache isn't available so the caller can query the database.
+pub fn get_colony_inventory(colony_id: &Uuid, market: &str, conn: &Ppc) -> Option<Vec<Inventory>> {
+    use crate::db::schema::inventory as schema;
+
+    let generation = get_generation()?;
+    let tradables = get_colony_tradables(colony_id).ok()?;
+    let used_lines = get_used_lines(generation, market, conn).ok()?;
+
+    // Used stock lines are listed straight after the item they came from
+    let codes: Vec<&String> = tradables
+        .tradables
+        .iter()
+        .flat_map(|code| std::iter::once(code).chain(used_lines.get(code)))
+        .collect();
+
+    let keys: Vec<String> = codes
+        .iter()
+        .map(|code| inventory_key(generation, market, code))
+        .collect();
+    let cached: Vec<Option<Inventory>> = get_many(&keys)?;
+
+    let missing: Vec<&String> = codes
+        .iter()
+        .copied()
+        .zip(cached.iter())
+        .filter(|(_, row)| row.is_none())
+        .map(|(code, _)| code)
+        .collect();
+
+    let mut loaded = HashMap::<String, Inventory>::with_capacity(missing.len());
+    if !missing.is_empty() {
+        let rows: Vec<Inventory> = schema::table
+            .filter(schema::market.eq(market))
+            .filter(schema::item_code.eq(any(missing)))
+            .get_results(conn)
+            .ok()?;
+
+        set_many(
+            &rows
+                .iter()
+                .map(|row| (inventory_key(generation, market, &row.item_code), row))
+                .collect::<Vec<(String, &Inventory)>>(),
+            INVENTORY_TTL,
+        );
+
+        for row in rows {
+            loaded.insert(row.item_code.clone(), row);
+        }
+    }
+
+    // Items that aren't in the inventory table are dropped, same as the database join.
+    Some(
+        codes
+            .into_iter()
+            .zip(cached.into_iter())
+            .filter_map(|(code, row)| row.or_else(|| loaded.remove(code)))
+            .collect(),
+    )
+}
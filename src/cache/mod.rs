@@ -1 +1,159 @@
+use std::cell::RefCell;
+use std::time::Duration;
 
+use bb8_redis::redis;
+use bb8_redis::redis::{Commands, Connection};
+use once_cell::sync::OnceCell;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub mod bind;
+pub mod blacklist;
+pub mod config;
+pub mod inventory;
+
+/// Every key we create starts with this, makes it easy to flush just our data.
+const KEY_PREFIX: &'static str = "deepfreeze:";
+
+/// Give up on Redis quickly, Postgres is always there as a fallback.
+const TIMEOUT_MILLIS: u64 = 250;
+
+pub const COLONY_TTL: usize = 300;
+
+static REDIS_CLIENT: OnceCell<redis::Client> = OnceCell::new();
+
+thread_local! {
+    // Most of the database code is synchronous, so each worker thread keeps
+    // its own blocking connection.
+    static CONNECTION: RefCell<Option<Connection>> = RefCell::new(None);
+}
+
+/// Enable the cache, if this isn't called every lookup goes straight to Postgres.
+pub fn init_cache(url: &String) -> Result<(), String> {
+    let client = redis::Client::open(url.as_str()).map_err(|e| format!("{}", e))?;
+    REDIS_CLIENT
+        .set(client)
+        .map_err(|_| "Cache already initialised".to_owned())
+}
+
+/// Check Redis is reachable, the cache still works without it but everything is slower.
+pub fn ping_cache() -> bool {
+    with_connection(|con| redis::cmd("PING").query::<String>(con)).is_some()
+}
+
+pub fn make_key(parts: &[&str]) -> String {
+    let mut key = String::from(KEY_PREFIX);
+    key.push_str(&*parts.join(":"));
+    key
+}
+
+/// Run a command against Redis, on any error the connection is dropped
+/// so we get a fresh one next time and the caller gets None.
+fn with_connection<T, F>(f: F) -> Option<T>
+where
+    F: FnOnce(&mut Connection) -> redis::RedisResult<T>,
+{
+    let client = REDIS_CLIENT.get()?;
+    CONNECTION.with(|cell| {
+        let mut slot = cell.borrow_mut();
+        if slot.is_none() {
+            let timeout = Duration::from_millis(TIMEOUT_MILLIS);
+            match client.get_connection_with_timeout(timeout) {
+                Ok(con) => {
+                    let _ = con.set_read_timeout(Some(timeout));
+                    let _ = con.set_write_timeout(Some(timeout));
+                    *slot = Some(con);
+                }
+                Err(e) => {
+                    debug!("Unable to connect to cache: {}", e);
+                    return None;
+                }
+            }
+        }
+
+        match f(slot.as_mut().unwrap()) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                debug!("Cache error: {}", e);
+                *slot = None;
+                None
+            }
+        }
+    })
+}
+
+pub fn get<T: DeserializeOwned>(key: &str) -> Option<T> {
+    with_connection(|con| con.get::<_, Option<String>>(key))
+        .flatten()
+        .and_then(|v| serde_json::from_str(&v).ok())
+}
+
+/// Get many keys at once, the result is the same length and order as the keys.
+pub fn get_many<T: DeserializeOwned>(keys: &[String]) -> Option<Vec<Option<T>>> {
+    if keys.is_empty() {
+        return Some(Vec::new());
+    }
+    with_connection(|con| {
+        redis::cmd("MGET")
+            .arg(keys)
+            .query::<Vec<Option<String>>>(con)
+    })
+    .map(|values| {
+        values
+            .into_iter()
+            .map(|v| v.and_then(|v| serde_json::from_str(&v).ok()))
+            .collect()
+    })
+}
+
+pub fn set<T: Serialize>(key: &str, value: &T, ttl: usize) {
+    if let Ok(data) = serde_json::to_string(value) {
+        with_connection(|con| con.set_ex::<_, _, ()>(key, data, ttl));
+    }
+}
+
+pub fn set_many<T: Serialize>(values: &[(String, &T)], ttl: usize) {
+    if values.is_empty() {
+        return;
+    }
+    with_connection(|con| {
+        let mut pipe = redis::pipe();
+        for (key, value) in values {
+            if let Ok(data) = serde_json::to_string(value) {
+                pipe.set_ex(key, data, ttl).ignore();
+            }
+        }
+        pipe.query::<()>(con)
+    });
+}
+
+pub fn delete(keys: &[String]) {
+    if keys.is_empty() {
+        return;
+    }
+    with_connection(|con| con.del::<_, ()>(keys));
+}
+
+/// Read a value through the cache, loading and storing it on a miss.
+/// Errors from the loader are never cached.
+pub fn get_or_load<T, E, F>(key: &str, ttl: usize, loader: F) -> Result<T, E>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Result<T, E>,
+{
+    if let Some(value) = get::<T>(key) {
+        return Ok(value);
+    }
+    let value = loader()?;
+    set(key, &value, ttl);
+    Ok(value)
+}
+
+pub fn colony_key(colony_id: &uuid::Uuid) -> String {
+    make_key(&["colony", &*colony_id.to_string()])
+}
+
+/// Call this after saving a colony so the next load sees the changes
+pub fn invalidate_colony(colony_id: &uuid::Uuid) {
+    delete(&[colony_key(colony_id)]);
+}
@@ -21,6 +21,9 @@ pub struct LoggingConfig {
 #[derive(Debug, Deserialize)]
 pub struct DiscordConfig {
     pub auth_token: String,
+    /// Discord users allowed to run the commands that change anything
+    #[serde(default)]
+    pub admin_user_ids: Vec<u64>,
 }
 
 #[derive(Debug, Deserialize)]
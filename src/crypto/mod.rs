@@ -34,10 +34,29 @@ pub fn hash_short_identity_string(val: String) -> String {
 //     hash_identity_string(val)
 // }
 
+/// Fast but predictable, only use it for ids that don't need to be kept secret.
 pub fn generate_random_alphanum_string(length: usize) -> String {
     repeat_with(fastrand::alphanumeric).take(length).collect()
 }
 
+/// Alphanumeric string from the system CSPRNG, for session ids, keys and anything else secret.
+pub fn generate_secure_token(length: usize) -> String {
+    const ALPHABET: &[u8; 62] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    // Anything above the last whole multiple of the alphabet is thrown away,
+    // otherwise the first few characters would turn up more often.
+    const LIMIT: u8 = (256 - 256 % ALPHABET.len()) as u8;
+
+    let mut token = String::with_capacity(length);
+    while token.len() < length {
+        for byte in random_bytes(length) {
+            if byte < LIMIT && token.len() < length {
+                token.push(ALPHABET[byte as usize % ALPHABET.len()] as char);
+            }
+        }
+    }
+    token
+}
+
 pub fn generate_v4_uuid() -> Uuid {
     Uuid::new_v4()
 }
@@ -49,6 +68,156 @@ pub fn parse_uuid(input: &str) -> Result<Uuid, ()> {
     }
 }
 
+const PASSWORD_ITERATIONS: u32 = 100_000;
+const PASSWORD_SALT_LENGTH: usize = 16;
+const TOTP_STEP_SECONDS: u64 = 30;
+const TOTP_DIGITS: u32 = 6;
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+fn random_bytes(length: usize) -> Vec<u8> {
+    use ring::rand::{SecureRandom, SystemRandom};
+
+    let mut bytes = vec![0u8; length];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .expect("Unable to read from the system RNG");
+    bytes
+}
+
+/// Hash a password with PBKDF2-SHA256 and a random salt,
+/// the result contains everything needed to verify it later.
+pub fn hash_password(password: &str) -> String {
+    use ring::pbkdf2;
+    use std::num::NonZeroU32;
+
+    let salt = random_bytes(PASSWORD_SALT_LENGTH);
+    let mut hash = [0u8; ring::digest::SHA256_OUTPUT_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PASSWORD_ITERATIONS).unwrap(),
+        &salt,
+        password.as_bytes(),
+        &mut hash,
+    );
+    format!(
+        "pbkdf2_sha256${}${}${}",
+        PASSWORD_ITERATIONS,
+        hex::encode(salt),
+        hex::encode(hash)
+    )
+}
+
+/// Check a password against a hash created by `hash_password`
+pub fn verify_password(password: &str, stored: &str) -> bool {
+    use ring::pbkdf2;
+    use std::num::NonZeroU32;
+
+    let parts: Vec<&str> = stored.split('$').collect();
+    if parts.len() != 4 || parts[0] != "pbkdf2_sha256" {
+        return false;
+    }
+
+    let iterations = match parts[1].parse::<u32>().ok().and_then(NonZeroU32::new) {
+        Some(v) => v,
+        None => return false,
+    };
+
+    match (hex::decode(parts[2]), hex::decode(parts[3])) {
+        (Ok(salt), Ok(hash)) => pbkdf2::verify(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations,
+            &salt,
+            password.as_bytes(),
+            &hash,
+        )
+        .is_ok(),
+        _ => false,
+    }
+}
+
+pub fn base32_encode(data: &[u8]) -> String {
+    let mut output = String::with_capacity((data.len() * 8 + 4) / 5);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for byte in data {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        output.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    output
+}
+
+pub fn base32_decode(data: &str) -> Result<Vec<u8>, ()> {
+    let mut output = Vec::<u8>::with_capacity(data.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in data.trim_end_matches('=').bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|a| *a == c.to_ascii_uppercase())
+            .ok_or(())?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(output)
+}
+
+/// Generate a new random TOTP secret, encoded as Base32 for authenticator apps.
+pub fn generate_totp_secret() -> String {
+    base32_encode(&random_bytes(20))
+}
+
+/// Calculate the TOTP code (RFC 6238, HMAC-SHA1) for a secret at a given unix time
+pub fn totp_code(secret: &[u8], unix_time: u64) -> u32 {
+    use ring::hmac;
+
+    let counter = unix_time / TOTP_STEP_SECONDS;
+    let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, secret);
+    let digest = hmac::sign(&key, &counter.to_be_bytes());
+    let digest = digest.as_ref();
+
+    let offset = (digest[digest.len() - 1] & 0xf) as usize;
+    let value = ((digest[offset] as u32 & 0x7f) << 24)
+        | ((digest[offset + 1] as u32) << 16)
+        | ((digest[offset + 2] as u32) << 8)
+        | (digest[offset + 3] as u32);
+    value % 10u32.pow(TOTP_DIGITS)
+}
+
+/// Check a TOTP code for a Base32 secret,
+/// one step either side is allowed to account for clock drift.
+pub fn verify_totp(secret: &str, code: &str, unix_time: u64) -> bool {
+    totp_step(secret, code, unix_time).is_some()
+}
+
+/// Same as `verify_totp` but returns the time step the code matched,
+/// so callers can refuse a code that's already been used.
+pub fn totp_step(secret: &str, code: &str, unix_time: u64) -> Option<u64> {
+    let secret = match base32_decode(secret) {
+        Ok(v) if !v.is_empty() => v,
+        _ => return None,
+    };
+    let code = code.trim().parse::<u32>().ok()?;
+    [0i64, -1, 1].iter().find_map(|step| {
+        let time = unix_time as i64 + (step * TOTP_STEP_SECONDS as i64);
+        if time >= 0 && totp_code(&secret, time as u64) == code {
+            Some(time as u64 / TOTP_STEP_SECONDS)
+        } else {
+            None
+        }
+    })
+}
+
 #[test]
 fn test_hash_short_identity_string() {
     let val = String::from("this is my really long string, I hope this is long enough");
@@ -65,3 +234,49 @@ fn test_make_uuid_string() {
     let second_uuid = Uuid::parse_str(&*repr).unwrap();
     assert_eq!(first_uuid, second_uuid);
 }
+
+#[test]
+fn test_generate_secure_token() {
+    let token = generate_secure_token(64);
+    assert_eq!(token.len(), 64);
+    assert!(token.chars().all(|c| c.is_ascii_alphanumeric()));
+    assert_ne!(token, generate_secure_token(64));
+}
+
+#[test]
+fn test_password_hash_and_verify() {
+    let hash = hash_password("correct horse battery staple");
+    assert!(hash.starts_with("pbkdf2_sha256$"));
+    assert!(verify_password("correct horse battery staple", &hash));
+    assert!(!verify_password("Tr0ub4dor&3", &hash));
+    assert!(!verify_password(
+        "correct horse battery staple",
+        "not a hash"
+    ));
+}
+
+#[test]
+fn test_base32_round_trip() {
+    assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+    assert_eq!(base32_decode("MZXW6YTBOI").unwrap(), b"foobar".to_vec());
+    assert_eq!(
+        base32_decode("mzxw6ytboi======").unwrap(),
+        b"foobar".to_vec()
+    );
+    assert!(base32_decode("not base32!").is_err());
+}
+
+#[test]
+fn test_totp_rfc6238_vectors() {
+    // RFC 6238 Appendix B, SHA1 seed truncated to 6 digits
+    let secret = b"12345678901234567890";
+    assert_eq!(totp_code(secret, 59), 287082);
+    assert_eq!(totp_code(secret, 1111111109), 81804);
+    assert_eq!(totp_code(secret, 1234567890), 5924);
+
+    let encoded = base32_encode(secret);
+    assert!(verify_totp(&encoded, "287082", 59));
+    assert!(verify_totp(&encoded, "287082", 80));
+    assert!(!verify_totp(&encoded, "287082", 200));
+    assert_eq!(totp_step(&encoded, "287082", 80), Some(1));
+}
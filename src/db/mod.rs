@@ -15,19 +15,12 @@ pub mod models;
 pub mod schema;
 pub mod views;
 
-use bb8_redis::{bb8, RedisConnectionManager};
-
 pub type Ppc = PooledConnection<ConnectionManager<PgConnection>>;
-pub type RedisConnection = OnceCell<bb8::Pool<RedisConnectionManager>>;
 
 lazy_static! {
     pub static ref PG_POOL: OnceCell<Pool<ConnectionManager<PgConnection>>> = OnceCell::new();
 }
 
-lazy_static! {
-    pub static ref RS_POOL: OnceCell::<bb8::Pool<RedisConnectionManager>> = OnceCell::new();
-}
-
 pub fn get_pg_connection() -> PooledConnection<ConnectionManager<PgConnection>> {
     PG_POOL
         .get()
@@ -44,19 +37,6 @@ pub fn create_pg_connection_pool(db_url: &String) -> Pool<ConnectionManager<PgCo
         .expect("Code: E100 - Error creating Database connection.")
 }
 
-pub async fn create_redis_connection_pool(url: &String) -> bb8::Pool<RedisConnectionManager> {
-    info!("Checking Redis connectivity");
-    let manager = RedisConnectionManager::new(url.clone()).unwrap();
-    let pool = bb8::Pool::builder().build(manager).await.unwrap();
-    let pool_ = pool.clone();
-    let mut connection = pool_.get().await.unwrap();
-    bb8_redis::redis::cmd("PING")
-        .query_async::<_, String>(&mut *connection)
-        .await
-        .expect("Redis PING failure");
-    pool
-}
-
 pub fn get_db_object_by_id<'a, Model, Table, Key>(
     conn: &Ppc,
     table: Table,
@@ -1,8 +1,11 @@
 use chrono::NaiveDateTime;
 
+use crate::db::schema::account_sessions;
 use crate::db::schema::accounts;
 
-#[derive(Queryable, Debug)]
+#[derive(Queryable, Identifiable, Debug, Clone)]
+#[primary_key(account_id)]
+#[table_name = "accounts"]
 pub struct Account {
     pub account_id: i32,
     pub date_added: NaiveDateTime,
@@ -12,6 +15,13 @@ pub struct Account {
     pub mfa_code: Option<String>,
     pub active: bool,
     pub steam_id: Option<String>,
+    /// Allowed to use the admin API
+    pub admin: bool,
+    /// Failed logins and MFA codes since the last successful login
+    pub failed_logins: i32,
+    pub locked_until: Option<NaiveDateTime>,
+    /// TOTP time step of the last code we accepted, older and equal steps are refused
+    pub last_totp_step: Option<i64>,
 }
 
 #[derive(Insertable, Debug)]
@@ -25,3 +35,14 @@ pub struct NewAccount {
     pub active: bool,
     pub steam_id: Option<String>,
 }
+
+#[derive(Queryable, Insertable, Identifiable, Debug)]
+#[primary_key(session_id)]
+#[table_name = "account_sessions"]
+pub struct AccountSession {
+    /// The token the player sends in the Authorization header
+    pub session_id: String,
+    pub account_fk: i32,
+    pub date_added: NaiveDateTime,
+    pub date_expire: NaiveDateTime,
+}
@@ -10,7 +10,7 @@ use diesel::types::FromSql;
 use diesel::types::ToSql;
 use std::io::Write;
 
-#[derive(Queryable, Identifiable, Debug, Default, Clone)]
+#[derive(Queryable, Identifiable, Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
 #[primary_key(version)]
 #[table_name = "api_config"]
 pub struct ApiConfig {
@@ -0,0 +1,25 @@
+use crate::db::schema::backorders;
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
+#[derive(Queryable, Identifiable, Insertable, Debug, AsChangeset)]
+#[primary_key(backorder_id)]
+#[table_name = "backorders"]
+pub struct Backorder {
+    pub backorder_id: Uuid,
+    pub colony_id: Uuid,
+    pub item_code: String,
+    pub quantity: i32,
+    pub health: f32,
+    /// Amount taken from the colony's bank when the backorder was placed
+    pub paid: i32,
+    pub currency: i32,
+    pub status: i32,
+    /// The order created to deliver the items once the backorder is filled
+    pub order_id: Option<Uuid>,
+    pub placed_tick: i32,
+    pub create_date: NaiveDateTime,
+    pub update_date: NaiveDateTime,
+    /// The market the backorder is filled from
+    pub market: String,
+}
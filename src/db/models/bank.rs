@@ -1,4 +1,7 @@
 use crate::db::schema::bank_balances;
+use crate::db::schema::bank_ledger;
+use crate::db::schema::bank_transfers;
+use chrono::NaiveDateTime;
 use uuid::Uuid;
 
 #[derive(Queryable, Identifiable, Insertable, Debug, AsChangeset)]
@@ -8,3 +11,48 @@ pub struct BankBalance {
     pub currency: i32,
     pub balance: i32,
 }
+
+#[derive(Queryable, Identifiable, Insertable, Debug, AsChangeset)]
+#[primary_key(transfer_id)]
+#[table_name = "bank_transfers"]
+pub struct BankTransfer {
+    pub transfer_id: Uuid,
+    pub from_colony_id: Uuid,
+    pub to_colony_id: Uuid,
+    pub currency: i32,
+    /// Taken from the sender when the transfer is created,
+    /// held until the recipient accepts it if they're on another account
+    pub amount: i32,
+    pub status: i32,
+    pub create_date: NaiveDateTime,
+    pub update_date: NaiveDateTime,
+}
+
+#[derive(Queryable, Identifiable, Debug)]
+#[primary_key(entry_id)]
+#[table_name = "bank_ledger"]
+pub struct BankLedgerEntry {
+    pub entry_id: i64,
+    pub colony_id: Uuid,
+    pub currency: i32,
+    /// Signed, negative amounts were taken from the colony
+    pub amount: i32,
+    /// The balance after this entry was applied
+    pub balance: i32,
+    pub kind: i32,
+    /// The order, backorder or transfer that caused the entry, if any
+    pub reference_id: Option<Uuid>,
+    pub create_date: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "bank_ledger"]
+pub struct NewBankLedgerEntry {
+    pub colony_id: Uuid,
+    pub currency: i32,
+    pub amount: i32,
+    pub balance: i32,
+    pub kind: i32,
+    pub reference_id: Option<Uuid>,
+    pub create_date: NaiveDateTime,
+}
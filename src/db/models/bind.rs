@@ -15,11 +15,12 @@ pub struct AccountBind {
     pub account_fk: Option<i32>,
 }
 
-#[derive(Queryable, Insertable, Debug)]
+#[derive(Queryable, Insertable, Debug, serde::Serialize, serde::Deserialize)]
 #[table_name = "client_binds"]
 pub struct ClientBind {
     pub client_bind_id: Uuid,
     pub account_fk: i32,
     pub confirmed: bool,
     pub date_added: NaiveDateTime,
+    pub account_bind_fk: Option<Uuid>,
 }
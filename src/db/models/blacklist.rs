@@ -0,0 +1,27 @@
+use chrono::NaiveDateTime;
+
+use crate::db::schema::item_blacklist;
+
+/// Items we refuse to stock. Every field that's set has to match, `thing_def`
+/// may use `*` to match any run of characters.
+#[derive(Queryable, Identifiable, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[primary_key(entry_id)]
+#[table_name = "item_blacklist"]
+pub struct BlacklistEntry {
+    pub entry_id: i32,
+    pub item_code: Option<String>,
+    pub thing_def: Option<String>,
+    pub stuff: Option<String>,
+    pub reason: String,
+    pub date_added: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "item_blacklist"]
+pub struct NewBlacklistEntry {
+    pub item_code: Option<String>,
+    pub thing_def: Option<String>,
+    pub stuff: Option<String>,
+    pub reason: String,
+    pub date_added: NaiveDateTime,
+}
@@ -3,7 +3,9 @@ use chrono::NaiveDateTime;
 use crate::db::schema::colonies;
 use uuid::Uuid;
 
-#[derive(Queryable, Insertable, Identifiable, Debug, AsChangeset)]
+#[derive(
+    Queryable, Insertable, Identifiable, Debug, AsChangeset, serde::Serialize, serde::Deserialize,
+)]
 #[primary_key(colony_id)]
 #[table_name = "colonies"]
 pub struct Colony {
@@ -21,4 +23,6 @@ pub struct Colony {
     pub update_date: NaiveDateTime,
     pub seed: String,
     pub location: String,
+    /// Set from the game version, see `market_for_game_version`
+    pub market: Option<String>,
 }
@@ -4,7 +4,9 @@ use crate::db::models::StringVec;
 use crate::db::schema::colony_tradables;
 use uuid::Uuid;
 
-#[derive(Queryable, Insertable, Identifiable, Debug, AsChangeset)]
+#[derive(
+    Queryable, Insertable, Identifiable, Debug, AsChangeset, serde::Serialize, serde::Deserialize,
+)]
 #[primary_key(colony_id)]
 #[table_name = "colony_tradables"]
 pub struct ColonyTradables {
@@ -0,0 +1,25 @@
+use crate::db::schema::contracts;
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
+#[derive(Queryable, Identifiable, Insertable, Debug, AsChangeset)]
+#[primary_key(contract_id)]
+#[table_name = "contracts"]
+pub struct Contract {
+    pub contract_id: Uuid,
+    pub colony_id: Uuid,
+    pub item_code: String,
+    pub quantity: i32,
+    pub reward: i32,
+    pub currency: i32,
+    pub status: i32,
+    pub start_tick: i32,
+    /// The contract must be fulfilled on or before this tick
+    pub deadline_tick: i32,
+    /// Secret used to sign the Contract ID that is sent to the colony
+    pub private_key: String,
+    pub create_date: NaiveDateTime,
+    pub update_date: NaiveDateTime,
+    /// The market the items are delivered to
+    pub market: String,
+}
@@ -4,9 +4,18 @@ use bigdecimal::BigDecimal;
 use macros::FieldCount;
 
 #[derive(
-    Queryable, QueryableByName, Insertable, Identifiable, Debug, AsChangeset, Clone, FieldCount,
+    Queryable,
+    QueryableByName,
+    Insertable,
+    Identifiable,
+    Debug,
+    AsChangeset,
+    Clone,
+    FieldCount,
+    serde::Serialize,
+    serde::Deserialize,
 )]
-#[primary_key(item_code)]
+#[primary_key(market, item_code)]
 #[table_name = "inventory"]
 pub struct Inventory {
     pub item_code: String,
@@ -22,12 +31,20 @@ pub struct Inventory {
     pub stuff: Option<String>,
     pub weight: BigDecimal,
     pub version: String,
+    /// See `ItemConditionEnum`
+    pub condition: i32,
+    /// Set on "used" stock lines, the item code of the new item they were pooled from
+    pub parent_item_code: Option<String>,
+    /// The mod that added the item, it's only offered to colonies that have it loaded
+    pub mod_name: Option<String>,
+    /// The game version the item trades in, see `market_for_game_version`
+    pub market: String,
 }
 
 #[derive(
     Queryable, QueryableByName, Insertable, Identifiable, Clone, Debug, AsChangeset, FieldCount,
 )]
-#[primary_key(item_code)]
+#[primary_key(market, item_code)]
 #[table_name = "inventory"]
 pub struct InventoryNoQuantity {
     pub item_code: String,
@@ -40,6 +57,8 @@ pub struct InventoryNoQuantity {
     pub stuff: Option<String>,
     pub weight: BigDecimal,
     pub version: String,
+    pub mod_name: Option<String>,
+    pub market: String,
 }
 
 #[derive(Queryable, QueryableByName, Identifiable, Clone, Debug, FieldCount)]
@@ -55,10 +74,12 @@ pub struct TempInventoryVote {
     pub stuff: Option<String>,
     pub weight: BigDecimal,
     pub version: String,
+    pub votes: i32,
+    pub market: String,
 }
 
 #[derive(Queryable, QueryableByName, Insertable, Identifiable, Debug, AsChangeset)]
-#[primary_key(item_code)]
+#[primary_key(market, item_code)]
 #[table_name = "inventory"]
 pub struct InventoryOnlyStats {
     pub item_code: String,
@@ -67,4 +88,5 @@ pub struct InventoryOnlyStats {
     pub base_value: BigDecimal,
     pub weight: BigDecimal,
     pub version: String,
+    pub market: String,
 }
@@ -1,6 +1,17 @@
-use crate::db::schema::maintenance;
+use crate::db::schema::{maintenance, threshold_misses};
+use crate::impl_from_sql;
+use crate::impl_to_sql;
+use crate::structs::trade_stats::StatisticsRollup;
+use bigdecimal::BigDecimal;
 use chrono::NaiveDateTime;
 
+use diesel::pg::Pg;
+use diesel::serialize::Output;
+use diesel::sql_types::Jsonb;
+use diesel::types::FromSql;
+use diesel::types::ToSql;
+use std::io::Write;
+
 #[derive(Queryable, Identifiable, Insertable, Debug, Default)]
 #[primary_key(checksum)]
 #[table_name = "maintenance"]
@@ -10,4 +21,47 @@ pub struct Maintenance {
     pub start_time: Option<NaiveDateTime>,
     pub execution_time: Option<NaiveDateTime>,
     pub node_name: Option<String>,
+    pub report: Option<MaintenanceReport>,
+}
+
+/// What the last maintenance run actually did
+#[derive(FromSqlRow, AsExpression, serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
+#[sql_type = "Jsonb"]
+pub struct MaintenanceReport {
+    #[serde(default)]
+    pub statistics_rollup: Option<StatisticsRollup>,
+    /// Bank balances that didn't match their ledger
+    #[serde(default)]
+    pub ledger_mismatches: Option<usize>,
+    /// Items the stock configuration doesn't cover, the full list is in `threshold_misses`
+    #[serde(default)]
+    pub threshold_misses: Option<ThresholdMissSummary>,
+    /// The markets that had their votes, stock and prices processed
+    #[serde(default)]
+    pub markets: Option<Vec<String>>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
+pub struct ThresholdMissSummary {
+    pub missing_price: i64,
+    pub missing_stock: i64,
+}
+
+/// An item maintenance couldn't price or restock because no threshold covers it,
+/// the table is emptied at the start of each run.
+#[derive(Queryable, Insertable, Debug, serde::Serialize)]
+#[primary_key(market, item_code)]
+#[table_name = "threshold_misses"]
+pub struct ThresholdMiss {
+    pub item_code: String,
+    pub thing_def: String,
+    pub base_value: BigDecimal,
+    pub condition: i32,
+    pub missing_price: bool,
+    pub missing_stock: bool,
+    pub found_date: NaiveDateTime,
+    pub market: String,
 }
+
+impl_to_sql!(for MaintenanceReport);
+impl_from_sql!(for MaintenanceReport);
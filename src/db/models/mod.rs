@@ -9,12 +9,15 @@ use diesel::sql_types::Jsonb;
 
 pub mod account;
 pub mod api_config;
+pub mod backorder;
 pub mod bank;
 pub mod bind;
+pub mod blacklist;
 pub mod blocked_steam_accounts;
 pub mod colony;
 pub mod colony_mod;
 pub mod colony_tradable;
+pub mod contract;
 pub mod inventory;
 pub mod inventory_promise;
 pub mod inventory_staging;
@@ -22,6 +25,7 @@ pub mod maintenance;
 pub mod new_inventory;
 pub mod new_inventory_vote;
 pub mod order;
+pub mod pending_inventory;
 pub mod price_tracker;
 pub mod stock_config;
 pub mod summary_inventory_votes;
@@ -4,7 +4,7 @@ use chrono::NaiveDateTime;
 use macros::FieldCount;
 
 #[derive(Queryable, QueryableByName, Insertable, Identifiable, Debug, AsChangeset, FieldCount)]
-#[primary_key(version)]
+#[primary_key(market, version)]
 #[table_name = "new_inventory"]
 pub struct NewInventory {
     pub item_code: String,
@@ -16,4 +16,5 @@ pub struct NewInventory {
     pub weight: BigDecimal,
     pub version: String,
     pub date_added: NaiveDateTime,
+    pub market: String,
 }
@@ -16,6 +16,10 @@ pub struct Order {
     pub order_stats: OrderStats,
     pub create_date: NaiveDateTime,
     pub update_date: NaiveDateTime,
+    /// Why the delivery failed, see `OrderFailureReasonEnum`
+    pub failure_reason: Option<i32>,
+    /// The market the items were taken from or will be sold to
+    pub market: String,
 }
 
 #[derive(Queryable, Identifiable, Debug, AsChangeset)]
@@ -30,4 +34,8 @@ pub struct OrderNoManifest {
     pub order_stats: OrderStats,
     pub create_date: NaiveDateTime,
     pub update_date: NaiveDateTime,
+    /// Why the delivery failed, see `OrderFailureReasonEnum`
+    pub failure_reason: Option<i32>,
+    /// The market the items were taken from or will be sold to
+    pub market: String,
 }
@@ -0,0 +1,29 @@
+use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use macros::FieldCount;
+
+use crate::db::schema::pending_inventory;
+
+/// An item that's been voted in but has to be approved before it's added to the
+/// inventory, see `ApiConfigDataInventory::moderation`.
+#[derive(Queryable, Insertable, Identifiable, Clone, Debug, FieldCount, serde::Serialize)]
+#[primary_key(market, version)]
+#[table_name = "pending_inventory"]
+pub struct PendingInventory {
+    pub version: String,
+    pub item_code: String,
+    pub thing_def: String,
+    pub quality: Option<i32>,
+    pub minified: bool,
+    pub base_value: BigDecimal,
+    pub stuff: Option<String>,
+    pub weight: BigDecimal,
+    pub votes: i32,
+    /// See `ModerationStatusEnum`
+    pub status: i32,
+    pub date_added: NaiveDateTime,
+    pub review_date: Option<NaiveDateTime>,
+    pub reviewed_by: Option<String>,
+    pub mod_name: Option<String>,
+    pub market: String,
+}
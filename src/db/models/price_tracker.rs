@@ -4,10 +4,13 @@ use crate::db::schema::price_tracker;
 use bigdecimal::BigDecimal;
 
 #[derive(Queryable, Insertable, Debug, AsChangeset)]
-#[primary_key(item_code, value)]
+#[primary_key(market, item_code, create_date)]
 #[table_name = "price_tracker"]
 pub struct PriceTracker {
     pub item_code: String,
     pub value: BigDecimal,
     pub create_date: NaiveDateTime,
+    pub buy_at: BigDecimal,
+    pub sell_at: BigDecimal,
+    pub market: String,
 }
@@ -14,7 +14,7 @@ use std::io::Write;
 impl_to_sql!(for StockConfig);
 impl_from_sql!(for StockConfig);
 
-#[derive(Queryable, Identifiable, Debug, Default, Clone)]
+#[derive(Queryable, Identifiable, Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
 #[primary_key(version)]
 #[table_name = "stock_config"]
 pub struct StockConfigRow {
@@ -1,7 +1,8 @@
 use crate::db::views::summary_inventory_votes;
+use bigdecimal::BigDecimal;
 
 #[derive(Queryable, QueryableByName, Identifiable, Debug)]
-#[primary_key(version)]
+#[primary_key(market, version)]
 #[table_name = "summary_inventory_votes"]
 pub struct ThingVoteCount {
     pub item_code: String,
@@ -12,5 +13,9 @@ pub struct ThingVoteCount {
     pub stuff: Option<String>,
     pub weight: f32,
     pub version: String,
+    /// How many accounts voted for it
     pub votes: i32,
+    /// What promotion goes by, see `ApiConfigDataInventoryVoteWeights`
+    pub weighted_votes: BigDecimal,
+    pub market: String,
 }
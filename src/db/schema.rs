@@ -9,6 +9,15 @@ table! {
     }
 }
 
+table! {
+    account_sessions (session_id) {
+        session_id -> Varchar,
+        account_fk -> Int4,
+        date_added -> Timestamp,
+        date_expire -> Timestamp,
+    }
+}
+
 table! {
     accounts (account_id) {
         account_id -> Int4,
@@ -19,6 +28,10 @@ table! {
         mfa_code -> Nullable<Text>,
         active -> Bool,
         steam_id -> Nullable<Text>,
+        admin -> Bool,
+        failed_logins -> Int4,
+        locked_until -> Nullable<Timestamp>,
+        last_totp_step -> Nullable<Int8>,
     }
 }
 
@@ -29,6 +42,24 @@ table! {
     }
 }
 
+table! {
+    backorders (backorder_id) {
+        backorder_id -> Uuid,
+        colony_id -> Uuid,
+        item_code -> Varchar,
+        quantity -> Int4,
+        health -> Float4,
+        paid -> Int4,
+        currency -> Int4,
+        status -> Int4,
+        order_id -> Nullable<Uuid>,
+        placed_tick -> Int4,
+        create_date -> Timestamp,
+        update_date -> Timestamp,
+        market -> Varchar,
+    }
+}
+
 table! {
     bank_balances (colony_id, currency) {
         colony_id -> Uuid,
@@ -37,6 +68,32 @@ table! {
     }
 }
 
+table! {
+    bank_ledger (entry_id) {
+        entry_id -> Int8,
+        colony_id -> Uuid,
+        currency -> Int4,
+        amount -> Int4,
+        balance -> Int4,
+        kind -> Int4,
+        reference_id -> Nullable<Uuid>,
+        create_date -> Timestamp,
+    }
+}
+
+table! {
+    bank_transfers (transfer_id) {
+        transfer_id -> Uuid,
+        from_colony_id -> Uuid,
+        to_colony_id -> Uuid,
+        currency -> Int4,
+        amount -> Int4,
+        status -> Int4,
+        create_date -> Timestamp,
+        update_date -> Timestamp,
+    }
+}
+
 table! {
     blocked_steam_accounts (steam_id) {
         steam_id -> Text,
@@ -51,6 +108,7 @@ table! {
         account_fk -> Int4,
         confirmed -> Bool,
         date_added -> Timestamp,
+        account_bind_fk -> Nullable<Uuid>,
     }
 }
 
@@ -69,6 +127,7 @@ table! {
         update_date -> Timestamp,
         seed -> Varchar,
         location -> Varchar,
+        market -> Nullable<Varchar>,
     }
 }
 
@@ -88,7 +147,25 @@ table! {
 }
 
 table! {
-    inventory (item_code) {
+    contracts (contract_id) {
+        contract_id -> Uuid,
+        colony_id -> Uuid,
+        item_code -> Varchar,
+        quantity -> Int4,
+        reward -> Int4,
+        currency -> Int4,
+        status -> Int4,
+        start_tick -> Int4,
+        deadline_tick -> Int4,
+        private_key -> Varchar,
+        create_date -> Timestamp,
+        update_date -> Timestamp,
+        market -> Varchar,
+    }
+}
+
+table! {
+    inventory (market, item_code) {
         item_code -> Varchar,
         thing_def -> Text,
         quality -> Nullable<Int4>,
@@ -100,6 +177,10 @@ table! {
         stuff -> Nullable<Text>,
         weight -> Numeric,
         version -> Varchar,
+        condition -> Int4,
+        parent_item_code -> Nullable<Varchar>,
+        mod_name -> Nullable<Text>,
+        market -> Varchar,
     }
 }
 
@@ -113,6 +194,17 @@ table! {
     }
 }
 
+table! {
+    item_blacklist (entry_id) {
+        entry_id -> Int4,
+        item_code -> Nullable<Varchar>,
+        thing_def -> Nullable<Varchar>,
+        stuff -> Nullable<Varchar>,
+        reason -> Text,
+        date_added -> Timestamp,
+    }
+}
+
 table! {
     maintenance (checksum) {
         checksum -> Varchar,
@@ -120,11 +212,12 @@ table! {
         start_time -> Nullable<Timestamp>,
         execution_time -> Nullable<Timestamp>,
         node_name -> Nullable<Varchar>,
+        report -> Nullable<Jsonb>,
     }
 }
 
 table! {
-    new_inventory (version) {
+    new_inventory (market, version) {
         item_code -> Varchar,
         thing_def -> Varchar,
         quality -> Nullable<Int4>,
@@ -134,6 +227,7 @@ table! {
         weight -> Numeric,
         version -> Varchar,
         date_added -> Timestamp,
+        market -> Varchar,
     }
 }
 
@@ -152,10 +246,11 @@ table! {
 }
 
 table! {
-    new_inventory_vote_tracker (version, client_bind_id, colony_id) {
+    new_inventory_vote_tracker (market, version, client_bind_id, colony_id) {
         client_bind_id -> Uuid,
         version -> Varchar,
         colony_id -> Uuid,
+        market -> Varchar,
     }
 }
 
@@ -170,14 +265,39 @@ table! {
         order_stats -> Jsonb,
         create_date -> Timestamp,
         update_date -> Timestamp,
+        failure_reason -> Nullable<Int4>,
+        market -> Varchar,
+    }
+}
+
+table! {
+    pending_inventory (market, version) {
+        version -> Varchar,
+        item_code -> Varchar,
+        thing_def -> Text,
+        quality -> Nullable<Int4>,
+        minified -> Bool,
+        base_value -> Numeric,
+        stuff -> Nullable<Text>,
+        weight -> Numeric,
+        votes -> Int4,
+        status -> Int4,
+        date_added -> Timestamp,
+        review_date -> Nullable<Timestamp>,
+        reviewed_by -> Nullable<Varchar>,
+        mod_name -> Nullable<Text>,
+        market -> Varchar,
     }
 }
 
 table! {
-    price_tracker (item_code, value) {
+    price_tracker (market, item_code, create_date) {
         item_code -> Varchar,
         value -> Numeric,
         create_date -> Timestamp,
+        buy_at -> Numeric,
+        sell_at -> Numeric,
+        market -> Varchar,
     }
 }
 
@@ -189,47 +309,71 @@ table! {
 }
 
 table! {
-    trade_statistics (item_code, buy, date) {
+    threshold_misses (market, item_code) {
+        item_code -> Varchar,
+        thing_def -> Text,
+        base_value -> Numeric,
+        condition -> Int4,
+        missing_price -> Bool,
+        missing_stock -> Bool,
+        found_date -> Timestamp,
+        market -> Varchar,
+    }
+}
+
+table! {
+    trade_statistics (market, item_code, buy, date) {
         item_code -> Varchar,
         buy -> Bool,
         quantity -> Int8,
         date -> Date,
+        market -> Varchar,
     }
 }
 
 table! {
-    trade_statistics_monthly (item_code, buy, date) {
+    trade_statistics_monthly (market, item_code, buy, date) {
         item_code -> Varchar,
         buy -> Bool,
         quantity -> Int8,
         date -> Date,
+        market -> Varchar,
     }
 }
 
 joinable!(account_binds -> accounts (account_fk));
+joinable!(account_sessions -> accounts (account_fk));
+joinable!(client_binds -> account_binds (account_bind_fk));
 joinable!(client_binds -> accounts (account_fk));
 joinable!(colonies -> client_binds (client_bind_fk));
 joinable!(new_inventory_vote_tracker -> client_binds (client_bind_id));
-joinable!(new_inventory_vote_tracker -> new_inventory (version));
 
 allow_tables_to_appear_in_same_query!(
     account_binds,
+    account_sessions,
     accounts,
     api_config,
+    backorders,
     bank_balances,
+    bank_ledger,
+    bank_transfers,
     blocked_steam_accounts,
     client_binds,
     colonies,
     colony_mods,
     colony_tradables,
+    contracts,
     inventory,
     inventory_promises,
+    item_blacklist,
     maintenance,
     new_inventory,
     new_inventory_vote_tracker,
     orders,
+    pending_inventory,
     price_tracker,
     stock_config,
+    threshold_misses,
     trade_statistics,
     trade_statistics_monthly,
 );
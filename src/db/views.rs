@@ -1,5 +1,5 @@
 table! {
-    summary_inventory_votes (version) {
+    summary_inventory_votes (market, version) {
         item_code -> Varchar,
         thing_def -> Varchar,
         quality -> Nullable<Int4>,
@@ -9,6 +9,8 @@ table! {
         weight -> Numeric,
         version -> Varchar,
         votes -> Int8,
+        weighted_votes -> Numeric,
+        market -> Varchar,
     }
 }
 
@@ -23,5 +25,7 @@ table! {
         stuff -> Nullable<Varchar>,
         weight -> Numeric,
         version -> Varchar,
+        votes -> Int4,
+        market -> Varchar,
     }
 }
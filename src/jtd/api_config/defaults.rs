@@ -1,4 +1,18 @@
-use crate::jtd::api_config::structure::ApiConfigDataDelivery;
+use std::collections::HashMap;
+
+use crate::jtd::api_config::structure::{
+    ApiConfigDataBank, ApiConfigDataContracts, ApiConfigDataCurrency, ApiConfigDataDelivery,
+    ApiConfigDataInventoryVoteWeights, ApiConfigDataMaintenance, ApiConfigDataOrders,
+    ApiConfigDataOrdersStaleOrderPolicy,
+};
+
+impl Default for ApiConfigDataBank {
+    fn default() -> Self {
+        ApiConfigDataBank {
+            transfer_expiry_days: 7,
+        }
+    }
+}
 
 impl Default for ApiConfigDataDelivery {
     fn default() -> Self {
@@ -8,3 +22,61 @@ impl Default for ApiConfigDataDelivery {
         }
     }
 }
+
+impl Default for ApiConfigDataContracts {
+    fn default() -> Self {
+        ApiConfigDataContracts {
+            max_days: 10,
+            max_offers: 3,
+            max_value: 2_000,
+            min_days: 3,
+            reward_bonus_pct: 0.25,
+        }
+    }
+}
+
+impl Default for ApiConfigDataMaintenance {
+    fn default() -> Self {
+        ApiConfigDataMaintenance {
+            start_time: 0,
+            statistics_retention_days: 90,
+        }
+    }
+}
+
+impl Default for ApiConfigDataCurrency {
+    fn default() -> Self {
+        let mut rates = HashMap::new();
+        rates.insert("gold".to_string(), 10.0);
+        ApiConfigDataCurrency {
+            exchange_fee_pct: 0.05,
+            rates,
+        }
+    }
+}
+
+impl Default for ApiConfigDataOrders {
+    fn default() -> Self {
+        ApiConfigDataOrders {
+            insurance_pct: 0.5,
+            overdue_days: 5,
+            restocking_fee_pct: 0.1,
+            stale_colony_days: 14,
+            stale_order_policy: ApiConfigDataOrdersStaleOrderPolicy::Expire,
+        }
+    }
+}
+
+impl Default for ApiConfigDataInventoryVoteWeights {
+    fn default() -> Self {
+        ApiConfigDataInventoryVoteWeights {
+            drm_free_account: 0.5,
+            new_account: 0.25,
+            new_colony: 0.25,
+            steam_account: 1.0,
+            trusted_account_days: 30,
+            // Ten in-game days
+            trusted_colony_ticks: 600_000,
+        }
+    }
+}
@@ -1,6 +1,9 @@
-// Code generated by jtd-codegen for Rust v0.2.1
+// Originally generated by jtd-codegen for Rust v0.2.1, now maintained by hand.
+// Don't regenerate it, optional properties are plain fields with serde defaults
+// and their Default impls live in defaults.rs. Keep it in step with jtd/api_config_data.jtd.json.
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ApiConfigDataApi {
@@ -8,6 +11,15 @@ pub struct ApiConfigDataApi {
     pub force_offline: bool,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ApiConfigDataBank {
+    /// Transfers to another account's colony go back to the sender if they
+    /// aren't accepted within this many days
+    #[serde(rename = "transfer_expiry_days")]
+    pub transfer_expiry_days: u32,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ApiConfigDataDelivery {
     #[serde(rename = "collect_cost_per_kg")]
@@ -17,19 +29,148 @@ pub struct ApiConfigDataDelivery {
     pub delivery_cost_per_kg: u32,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiConfigDataContracts {
+    /// Longest time in game days a colony is given to fulfil a contract
+    #[serde(rename = "max_days")]
+    pub max_days: u32,
+
+    /// Maximum number of open contract offers per colony
+    #[serde(rename = "max_offers")]
+    pub max_offers: u32,
+
+    /// Upper limit on the base value of the items requested in a single
+    /// contract
+    #[serde(rename = "max_value")]
+    pub max_value: u32,
+
+    /// Shortest time in game days a colony is given to fulfil a contract
+    #[serde(rename = "min_days")]
+    pub min_days: u32,
+
+    /// The fraction (0..1) added on top of the base value of the items as a
+    /// reward
+    #[serde(rename = "reward_bonus_pct")]
+    pub reward_bonus_pct: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiConfigDataCurrency {
+    /// Fraction (0..1) kept by the bank when exchanging currencies
+    #[serde(rename = "exchange_fee_pct")]
+    pub exchange_fee_pct: f32,
+
+    /// How many UTC one unit of each currency is worth, currencies without a
+    /// rate can't be used
+    #[serde(rename = "rates")]
+    pub rates: HashMap<String, f32>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ApiConfigDataInventory {
+    /// Items voted in for the first time, or voted a new base value, wait in the
+    /// moderation queue until an admin approves them
+    #[serde(rename = "moderation", default)]
+    pub moderation: bool,
+
     #[serde(rename = "vote_age_threshold")]
     pub vote_age_threshold: u32,
 
+    /// Weighted votes a new item needs before it's added to the inventory
     #[serde(rename = "vote_promotion_threshold")]
     pub vote_promotion_threshold: u32,
+
+    /// How much an account's vote counts, each account's best colony is used
+    /// and colonies that used dev mode don't count
+    #[serde(rename = "vote_weights", default)]
+    pub vote_weights: ApiConfigDataInventoryVoteWeights,
+}
+
+/// Read by the `summary_inventory_votes` view, its defaults have to match these.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ApiConfigDataInventoryVoteWeights {
+    /// Weight of an account that isn't linked to Steam
+    #[serde(rename = "drm_free_account")]
+    pub drm_free_account: f32,
+
+    /// Fraction (0..1) of the weight a brand new account gets, rising to all of
+    /// it at trusted_account_days
+    #[serde(rename = "new_account")]
+    pub new_account: f32,
+
+    /// Fraction (0..1) of the weight a brand new colony gets, rising to all of
+    /// it at trusted_colony_ticks
+    #[serde(rename = "new_colony")]
+    pub new_colony: f32,
+
+    /// Weight of an account linked to Steam
+    #[serde(rename = "steam_account")]
+    pub steam_account: f32,
+
+    #[serde(rename = "trusted_account_days")]
+    pub trusted_account_days: u32,
+
+    #[serde(rename = "trusted_colony_ticks")]
+    pub trusted_colony_ticks: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ApiConfigDataMarkets {
+    /// Markets colonies can trade in, each is a major game version like 1.3.
+    /// Leave it empty to open every market
+    #[serde(rename = "live")]
+    pub live: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct ApiConfigDataMaintenance {
     #[serde(rename = "start_time")]
     pub start_time: u32,
+
+    /// Daily trade statistics older than this are pruned once rolled up into
+    /// monthly totals
+    #[serde(rename = "statistics_retention_days")]
+    pub statistics_retention_days: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ApiConfigDataOrdersStaleOrderPolicy {
+    #[serde(rename = "expire")]
+    Expire,
+
+    #[serde(rename = "fail")]
+    Fail,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ApiConfigDataOrders {
+    /// Fraction (0..1) of the cost of the items refunded when a delivery
+    /// fails
+    #[serde(rename = "insurance_pct")]
+    pub insurance_pct: f32,
+
+    /// Open orders are closed by the scheduler once they're this many game
+    /// days past delivery
+    #[serde(rename = "overdue_days")]
+    pub overdue_days: u32,
+
+    /// Fraction (0..1) of the cost of the items kept when a colony cancels an
+    /// order
+    #[serde(rename = "restocking_fee_pct")]
+    pub restocking_fee_pct: f32,
+
+    /// Open orders are closed by the scheduler once their colony hasn't been
+    /// seen for this many days
+    #[serde(rename = "stale_colony_days")]
+    pub stale_colony_days: u32,
+
+    /// Expire refunds the items in full, fail only refunds what the insurance
+    /// covers
+    #[serde(rename = "stale_order_policy")]
+    pub stale_order_policy: ApiConfigDataOrdersStaleOrderPolicy,
 }
 
 #[derive(Serialize, Deserialize, FromSqlRow, AsExpression, Debug, Clone, Default)]
@@ -37,6 +178,15 @@ pub struct ApiConfigData {
     #[serde(rename = "api")]
     pub api: ApiConfigDataApi,
 
+    #[serde(rename = "bank", default)]
+    pub bank: ApiConfigDataBank,
+
+    #[serde(rename = "contracts", default)]
+    pub contracts: ApiConfigDataContracts,
+
+    #[serde(rename = "currency", default)]
+    pub currency: ApiConfigDataCurrency,
+
     #[serde(rename = "delivery")]
     pub delivery: ApiConfigDataDelivery,
 
@@ -45,4 +195,10 @@ pub struct ApiConfigData {
 
     #[serde(rename = "maintenance")]
     pub maintenance: ApiConfigDataMaintenance,
+
+    #[serde(rename = "markets", default)]
+    pub markets: ApiConfigDataMarkets,
+
+    #[serde(rename = "orders", default)]
+    pub orders: ApiConfigDataOrders,
 }
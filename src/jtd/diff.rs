@@ -0,0 +1,58 @@
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+/// One value that differs between two documents, `old` is None if it was
+/// added and `new` is None if it was removed.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct DiffEntry {
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+/// Compare two JSON documents, paths are JSON Pointers to the leaves that changed.
+pub fn diff(old: &Value, new: &Value) -> Vec<DiffEntry> {
+    let mut changes = Vec::new();
+    diff_into(String::new(), old, new, &mut changes);
+    changes
+}
+
+fn diff_into(path: String, old: &Value, new: &Value, changes: &mut Vec<DiffEntry>) {
+    match (old, new) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            // Sorted so the output is stable between calls
+            let keys: BTreeSet<&String> = old_map.keys().chain(new_map.keys()).collect();
+            for key in keys {
+                let child = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                match (old_map.get(key), new_map.get(key)) {
+                    (Some(o), Some(n)) => diff_into(child, o, n, changes),
+                    (o, n) => changes.push(DiffEntry {
+                        path: child,
+                        old: o.cloned(),
+                        new: n.cloned(),
+                    }),
+                }
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for index in 0..old_items.len().max(new_items.len()) {
+                let child = format!("{}/{}", path, index);
+                match (old_items.get(index), new_items.get(index)) {
+                    (Some(o), Some(n)) => diff_into(child, o, n, changes),
+                    (o, n) => changes.push(DiffEntry {
+                        path: child,
+                        old: o.cloned(),
+                        new: n.cloned(),
+                    }),
+                }
+            }
+        }
+        (o, n) if o != n => changes.push(DiffEntry {
+            path,
+            old: Some(o.clone()),
+            new: Some(n.clone()),
+        }),
+        _ => {}
+    }
+}
@@ -1,2 +1,4 @@
 pub mod api_config;
+pub mod diff;
 pub mod stock_config;
+pub mod validate;
@@ -0,0 +1,19 @@
+use crate::jtd::stock_config::{PriceThresholdDemand, StockConfigCondition};
+
+impl Default for StockConfigCondition {
+    fn default() -> Self {
+        StockConfigCondition {
+            used_health_threshold: 50f32,
+            used_value_pct: 0.5,
+        }
+    }
+}
+
+impl Default for PriceThresholdDemand {
+    fn default() -> Self {
+        PriceThresholdDemand {
+            elasticity: 0.25,
+            window_days: 7,
+        }
+    }
+}
@@ -1,7 +1,12 @@
-// Code generated by jtd-codegen for Rust v0.2.1
+// Originally generated by jtd-codegen for Rust v0.2.1, now maintained by hand.
+// Don't regenerate it, optional properties are plain fields with serde defaults
+// and their Default impls live in defaults.rs. Keep it in step with jtd/stock_config.jtd.json.
 
 use serde::{Deserialize, Serialize};
 
+pub mod defaults;
+pub mod ranges;
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct StockConfigPricing {
     #[serde(rename = "thresholds")]
@@ -23,6 +28,20 @@ pub struct StockConfigThreading {
     pub parallelism: u32,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct StockConfigCondition {
+    /// Items sold to us with less health (0..100) than this go into the used
+    /// stock line
+    #[serde(rename = "used_health_threshold")]
+    pub used_health_threshold: f32,
+
+    /// Fraction (0..1) of the new item's base value that the used stock line
+    /// is worth
+    #[serde(rename = "used_value_pct")]
+    pub used_value_pct: f32,
+}
+
 #[derive(Serialize, Deserialize, FromSqlRow, AsExpression, Debug, Clone, Default)]
 pub struct StockConfig {
     #[serde(rename = "pricing")]
@@ -33,6 +52,65 @@ pub struct StockConfig {
 
     #[serde(rename = "threading")]
     pub threading: StockConfigThreading,
+
+    #[serde(rename = "condition", default)]
+    pub condition: StockConfigCondition,
+
+    /// Per-item settings applied before the thresholds are looked up
+    #[serde(rename = "overrides", default)]
+    pub overrides: Vec<ItemOverride>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ItemOverride {
+    /// Always buy from colonies at this price
+    #[serde(rename = "buy_at")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buy_at: Option<f32>,
+
+    /// Override a single item, takes priority over a thing_def override
+    #[serde(rename = "item_code")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_code: Option<String>,
+
+    /// Replaces the restock threshold's max_quantity
+    #[serde(rename = "max_quantity")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_quantity: Option<u32>,
+
+    /// Replaces the restock threshold's min_quantity
+    #[serde(rename = "min_quantity")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_quantity: Option<u32>,
+
+    /// Refuse to buy it from colonies
+    #[serde(rename = "never_buy")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub never_buy: Option<bool>,
+
+    /// Only colonies selling it to us bring it back into stock
+    #[serde(rename = "never_restock")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub never_restock: Option<bool>,
+
+    /// Always sell to colonies at this price
+    #[serde(rename = "sell_at")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sell_at: Option<f32>,
+
+    /// Override every item of this ThingDef, whatever its quality or stuff
+    #[serde(rename = "thing_def")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thing_def: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ItemCondition {
+    #[serde(rename = "new")]
+    New,
+
+    #[serde(rename = "used")]
+    Used,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -53,11 +131,42 @@ pub struct PriceDeviation {
     pub unit_to_stock_ratio: u32,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum PriceStrategy {
+    #[serde(rename = "step")]
+    Step,
+
+    #[serde(rename = "demand")]
+    Demand,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PriceThresholdDemand {
+    /// Fraction (0..1) of the base value prices move by at full demand or full
+    /// surplus
+    #[serde(rename = "elasticity")]
+    pub elasticity: f32,
+
+    /// How many days of trade statistics to look back over, including today
+    #[serde(rename = "window_days")]
+    pub window_days: u32,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct PriceThreshold {
     #[serde(rename = "buying")]
     pub buying: PriceDeviation,
 
+    /// Only use this configuration for items in this condition, otherwise it
+    /// applies to any condition that doesn't have its own
+    #[serde(rename = "condition")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<ItemCondition>,
+
+    #[serde(rename = "demand", default)]
+    pub demand: PriceThresholdDemand,
+
     #[serde(rename = "price_end")]
     pub price_end: u32,
 
@@ -66,6 +175,11 @@ pub struct PriceThreshold {
 
     #[serde(rename = "selling")]
     pub selling: PriceDeviation,
+
+    /// How prices are moved, defaults to step
+    #[serde(rename = "strategy")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<PriceStrategy>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -75,6 +189,12 @@ pub struct StockThreshold {
     #[serde(rename = "chance_to_restock")]
     pub chance_to_restock: f32,
 
+    /// Only use this configuration for items in this condition, otherwise it
+    /// applies to any condition that doesn't have its own
+    #[serde(rename = "condition")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<ItemCondition>,
+
     /// This is the max amount of stock we want to have
     #[serde(rename = "max_quantity")]
     pub max_quantity: u32,
@@ -0,0 +1,105 @@
+use std::fmt;
+
+use crate::jtd::stock_config::{ItemCondition, StockConfig};
+
+/// A problem with the `price_start..price_end` ranges of a threshold list,
+/// `path` is the JSON Pointer of the threshold at fault.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub enum RangeProblem {
+    /// `price_start` is above `price_end`
+    Inverted { path: String },
+    /// Nothing covers the prices between `from` and `to`
+    Gap { path: String, from: u32, to: u32 },
+    /// The threshold covers some of the same prices as `other`,
+    /// only the first one would ever be used
+    Overlap { path: String, other: String },
+}
+
+impl fmt::Display for RangeProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeProblem::Inverted { path } => {
+                write!(f, "{} starts above where it ends", path)
+            }
+            RangeProblem::Gap { path, from, to } => {
+                write!(f, "Nothing covers {} to {} before {}", from, to, path)
+            }
+            RangeProblem::Overlap { path, other } => write!(f, "{} overlaps {}", path, other),
+        }
+    }
+}
+
+/// Check each condition tier's thresholds separately, tiers are allowed to overlap
+/// each other. Thresholds without a condition are the fallback for every tier so
+/// they must not have gaps, tiers only have to be continuous if there's no fallback.
+fn check_ranges(
+    base_path: &str,
+    ranges: Vec<(u32, u32, Option<ItemCondition>)>,
+) -> Vec<RangeProblem> {
+    let path = |i: usize| format!("{}/{}", base_path, i);
+    let mut problems = Vec::new();
+
+    for (i, (start, end, _)) in ranges.iter().enumerate() {
+        if start > end {
+            problems.push(RangeProblem::Inverted { path: path(i) });
+        }
+    }
+
+    let has_fallback = ranges.iter().any(|(_, _, tier)| tier.is_none());
+    for tier in [None, Some(ItemCondition::New), Some(ItemCondition::Used)].iter() {
+        let mut tiered: Vec<(usize, u32, u32)> = ranges
+            .iter()
+            .enumerate()
+            .filter(|(_, (start, end, t))| t == tier && start <= end)
+            .map(|(i, (start, end, _))| (i, *start, *end))
+            .collect();
+        tiered.sort_by_key(|(_, start, end)| (*start, *end));
+
+        // Ranges are inclusive, so one may start where the last one ended
+        let mut covered: Option<(usize, u32)> = None;
+        for (i, start, end) in tiered {
+            if let Some((last_i, last_end)) = covered {
+                if start < last_end {
+                    problems.push(RangeProblem::Overlap {
+                        path: path(i),
+                        other: path(last_i),
+                    });
+                } else if start > last_end && (tier.is_none() || !has_fallback) {
+                    problems.push(RangeProblem::Gap {
+                        path: path(i),
+                        from: last_end,
+                        to: start,
+                    });
+                }
+            }
+            if covered.map_or(true, |(_, last_end)| end >= last_end) {
+                covered = Some((i, end));
+            }
+        }
+    }
+    problems
+}
+
+/// Find gaps and overlaps in the pricing and restock thresholds, the schema can't
+/// catch these but the market routine quietly skips any item they leave uncovered.
+pub fn find_range_problems(config: &StockConfig) -> Vec<RangeProblem> {
+    let mut problems = check_ranges(
+        "/pricing/thresholds",
+        config
+            .pricing
+            .thresholds
+            .iter()
+            .map(|t| (t.price_start, t.price_end, t.condition))
+            .collect(),
+    );
+    problems.extend(check_ranges(
+        "/restock/thresholds",
+        config
+            .restock
+            .thresholds
+            .iter()
+            .map(|t| (t.price_start, t.price_end, t.condition))
+            .collect(),
+    ));
+    problems
+}
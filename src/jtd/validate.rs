@@ -0,0 +1,249 @@
+use chrono::DateTime;
+use serde_json::{Map, Value};
+
+/// Refs can point back at themselves, stop following them after this many.
+const MAX_DEPTH: usize = 32;
+
+pub const API_CONFIG_SCHEMA: &'static str = include_str!("../../jtd/api_config_data.jtd.json");
+pub const STOCK_CONFIG_SCHEMA: &'static str = include_str!("../../jtd/stock_config.jtd.json");
+
+/// A single place where the document doesn't match the schema,
+/// both paths are JSON Pointers as described in RFC 8927.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct ValidationError {
+    pub instance_path: String,
+    pub schema_path: String,
+}
+
+struct Validator<'a> {
+    definitions: Option<&'a Map<String, Value>>,
+    instance_path: Vec<String>,
+    schema_path: Vec<String>,
+    errors: Vec<ValidationError>,
+}
+
+/// Validate a JSON document against a JSON Type Definition schema,
+/// returns every error found, an empty list means the document is valid.
+pub fn validate(schema: &Value, instance: &Value) -> Vec<ValidationError> {
+    let mut validator = Validator {
+        definitions: schema.get("definitions").and_then(Value::as_object),
+        instance_path: Vec::new(),
+        schema_path: Vec::new(),
+        errors: Vec::new(),
+    };
+    validator.check(schema, instance, None, 0);
+    validator.errors
+}
+
+fn to_pointer(path: &Vec<String>) -> String {
+    path.iter()
+        .map(|p| format!("/{}", p.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+impl<'a> Validator<'a> {
+    fn push_error(&mut self) {
+        self.errors.push(ValidationError {
+            instance_path: to_pointer(&self.instance_path),
+            schema_path: to_pointer(&self.schema_path),
+        });
+    }
+
+    /// Record an error with an extra segment on the schema path
+    fn push_error_at(&mut self, schema_token: &str) {
+        self.schema_path.push(schema_token.to_owned());
+        self.push_error();
+        self.schema_path.pop();
+    }
+
+    fn check(&mut self, schema: &'a Value, instance: &Value, tag: Option<&str>, depth: usize) {
+        if depth > MAX_DEPTH {
+            self.push_error();
+            return;
+        }
+
+        if schema.get("nullable").and_then(Value::as_bool) == Some(true) && instance.is_null() {
+            return;
+        }
+
+        if let Some(name) = schema.get("ref").and_then(Value::as_str) {
+            let saved = std::mem::replace(
+                &mut self.schema_path,
+                vec!["definitions".to_owned(), name.to_owned()],
+            );
+            match self.definitions.and_then(|d| d.get(name)) {
+                Some(definition) => self.check(definition, instance, None, depth + 1),
+                None => self.push_error(),
+            }
+            self.schema_path = saved;
+        } else if let Some(type_name) = schema.get("type").and_then(Value::as_str) {
+            if !check_type(type_name, instance) {
+                self.push_error_at("type");
+            }
+        } else if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            if !instance.is_string() || !values.contains(instance) {
+                self.push_error_at("enum");
+            }
+        } else if let Some(elements) = schema.get("elements") {
+            match instance.as_array() {
+                None => self.push_error_at("elements"),
+                Some(items) => {
+                    self.schema_path.push("elements".to_owned());
+                    for (index, item) in items.iter().enumerate() {
+                        self.instance_path.push(index.to_string());
+                        self.check(elements, item, None, depth + 1);
+                        self.instance_path.pop();
+                    }
+                    self.schema_path.pop();
+                }
+            }
+        } else if schema.get("properties").is_some() || schema.get("optionalProperties").is_some() {
+            self.check_properties(schema, instance, tag, depth);
+        } else if let Some(values) = schema.get("values") {
+            match instance.as_object() {
+                None => self.push_error_at("values"),
+                Some(object) => {
+                    self.schema_path.push("values".to_owned());
+                    for (key, value) in object {
+                        self.instance_path.push(key.clone());
+                        self.check(values, value, None, depth + 1);
+                        self.instance_path.pop();
+                    }
+                    self.schema_path.pop();
+                }
+            }
+        } else if let Some(discriminator) = schema.get("discriminator").and_then(Value::as_str) {
+            self.check_discriminator(schema, discriminator, instance, depth);
+        }
+        // Anything else is the empty form, which accepts everything.
+    }
+
+    fn check_properties(
+        &mut self,
+        schema: &'a Value,
+        instance: &Value,
+        tag: Option<&str>,
+        depth: usize,
+    ) {
+        let required = schema.get("properties").and_then(Value::as_object);
+        let optional = schema.get("optionalProperties").and_then(Value::as_object);
+
+        let object = match instance.as_object() {
+            Some(object) => object,
+            None => {
+                self.push_error_at(if required.is_some() {
+                    "properties"
+                } else {
+                    "optionalProperties"
+                });
+                return;
+            }
+        };
+
+        for (form, properties, must_exist) in vec![
+            ("properties", required, true),
+            ("optionalProperties", optional, false),
+        ] {
+            let properties = match properties {
+                Some(p) => p,
+                None => continue,
+            };
+            self.schema_path.push(form.to_owned());
+            for (key, property_schema) in properties {
+                self.schema_path.push(key.clone());
+                match object.get(key) {
+                    Some(value) => {
+                        self.instance_path.push(key.clone());
+                        self.check(property_schema, value, None, depth + 1);
+                        self.instance_path.pop();
+                    }
+                    None if must_exist => self.push_error(),
+                    None => {}
+                }
+                self.schema_path.pop();
+            }
+            self.schema_path.pop();
+        }
+
+        if schema.get("additionalProperties").and_then(Value::as_bool) != Some(true) {
+            for key in object.keys() {
+                let known = required.map_or(false, |p| p.contains_key(key))
+                    || optional.map_or(false, |p| p.contains_key(key))
+                    || tag == Some(key.as_str());
+                if !known {
+                    self.instance_path.push(key.clone());
+                    self.push_error();
+                    self.instance_path.pop();
+                }
+            }
+        }
+    }
+
+    fn check_discriminator(
+        &mut self,
+        schema: &'a Value,
+        discriminator: &str,
+        instance: &Value,
+        depth: usize,
+    ) {
+        let object = match instance.as_object() {
+            Some(object) => object,
+            None => return self.push_error_at("discriminator"),
+        };
+
+        let tag = match object.get(discriminator) {
+            None => return self.push_error_at("discriminator"),
+            Some(tag) => tag,
+        };
+
+        let tag = match tag.as_str() {
+            None => {
+                self.instance_path.push(discriminator.to_owned());
+                self.push_error_at("discriminator");
+                self.instance_path.pop();
+                return;
+            }
+            Some(tag) => tag,
+        };
+
+        match schema.get("mapping").and_then(|m| m.get(tag)) {
+            None => {
+                self.instance_path.push(discriminator.to_owned());
+                self.push_error_at("mapping");
+                self.instance_path.pop();
+            }
+            Some(mapping) => {
+                self.schema_path.push("mapping".to_owned());
+                self.schema_path.push(tag.to_owned());
+                self.check(mapping, instance, Some(discriminator), depth + 1);
+                self.schema_path.pop();
+                self.schema_path.pop();
+            }
+        }
+    }
+}
+
+fn check_type(type_name: &str, instance: &Value) -> bool {
+    match type_name {
+        "boolean" => instance.is_boolean(),
+        "string" => instance.is_string(),
+        "timestamp" => instance
+            .as_str()
+            .map_or(false, |v| DateTime::parse_from_rfc3339(v).is_ok()),
+        "float32" | "float64" => instance.is_number(),
+        "int8" => check_integer(instance, -128f64, 127f64),
+        "uint8" => check_integer(instance, 0f64, 255f64),
+        "int16" => check_integer(instance, -32_768f64, 32_767f64),
+        "uint16" => check_integer(instance, 0f64, 65_535f64),
+        "int32" => check_integer(instance, -2_147_483_648f64, 2_147_483_647f64),
+        "uint32" => check_integer(instance, 0f64, 4_294_967_295f64),
+        _ => false,
+    }
+}
+
+/// JTD integers are any number without a fractional part, so `1.0` is fine.
+fn check_integer(instance: &Value, min: f64, max: f64) -> bool {
+    instance
+        .as_f64()
+        .map_or(false, |v| v.fract() == 0f64 && v >= min && v <= max)
+}
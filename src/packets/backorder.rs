@@ -0,0 +1,70 @@
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BackorderData {
+    #[prost(string, tag="1")]
+    pub backorder_id: std::string::String,
+    #[prost(string, tag="2")]
+    pub item_code: std::string::String,
+    #[prost(int32, tag="3")]
+    pub quantity: i32,
+    #[prost(float, tag="4")]
+    pub health: f32,
+    /// Amount that was paid for the items, refunded if cancelled
+    #[prost(int32, tag="5")]
+    pub paid: i32,
+    #[prost(enumeration="super::common::CurrencyEnum", tag="6")]
+    pub currency: i32,
+    #[prost(enumeration="BackorderStatusEnum", tag="7")]
+    pub status: i32,
+    /// The order that will deliver the items, only set once filled
+    #[prost(string, tag="8")]
+    pub order_id: std::string::String,
+    #[prost(int32, tag="9")]
+    pub placed_tick: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BackorderListRequest {
+    #[prost(string, tag="1")]
+    pub client_bind_id: std::string::String,
+    #[prost(string, tag="2")]
+    pub colony_id: std::string::String,
+    /// Include backorders that are filled or cancelled
+    #[prost(bool, tag="3")]
+    pub any: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BackorderListReply {
+    #[prost(message, repeated, tag="1")]
+    pub backorders: ::std::vec::Vec<BackorderData>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BackorderCancelRequest {
+    #[prost(string, tag="1")]
+    pub client_bind_id: std::string::String,
+    #[prost(string, tag="2")]
+    pub colony_id: std::string::String,
+    #[prost(string, tag="3")]
+    pub backorder_id: std::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BackorderCancelReply {
+    #[prost(bool, tag="1")]
+    pub success: bool,
+    #[prost(int32, tag="2")]
+    pub refunded: i32,
+    #[prost(int32, tag="3")]
+    pub balance: i32,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+#[derive(Serialize, Deserialize)]
+#[derive(TryFromPrimitive)]
+pub enum BackorderStatusEnum {
+    Pending = 0,
+    Filled = 1,
+    Cancelled = 2,
+}
@@ -3,6 +3,8 @@
 pub struct BankDataReply {
     #[prost(map="int32, int32", tag="1")]
     pub balance: ::std::collections::HashMap<i32, i32>,
+    #[prost(map="int32, float", tag="2")]
+    pub exchange_rates: ::std::collections::HashMap<i32, f32>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -34,3 +36,208 @@ pub struct BankWithdrawReply {
     #[prost(int32, tag="5")]
     pub balance: i32,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BankExchangeRequest {
+    #[prost(string, tag="1")]
+    pub client_bind_id: std::string::String,
+    #[prost(string, tag="2")]
+    pub colony_id: std::string::String,
+    #[prost(enumeration="super::common::CurrencyEnum", tag="3")]
+    pub from_currency: i32,
+    #[prost(enumeration="super::common::CurrencyEnum", tag="4")]
+    pub to_currency: i32,
+    #[prost(int32, tag="5")]
+    pub amount: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BankExchangeReply {
+    #[prost(bool, tag="1")]
+    pub success: bool,
+    #[prost(int32, tag="2")]
+    pub received: i32,
+    #[prost(map="int32, int32", tag="3")]
+    pub balance: ::std::collections::HashMap<i32, i32>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BankTransferData {
+    #[prost(string, tag="1")]
+    pub transfer_id: std::string::String,
+    #[prost(string, tag="2")]
+    pub from_colony_id: std::string::String,
+    #[prost(string, tag="3")]
+    pub to_colony_id: std::string::String,
+    #[prost(enumeration="super::common::CurrencyEnum", tag="4")]
+    pub currency: i32,
+    #[prost(int32, tag="5")]
+    pub amount: i32,
+    #[prost(enumeration="BankTransferStatusEnum", tag="6")]
+    pub status: i32,
+    /// Unix timestamp
+    #[prost(int64, tag="7")]
+    pub create_date: i64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BankTransferRequest {
+    #[prost(string, tag="1")]
+    pub client_bind_id: std::string::String,
+    /// The colony sending the money
+    #[prost(string, tag="2")]
+    pub colony_id: std::string::String,
+    #[prost(string, tag="3")]
+    pub to_colony_id: std::string::String,
+    #[prost(enumeration="super::common::CurrencyEnum", tag="4")]
+    pub currency: i32,
+    #[prost(int32, tag="5")]
+    pub amount: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BankTransferReply {
+    #[prost(bool, tag="1")]
+    pub success: bool,
+    #[prost(message, optional, tag="2")]
+    pub data: ::std::option::Option<BankTransferData>,
+    #[prost(int32, tag="3")]
+    pub balance: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BankTransferConfirmRequest {
+    #[prost(string, tag="1")]
+    pub client_bind_id: std::string::String,
+    /// The colony receiving the money
+    #[prost(string, tag="2")]
+    pub colony_id: std::string::String,
+    #[prost(string, tag="3")]
+    pub transfer_id: std::string::String,
+    /// False to decline the transfer and return the money to the sender
+    #[prost(bool, tag="4")]
+    pub accept: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BankTransferConfirmReply {
+    #[prost(bool, tag="1")]
+    pub success: bool,
+    #[prost(int32, tag="2")]
+    pub balance: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BankTransferCancelRequest {
+    #[prost(string, tag="1")]
+    pub client_bind_id: std::string::String,
+    /// The colony that sent the money
+    #[prost(string, tag="2")]
+    pub colony_id: std::string::String,
+    #[prost(string, tag="3")]
+    pub transfer_id: std::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BankTransferCancelReply {
+    #[prost(bool, tag="1")]
+    pub success: bool,
+    #[prost(int32, tag="2")]
+    pub balance: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BankTransferListRequest {
+    #[prost(string, tag="1")]
+    pub client_bind_id: std::string::String,
+    #[prost(string, tag="2")]
+    pub colony_id: std::string::String,
+    /// Include transfers that are no longer pending
+    #[prost(bool, tag="3")]
+    pub any: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BankTransferListReply {
+    #[prost(message, repeated, tag="1")]
+    pub transfers: ::std::vec::Vec<BankTransferData>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BankLedgerEntryData {
+    #[prost(int64, tag="1")]
+    pub entry_id: i64,
+    #[prost(enumeration="super::common::CurrencyEnum", tag="2")]
+    pub currency: i32,
+    #[prost(int32, tag="3")]
+    pub amount: i32,
+    /// The balance after this entry was applied
+    #[prost(int32, tag="4")]
+    pub balance: i32,
+    #[prost(enumeration="BankLedgerKindEnum", tag="5")]
+    pub kind: i32,
+    /// The order, backorder or transfer that caused the entry, empty if none
+    #[prost(string, tag="6")]
+    pub reference_id: std::string::String,
+    /// Unix timestamp
+    #[prost(int64, tag="7")]
+    pub create_date: i64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BankHistoryRequest {
+    #[prost(string, tag="1")]
+    pub client_bind_id: std::string::String,
+    #[prost(string, tag="2")]
+    pub colony_id: std::string::String,
+    #[prost(enumeration="super::common::CurrencyEnum", tag="3")]
+    pub currency: i32,
+    /// Only return entries older than this one, 0 for the newest
+    #[prost(int64, tag="4")]
+    pub before_entry_id: i64,
+    #[prost(int32, tag="5")]
+    pub limit: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct BankHistoryReply {
+    /// Newest first
+    #[prost(message, repeated, tag="1")]
+    pub entries: ::std::vec::Vec<BankLedgerEntryData>,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+#[derive(Serialize, Deserialize)]
+#[derive(TryFromPrimitive)]
+pub enum BankTransferStatusEnum {
+    /// Waiting for the recipient to accept it
+    Pending = 0,
+    Completed = 1,
+    Declined = 2,
+    /// Called off by the sender before it was accepted
+    Cancelled = 3,
+    /// Not accepted in time, the money went back to the sender
+    Expired = 4,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+#[derive(Serialize, Deserialize)]
+#[derive(TryFromPrimitive)]
+pub enum BankLedgerKindEnum {
+    /// The balance carried over from before the ledger existed
+    Opening = 0,
+    /// Buying or selling items, including funds sent with the order
+    Order = 1,
+    /// Items that couldn't be supplied or a cancelled backorder
+    Refund = 2,
+    Withdrawal = 3,
+    Rollback = 4,
+    AdminAdjustment = 5,
+    Transfer = 6,
+    Exchange = 7,
+    Backorder = 8,
+    /// Kept when a colony cancels an order
+    RestockingFee = 9,
+    /// Paid out when a delivery fails
+    Insurance = 10,
+}
@@ -5,4 +5,6 @@
 pub enum CurrencyEnum {
     /// Universal Trade Credits ᛊ
     Utc = 0,
+    /// Paid out as Gold
+    Gold = 1,
 }
@@ -0,0 +1,83 @@
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct ContractData {
+    /// Signed Contract ID, must be sent back unaltered
+    /// when accepting or fulfilling the contract.
+    #[prost(string, tag="1")]
+    pub contract_id: std::string::String,
+    #[prost(string, tag="2")]
+    pub item_code: std::string::String,
+    #[prost(string, tag="3")]
+    pub thing_def: std::string::String,
+    #[prost(int32, tag="4")]
+    pub quality: i32,
+    #[prost(string, tag="5")]
+    pub stuff: std::string::String,
+    #[prost(bool, tag="6")]
+    pub minified: bool,
+    /// Number of items that must be delivered
+    #[prost(int32, tag="7")]
+    pub quantity: i32,
+    /// Amount paid into the bank once the contract is fulfilled
+    #[prost(int32, tag="8")]
+    pub reward: i32,
+    #[prost(enumeration="super::common::CurrencyEnum", tag="9")]
+    pub currency: i32,
+    /// The colony tick that the items must be delivered by
+    #[prost(int32, tag="10")]
+    pub deadline_tick: i32,
+    #[prost(enumeration="ContractStatusEnum", tag="11")]
+    pub status: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct ContractListRequest {
+    #[prost(string, tag="1")]
+    pub client_bind_id: std::string::String,
+    #[prost(string, tag="2")]
+    pub colony_id: std::string::String,
+    #[prost(int32, tag="3")]
+    pub colony_tick: i32,
+    /// Include contracts that are fulfilled or expired
+    #[prost(bool, tag="4")]
+    pub any: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct ContractListReply {
+    #[prost(message, repeated, tag="1")]
+    pub contracts: ::std::vec::Vec<ContractData>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct ContractUpdateRequest {
+    #[prost(string, tag="1")]
+    pub client_bind_id: std::string::String,
+    #[prost(string, tag="2")]
+    pub colony_id: std::string::String,
+    #[prost(string, tag="3")]
+    pub contract_id: std::string::String,
+    #[prost(int32, tag="4")]
+    pub colony_tick: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct ContractUpdateReply {
+    #[prost(bool, tag="1")]
+    pub success: bool,
+    #[prost(message, optional, tag="2")]
+    pub data: ::std::option::Option<ContractData>,
+    /// Bank balance after the update, only set when fulfilling.
+    #[prost(int32, tag="3")]
+    pub balance: i32,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+#[derive(Serialize, Deserialize)]
+#[derive(TryFromPrimitive)]
+pub enum ContractStatusEnum {
+    Offered = 0,
+    Accepted = 1,
+    Fulfilled = 2,
+    Expired = 3,
+}
@@ -4,6 +4,9 @@
 pub struct HelloRequest {
     #[prost(string, tag="1")]
     pub client_version: std::string::String,
+    /// RimWorld version the client is running, optional
+    #[prost(string, tag="2")]
+    pub game_version: std::string::String,
 }
 /// The response message containing the server version
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -11,4 +14,10 @@ pub struct HelloRequest {
 pub struct HelloReply {
     #[prost(string, tag="1")]
     pub server_version: std::string::String,
+    /// Markets open for trade, empty if they all are
+    #[prost(string, repeated, tag="2")]
+    pub markets: ::std::vec::Vec<std::string::String>,
+    /// Market the client's game version trades in, empty if it wasn't sent or can't be read
+    #[prost(string, tag="3")]
+    pub market: std::string::String,
 }
@@ -93,3 +93,52 @@ pub struct GeneratePromiseReply {
     #[prost(int64, tag="2")]
     pub inventory_promise_expires: i64,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct PriceHistoryRequest {
+    #[prost(string, tag="1")]
+    pub client_bind_id: std::string::String,
+    #[prost(string, tag="2")]
+    pub colony_id: std::string::String,
+    /// Signed item code, as sent in the inventory reply
+    #[prost(string, tag="3")]
+    pub item_code: std::string::String,
+    /// How many days of history to return
+    #[prost(int32, tag="4")]
+    pub days: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct PricePoint {
+    /// UTC Epoch timestamp of when the prices were recorded
+    #[prost(int64, tag="1")]
+    pub date: i64,
+    #[prost(float, tag="2")]
+    pub base_value: f32,
+    #[prost(float, tag="3")]
+    pub we_buy_at: f32,
+    #[prost(float, tag="4")]
+    pub we_sell_at: f32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[derive(Serialize, Deserialize)]
+pub struct PriceHistoryReply {
+    /// Signed item code, as sent in the request
+    #[prost(string, tag="1")]
+    pub item_code: std::string::String,
+    /// Oldest first
+    #[prost(message, repeated, tag="2")]
+    pub history: ::std::vec::Vec<PricePoint>,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+#[derive(Serialize, Deserialize)]
+#[derive(TryFromPrimitive)]
+pub enum ModerationStatusEnum {
+    /// Voted in, waiting for an admin
+    Pending = 0,
+    /// Added to the inventory
+    Approved = 1,
+    /// Never added, the same version isn't queued again
+    Rejected = 2,
+}
@@ -1,7 +1,9 @@
+pub mod backorder;
 pub mod bank;
 pub mod bind;
 pub mod colony;
 pub mod common;
+pub mod contract;
 pub mod hello;
 pub mod inventory;
 pub mod order;
@@ -45,6 +45,9 @@ pub struct OrderRequest {
     pub currency: i32,
     #[prost(int32, tag="8")]
     pub additional_funds: i32,
+    /// Backorder any items that are out of stock instead of refunding them
+    #[prost(bool, tag="9")]
+    pub allow_backorder: bool,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -89,6 +92,9 @@ pub struct OrderUpdateRequest {
     pub status: i32,
     #[prost(int32, tag="5")]
     pub colony_tick: i32,
+    /// Only used when the status is Failed
+    #[prost(enumeration="OrderFailureReasonEnum", tag="6")]
+    pub failure_reason: i32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -115,6 +121,9 @@ pub struct OrderReply {
     pub refunded: i32,
     #[prost(int32, tag="5")]
     pub balance: i32,
+    /// Items that were out of stock and have been backordered
+    #[prost(message, repeated, tag="6")]
+    pub backordered: ::std::vec::Vec<OrderItem>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -138,6 +147,24 @@ pub enum OrderStatusEnum {
     Delivered = 2,
     Failed = 3,
     Reversed = 4,
+    /// Cancelled by the colony before it was dispatched
+    Cancelled = 5,
+    /// Closed by the server after the colony stopped updating it
+    Expired = 6,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+#[derive(Serialize, Deserialize)]
+#[derive(TryFromPrimitive)]
+pub enum OrderFailureReasonEnum {
+    Unknown = 0,
+    /// The drop pods were lost in a raid
+    Raid = 1,
+    PodDestroyed = 2,
+    /// The colony left the map before the delivery arrived
+    MapLeft = 3,
+    /// Closed by the server after the colony stopped updating it
+    Abandoned = 4,
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
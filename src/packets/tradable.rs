@@ -21,6 +21,8 @@ pub struct Tradable {
     pub stuff: std::string::String,
     #[prost(float, tag="45")]
     pub weight: f32,
+    #[prost(enumeration="ItemConditionEnum", tag="50")]
+    pub condition: i32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 #[derive(Serialize, Deserialize)]
@@ -38,3 +40,12 @@ pub struct ColonyTradable {
     #[prost(string, tag="35")]
     pub stuff: std::string::String,
 }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+#[derive(Serialize, Deserialize)]
+#[derive(TryFromPrimitive)]
+pub enum ItemConditionEnum {
+    New = 0,
+    /// Sold to us worn out, pooled into a separate discounted stock line
+    Used = 1,
+}
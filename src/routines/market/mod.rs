@@ -1,19 +1,41 @@
-use chrono::{Duration, NaiveDateTime, Utc};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
 
 use crate::db::{get_pg_connection, Ppc};
 
+use crate::cache::blacklist::get_blacklist;
+use crate::cache::config::get_latest_stock_config;
+use crate::cache::inventory::bump_inventory_generation;
 use crate::crypto::hash_short_identity_string;
 use crate::db::models::api_config::ApiConfig;
 use crate::db::models::inventory::{Inventory, InventoryNoQuantity, TempInventoryVote};
-use crate::db::models::maintenance::Maintenance;
-use crate::db::models::stock_config::StockConfigRow;
+use crate::db::models::maintenance::{Maintenance, MaintenanceReport, ThresholdMiss};
+use crate::db::models::pending_inventory::PendingInventory;
 use crate::db::models::trade_stats::TradeStatistic;
 
 use crate::jtd::stock_config::{
-    PriceThreshold, StockConfig, StockConfigPricing, StockConfigRestock, StockThreshold,
+    ItemCondition, ItemOverride, PriceStrategy, PriceThreshold, StockConfig, StockConfigPricing,
+    StockConfigRestock, StockThreshold,
+};
+use crate::packets::common::CurrencyEnum;
+use crate::packets::tradable::ItemConditionEnum;
+use crate::routines::market::pricing::{
+    strategy_for, MarketActivity, PricingStrategy, StepPricing,
 };
 use crate::routines::system::utc_midnight;
-use crate::structs::inventory::SILVER_ITEM;
+use crate::structs::backorder::fill_pending_backorders;
+use crate::structs::bank_ledger::reconcile_bank_balances;
+use crate::structs::blacklist::Blacklist;
+use crate::structs::colony_mods::{
+    clear_contradicted_mod_tags, get_mod_statistics, get_voter_mods, infer_item_mod,
+};
+use crate::structs::currency::{currency_item, currency_item_for_market, is_currency_item};
+use crate::structs::market::get_markets;
+use crate::structs::pending_inventory::queue_pending_inventory;
+use crate::structs::price_tracker::record_price_snapshots;
+use crate::structs::threshold_miss::{
+    clear_threshold_misses, record_threshold_misses, summarise_threshold_misses,
+};
+use crate::structs::trade_stats::StatisticsRollup;
 
 use crate::traits::numerical::{CanRound, Percentage};
 use bigdecimal::BigDecimal;
@@ -21,10 +43,17 @@ use diesel::connection::SimpleConnection;
 use diesel::expression::dsl::any;
 use diesel::pg::upsert::excluded;
 use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{Date, Text};
 use fastrand::Rng;
 use itertools::Itertools;
 use rand::{thread_rng, Rng as randRng};
+use strum::IntoEnumIterator;
 use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+pub mod pricing;
+pub mod simulate;
 
 pub async fn perform_maintenance(api_config: &ApiConfig) {
     let start_time = Utc::now().naive_utc();
@@ -33,15 +62,73 @@ pub async fn perform_maintenance(api_config: &ApiConfig) {
         utc_midnight() + Duration::seconds(api_config.config_data.maintenance.start_time as i64);
 
     let conn = &mut get_pg_connection();
-    if update_maintenance_table(true, Some(scheduled_time), Some(start_time), conn).is_err() {
+    if update_maintenance_table(true, Some(scheduled_time), Some(start_time), None, conn).is_err()
+    {
         warn!("Unable to update maintenance table! Another node might already be running maintenance.");
         return;
     }
-    process_votes(conn, api_config);
-    // Updates stock and pricing
-    process_market_data(conn, api_config);
-    update_maintenance_table(false, Some(scheduled_time), Some(start_time), conn)
-        .expect("Unable to update maintenance table!");
+
+    // Each market gets a pass of its own, they don't share stock, prices or votes.
+    let markets = get_markets(conn).unwrap_or_else(|e| {
+        error!("Unable to list markets, {}", e);
+        Vec::new()
+    });
+    for market in markets.iter() {
+        info!("Processing the {} market", market);
+        process_votes(conn, api_config, market);
+        match clear_contradicted_mod_tags(market, conn) {
+            Ok(0) => {}
+            Ok(cleared) => info!(
+                "Removed {} mod tags that didn't match colony data in {}",
+                cleared, market
+            ),
+            Err(e) => warn!("Failed to check inventory mod tags in {}, {}", market, e),
+        }
+        // Updates stock and pricing
+        process_market_data(conn, api_config, market);
+    }
+    // Prices and stock have all changed, drop everything in the cache.
+    bump_inventory_generation();
+
+    let mut report = MaintenanceReport {
+        markets: Some(markets),
+        ..Default::default()
+    };
+    if let Ok(rollup) = process_statistics_rollup(conn, api_config) {
+        info!(
+            "Trade statistics rolled up, {} monthly rows inserted, {} daily rows pruned",
+            rollup.monthly_rows_inserted, rollup.daily_rows_pruned
+        );
+        report.statistics_rollup = Some(rollup);
+    }
+
+    match summarise_threshold_misses(conn) {
+        Ok(summary) => {
+            if summary.missing_price > 0 || summary.missing_stock > 0 {
+                warn!(
+                    "{} items have no pricing threshold and {} have no restock threshold",
+                    summary.missing_price, summary.missing_stock
+                );
+            }
+            report.threshold_misses = Some(summary);
+        }
+        Err(e) => warn!("Failed to count threshold misses, {}", e),
+    }
+
+    // Balances should always match the ledger, anything that doesn't needs looking at.
+    match reconcile_bank_balances(conn) {
+        Ok(mismatches) => report.ledger_mismatches = Some(mismatches.len()),
+        Err(e) => warn!("Failed to reconcile bank balances, {}", e),
+    }
+
+    update_maintenance_table(
+        false,
+        Some(scheduled_time),
+        Some(start_time),
+        Some(report),
+        conn,
+    )
+    .expect("Unable to update maintenance table!");
 
     info!("Completed database maintenance")
 }
@@ -50,6 +137,7 @@ fn update_maintenance_table(
     in_progress: bool,
     start_time: Option<NaiveDateTime>,
     execution_time: Option<NaiveDateTime>,
+    report: Option<MaintenanceReport>,
     conn: &Ppc,
 ) -> Result<(), ()> {
     use crate::db::schema::maintenance as m_schema;
@@ -75,6 +163,7 @@ fn update_maintenance_table(
                     start_time,
                     execution_time,
                     node_name: Some(node_name),
+                    report: report.clone(),
                 })
                 .execute(conn)
                 .is_err()
@@ -89,27 +178,29 @@ fn update_maintenance_table(
 }
 
 fn get_stock_configuration(conn: &Ppc) -> StockConfig {
-    use crate::db::schema::stock_config as sc;
-    sc::table
-        .order_by(sc::version.desc())
-        .first::<StockConfigRow>(conn)
+    get_latest_stock_config(conn)
         .expect("Stock configuration is missing!")
         .config_data
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct TradeStatsPair {
     pub bought: i64,
     pub sold: i64,
 }
 
-fn get_stock_movements(conn: &Ppc, items: &Vec<Inventory>) -> HashMap<String, TradeStatsPair> {
+fn get_stock_movements(
+    conn: &Ppc,
+    market: &str,
+    items: &Vec<Inventory>,
+) -> HashMap<String, TradeStatsPair> {
     use crate::db::schema::trade_statistics as schema;
     let today = Utc::today().naive_utc();
     let item_codes: Vec<&String> = items.iter().map(|i| &i.item_code).collect();
     let mut stats = HashMap::<String, TradeStatsPair>::with_capacity(items.len());
 
     let results: Vec<TradeStatistic> = schema::table
+        .filter(schema::market.eq(market))
         .filter(schema::item_code.eq(any(item_codes)))
         .filter(schema::date.eq(today))
         .load(conn)
@@ -137,69 +228,18 @@ fn get_stock_movements(conn: &Ppc, items: &Vec<Inventory>) -> HashMap<String, Tr
     stats
 }
 
+/// Step pricing using only today's trade, see `StepPricing`
 pub fn update_item_price(
     config: PriceThreshold,
-    mut item: Inventory,
+    item: Inventory,
     stock_stats: Option<TradeStatsPair>,
-    _rng: &Rng,
+    rng: &Rng,
 ) -> Inventory {
-    let stock_stats = stock_stats.unwrap_or_default();
-
-    // Sale price adjust
-    if stock_stats.bought > 0 {
-        // There was trade, we increase the price we sell at by a number of steps up to max
-        let step = item.base_value.percent_fraction(config.selling.step_size);
-        let units = stock_stats.bought / (config.selling.unit_to_stock_ratio as i64);
-        item.sell_at = &item.base_value
-            + (step * BigDecimal::from(units.clamp(0, config.selling.max_steps as i64)));
-    } else {
-        // We didn't sell any today, decrease the price a step down to min.
-        let step = item.base_value.percent_fraction(config.selling.step_size);
-        item.sell_at = &item.base_value - step;
-    }
-    // Buy price adjust
-    if stock_stats.sold > 0 {
-        // There was trade, we decrease the price we buy at
-        let step = item.base_value.percent_fraction(config.buying.step_size);
-        let units = stock_stats.sold / (config.buying.unit_to_stock_ratio as i64);
-        item.buy_at = &item.base_value
-            - (step * BigDecimal::from(units.clamp(0, config.buying.max_steps as i64)));
-    } else {
-        // We didn't buy any today, increase the price a step.
-        let step = item.base_value.percent_fraction(config.selling.step_size);
-        item.buy_at = &item.base_value + step;
-    }
-
-    // Clamp the values to make sure they don't go out of range,
-    // It also fixes the values if the base value changes due to a new version.
-    item.sell_at = item
-        .sell_at
-        .clamp(
-            &item.base_value
-                - item
-                    .base_value
-                    .percent_fraction(config.selling.max_price_decrease_pct),
-            &item.base_value
-                + item
-                    .base_value
-                    .percent_fraction(config.selling.max_price_increase_pct),
-        )
-        .round_2dp();
-
-    item.buy_at = item
-        .buy_at
-        .clamp(
-            &item.base_value
-                - item
-                    .base_value
-                    .percent_fraction(config.buying.max_price_decrease_pct),
-            &item.base_value
-                + item
-                    .base_value
-                    .percent_fraction(config.buying.max_price_increase_pct),
-        )
-        .round_2dp();
-    item
+    let activity = MarketActivity {
+        today: stock_stats.unwrap_or_default(),
+        ..Default::default()
+    };
+    StepPricing { config }.update_price(item, &activity, rng)
 }
 
 pub fn update_item_stock(config: StockThreshold, mut item: Inventory, rng: &Rng) -> Inventory {
@@ -231,54 +271,187 @@ pub fn update_item_stock(config: StockThreshold, mut item: Inventory, rng: &Rng)
     item
 }
 
-fn get_price_threshold(config: &StockConfigPricing, item: &Inventory) -> Option<PriceThreshold> {
-    config
-        .thresholds
-        .iter()
-        .find(|threshold| {
-            (BigDecimal::from(threshold.price_start) <= item.base_value)
-                && (item.base_value <= BigDecimal::from(threshold.price_end))
-        })
-        .cloned()
+/// The condition tier an inventory row's thresholds are picked from
+fn condition_tier(item: &Inventory) -> ItemCondition {
+    match ItemConditionEnum::try_from(item.condition) {
+        Ok(ItemConditionEnum::Used) => ItemCondition::Used,
+        _ => ItemCondition::New,
+    }
 }
 
-fn get_stock_threshold(config: &StockConfigRestock, item: &Inventory) -> Option<StockThreshold> {
-    config
+/// Find the threshold covering the item's base value, one set up for the item's
+/// condition wins over one that applies to any condition.
+fn find_threshold<'a, T>(
+    thresholds: &'a Vec<T>,
+    item: &Inventory,
+    bounds: fn(&T) -> (u32, u32, Option<ItemCondition>),
+) -> Option<&'a T> {
+    let condition = condition_tier(item);
+    let mut fallback = None;
+    for threshold in thresholds {
+        let (price_start, price_end, tier) = bounds(threshold);
+        if (BigDecimal::from(price_start) > item.base_value)
+            || (item.base_value > BigDecimal::from(price_end))
+        {
+            continue;
+        }
+        match tier {
+            Some(tier) if tier == condition => return Some(threshold),
+            None if fallback.is_none() => fallback = Some(threshold),
+            _ => {}
+        }
+    }
+    fallback
+}
+
+pub fn get_price_threshold(
+    config: &StockConfigPricing,
+    item: &Inventory,
+) -> Option<PriceThreshold> {
+    find_threshold(&config.thresholds, item, |t| {
+        (t.price_start, t.price_end, t.condition)
+    })
+    .cloned()
+}
+
+pub fn get_stock_threshold(
+    config: &StockConfigRestock,
+    item: &Inventory,
+) -> Option<StockThreshold> {
+    find_threshold(&config.thresholds, item, |t| {
+        (t.price_start, t.price_end, t.condition)
+    })
+    .cloned()
+}
+
+/// Daily trade statistics for the items going back `days` days, including today
+fn get_recent_stock_movements(
+    conn: &Ppc,
+    market: &str,
+    items: &Vec<Inventory>,
+    days: u32,
+) -> HashMap<String, Vec<TradeStatistic>> {
+    use crate::db::schema::trade_statistics as schema;
+    let since = Utc::today().naive_utc() - Duration::days(days.max(1) as i64 - 1);
+    let item_codes: Vec<&String> = items.iter().map(|i| &i.item_code).collect();
+
+    let results: Vec<TradeStatistic> = schema::table
+        .filter(schema::market.eq(market))
+        .filter(schema::item_code.eq(any(item_codes)))
+        .filter(schema::date.ge(since))
+        .load(conn)
+        .expect("Unable to load recent stock stats batch");
+
+    results
+        .into_iter()
+        .map(|stat| (stat.item_code.clone(), stat))
+        .into_group_map()
+}
+
+/// Add up the units traded from `days` ago up to today
+fn total_stock_movements(stats: &Vec<TradeStatistic>, days: u32) -> TradeStatsPair {
+    let since = Utc::today().naive_utc() - Duration::days(days.max(1) as i64 - 1);
+    stats.iter().filter(|stat| stat.date >= since).fold(
+        TradeStatsPair::default(),
+        |mut total, stat| {
+            if stat.buy {
+                total.bought += stat.quantity;
+            } else {
+                total.sold += stat.quantity;
+            }
+            total
+        },
+    )
+}
+
+/// Trade statistics the pricing strategies need for a batch of items
+struct BatchTradeStats {
+    today: HashMap<String, TradeStatsPair>,
+    recent: HashMap<String, Vec<TradeStatistic>>,
+}
+
+fn get_batch_trade_stats(
+    config: &StockConfig,
+    conn: &Ppc,
+    market: &str,
+    items: &Vec<Inventory>,
+) -> BatchTradeStats {
+    // Only go back through the history if a threshold is going to use it
+    let recent = match config
+        .pricing
         .thresholds
         .iter()
-        .find(|threshold| {
-            (BigDecimal::from(threshold.price_start) <= item.base_value)
-                && (item.base_value <= BigDecimal::from(threshold.price_end))
-        })
-        .cloned()
+        .filter(|t| t.strategy == Some(PriceStrategy::Demand))
+        .map(|t| t.demand.window_days)
+        .max()
+    {
+        Some(days) => get_recent_stock_movements(conn, market, items, days),
+        None => HashMap::new(),
+    };
+
+    BatchTradeStats {
+        today: get_stock_movements(conn, market, items),
+        recent,
+    }
 }
 
-fn update_prices(
+/// Run each item through its pricing strategy, nothing is read or written here.
+fn price_items(
     config: &StockConfig,
-    conn: &Ppc,
+    stats: &BatchTradeStats,
     mut items: Vec<Inventory>,
     rng: &Rng,
 ) -> Vec<Inventory> {
     let item_count = items.len();
 
-    let mut stock_stats = get_stock_movements(conn, &items);
     let mut processed_items = Vec::<Inventory>::with_capacity(items.len());
     for item in items.drain(..) {
-        if let Some(threshold_config) = get_price_threshold(&config.pricing, &item) {
-            let stock_stat = stock_stats.remove(&item.item_code);
-            processed_items.push(update_item_price(threshold_config, item, stock_stat, rng));
+        let item_override = get_item_override(config, &item);
+        let item = if item_override.map_or(false, has_fixed_prices) {
+            // Nothing left for a strategy to decide
+            item
+        } else if let Some(threshold_config) = get_price_threshold(&config.pricing, &item) {
+            let activity = MarketActivity {
+                today: stats
+                    .today
+                    .get(&item.item_code)
+                    .cloned()
+                    .unwrap_or_default(),
+                rolling: stats
+                    .recent
+                    .get(&item.item_code)
+                    .map(|stats| total_stock_movements(stats, threshold_config.demand.window_days))
+                    .unwrap_or_default(),
+                max_quantity: get_stock_threshold(&config.restock, &item)
+                    .map(|t| apply_stock_override(item_override, t).max_quantity),
+            };
+            strategy_for(threshold_config).update_price(item, &activity, rng)
         } else {
             // We can't update the price since we don't know what to do with it
-            // Just push it onto the processed stack
-            // warn!(
-            //     "No pricing threshold found for item {} @ version {}",
-            //     item.item_code, item.version
-            // );
-            processed_items.push(item);
-        }
+            // Just push it onto the processed stack, it's recorded in threshold_misses
+            item
+        };
+        processed_items.push(apply_price_override(item_override, item));
     }
 
     assert_eq!(processed_items.len(), item_count);
+    processed_items
+}
+
+fn update_prices(
+    config: &StockConfig,
+    conn: &Ppc,
+    market: &str,
+    items: Vec<Inventory>,
+    rng: &Rng,
+) -> Vec<Inventory> {
+    let stats = get_batch_trade_stats(config, conn, market, &items);
+    let processed_items = price_items(config, &stats, items, rng);
+
+    // Keep a record of what the prices were set to for the history charts.
+    if record_price_snapshots(&processed_items, conn).is_err() {
+        warn!("Price history for market {} will be missing this run", market);
+    }
 
     processed_items
 }
@@ -292,16 +465,17 @@ fn update_stock(
     let item_count = items.len();
 
     let mut processed_items = Vec::<Inventory>::with_capacity(items.len());
-    for item in items.drain(..) {
+    for mut item in items.drain(..) {
+        let item_override = get_item_override(config, &item);
         if let Some(threshold_config) = get_stock_threshold(&config.restock, &item) {
+            let threshold_config = apply_stock_override(item_override, threshold_config);
             processed_items.push(update_item_stock(threshold_config, item, rng));
         } else {
             // We can't update the stock since we don't know what to do with it
-            // Just push it onto the processed stack
-            // warn!(
-            //     "No stock threshold found for item {} @ version {}",
-            //     item.item_code, item.version
-            // );
+            // Just push it onto the processed stack, it's recorded in threshold_misses
+            if let Some(max) = item_override.and_then(|o| o.max_quantity) {
+                item.quantity = item.quantity.min(max as i32);
+            }
             processed_items.push(item);
         }
     }
@@ -311,13 +485,98 @@ fn update_stock(
     processed_items
 }
 
-fn process_market_batch(config: StockConfig, start_offset: i64, page_size: i64) {
+/// An override for the item's own code wins over one for its ThingDef
+pub fn get_item_override<'a>(
+    config: &'a StockConfig,
+    item: &Inventory,
+) -> Option<&'a ItemOverride> {
+    config
+        .overrides
+        .iter()
+        .find(|o| o.item_code.as_ref() == Some(&item.item_code))
+        .or_else(|| {
+            config
+                .overrides
+                .iter()
+                .find(|o| o.item_code.is_none() && o.thing_def.as_ref() == Some(&item.thing_def))
+        })
+}
+
+pub fn has_fixed_prices(item_override: &ItemOverride) -> bool {
+    item_override.buy_at.is_some() && item_override.sell_at.is_some()
+}
+
+/// Fixed prices win over whatever the strategy came up with
+pub fn apply_price_override(
+    item_override: Option<&ItemOverride>,
+    mut item: Inventory,
+) -> Inventory {
+    if let Some(item_override) = item_override {
+        if let Some(buy_at) = item_override.buy_at {
+            item.buy_at = BigDecimal::from(buy_at).round_2dp();
+        }
+        if let Some(sell_at) = item_override.sell_at {
+            item.sell_at = BigDecimal::from(sell_at).round_2dp();
+        }
+    }
+    item
+}
+
+/// Replace the parts of the restock threshold the override sets
+pub fn apply_stock_override(
+    item_override: Option<&ItemOverride>,
+    mut threshold: StockThreshold,
+) -> StockThreshold {
+    if let Some(item_override) = item_override {
+        if let Some(min_quantity) = item_override.min_quantity {
+            threshold.min_quantity = min_quantity;
+        }
+        if let Some(max_quantity) = item_override.max_quantity {
+            threshold.max_quantity = max_quantity;
+            threshold.max_restock = threshold.max_restock.min(max_quantity);
+        }
+        if item_override.never_restock == Some(true) {
+            threshold.chance_to_restock = 0f32;
+        }
+        // Restocking picks a random amount from min_quantity up to max_restock
+        threshold.max_restock = threshold.max_restock.max(threshold.min_quantity + 1);
+    }
+    threshold
+}
+
+fn find_threshold_misses(config: &StockConfig, items: &Vec<Inventory>) -> Vec<ThresholdMiss> {
+    let now = Utc::now().naive_utc();
+    items
+        .iter()
+        .filter_map(|item| {
+            let missing_price = get_price_threshold(&config.pricing, item).is_none()
+                && !get_item_override(config, item).map_or(false, has_fixed_prices);
+            let missing_stock = get_stock_threshold(&config.restock, item).is_none();
+            if !missing_price && !missing_stock {
+                return None;
+            }
+            Some(ThresholdMiss {
+                item_code: item.item_code.clone(),
+                thing_def: item.thing_def.clone(),
+                base_value: item.base_value.clone(),
+                condition: item.condition,
+                missing_price,
+                missing_stock,
+                found_date: now,
+                market: item.market.clone(),
+            })
+        })
+        .collect()
+}
+
+fn process_market_batch(config: StockConfig, market: String, start_offset: i64, page_size: i64) {
     // This is run in a thread, we need a new connection
     use crate::db::schema::inventory as schema;
     let conn = &get_pg_connection();
 
     // Get the inventory
     let mut rows: Vec<Inventory> = schema::table
+        .filter(schema::market.eq(&market))
         .order(schema::item_code)
         .offset(start_offset)
         .limit(page_size)
@@ -325,23 +584,33 @@ fn process_market_batch(config: StockConfig, start_offset: i64, page_size: i64)
         .expect("Failed to load inventory");
 
     if rows.len() == 0 {
-        warn!("Nothing to process at offset {}", start_offset);
+        warn!(
+            "Nothing to process in {} at offset {}",
+            market, start_offset
+        );
         return;
     }
 
     // Strip blacklisted items from the list of item codes
+    let blacklist = get_blacklist(conn).unwrap_or_else(|e| {
+        warn!("Unable to load the item blacklist, {}", e);
+        Blacklist::default()
+    });
     rows = rows
         .drain(..)
-        .filter(|item| item.item_code != &*SILVER_ITEM.item_code)
+        .filter(|item| !is_currency_item(&item.item_code) && !blacklist.contains_inventory(item))
         .collect();
 
+    // Anything the configuration doesn't cover is left alone, keep a list so it can be fixed.
+    let _ = record_threshold_misses(&find_threshold_misses(&config, &rows), conn);
+
     let count = rows.len();
     let seed = thread_rng().gen::<u64>();
     debug!("Batch RNG seed is {}", seed);
     let rng = Rng::with_seed(seed);
 
     debug!("Updating prices");
-    rows = update_prices(&config, conn, rows, &rng);
+    rows = update_prices(&config, conn, &market, rows, &rng);
     debug!("Updating stock");
     rows = update_stock(&config, conn, rows, &rng);
 
@@ -350,7 +619,7 @@ fn process_market_batch(config: StockConfig, start_offset: i64, page_size: i64)
 
     diesel::insert_into(schema::table)
         .values(&rows)
-        .on_conflict(schema::item_code)
+        .on_conflict((schema::market, schema::item_code))
         .do_update()
         .set((
             schema::buy_at.eq(excluded(schema::buy_at)),
@@ -361,64 +630,104 @@ fn process_market_batch(config: StockConfig, start_offset: i64, page_size: i64)
         .expect("Failed to update inventory table!");
 }
 
-fn process_votes(conn: &mut Ppc, config: &ApiConfig) {
+fn process_votes(conn: &mut Ppc, config: &ApiConfig, market: &str) {
     use crate::db::schema::inventory as inventory_schema;
     use crate::db::schema::new_inventory as new_inventory_schema;
     use crate::db::schema::new_inventory_vote_tracker as vote_tracker;
 
     use crate::db::views::temporary_vote_data as temp_votes;
 
+    // Blacklisted items are never promoted, their votes are cleared along with the rest.
+    let blacklist = match get_blacklist(conn) {
+        Ok(b) => b,
+        Err(e) => {
+            error!(
+                "Unable to load the item blacklist, not processing votes, {}",
+                e
+            );
+            return;
+        }
+    };
+
+    // Used to work out which mod new items came from, they can still be added without it.
+    let mod_popularity = match get_mod_statistics(conn) {
+        Ok(stats) => Some((stats.popularity_map(), stats.colonies)),
+        Err(e) => {
+            warn!(
+                "Unable to load mod statistics, new items won't be tagged, {}",
+                e
+            );
+            None
+        }
+    };
+
     // Get the votes.
     conn.build_transaction()
         .repeatable_read()
         .run::<_, diesel::result::Error, _>(|| {
-            // Delete any votes that relate to silver.
+            let currency_codes: Vec<&String> = CurrencyEnum::iter()
+                .map(|c| &currency_item(c).item_code)
+                .collect();
+
+            // Delete any votes that relate to currency items.
             // Stop abusers trying to overwrite the values.
             diesel::delete(vote_tracker::table)
+                .filter(vote_tracker::market.eq(market))
                 .filter(
                     vote_tracker::version.eq_any(
                         new_inventory_schema::table
-                            .filter(new_inventory_schema::item_code.eq(&SILVER_ITEM.item_code))
+                            .filter(new_inventory_schema::market.eq(market))
+                            .filter(new_inventory_schema::item_code.eq_any(&currency_codes))
                             .select(new_inventory_schema::version),
                     ),
                 )
                 .execute(conn)
                 .expect("Failed to delete blacklisted votes");
 
-            // Then delete any new inventory for silver
+            // Then delete any new inventory for currency items
             diesel::delete(new_inventory_schema::table)
-                .filter(new_inventory_schema::item_code.eq(&SILVER_ITEM.item_code))
+                .filter(new_inventory_schema::market.eq(market))
+                .filter(new_inventory_schema::item_code.eq_any(&currency_codes))
                 .execute(conn)
                 .expect("Failed to delete blacklisted votes");
 
-            // Refresh the existing silver item and ensure it exists.
-            diesel::insert_into(inventory_schema::table)
-                .values(&*SILVER_ITEM)
-                .on_conflict(inventory_schema::item_code)
-                .do_update()
-                .set(&*SILVER_ITEM)
-                .execute(conn)
-                .expect("Failed to insert silver inventory item");
+            // Refresh the existing currency items and ensure they exist.
+            for currency in CurrencyEnum::iter() {
+                let item = currency_item_for_market(currency, market);
+                diesel::insert_into(inventory_schema::table)
+                    .values(&item)
+                    .on_conflict((inventory_schema::market, inventory_schema::item_code))
+                    .do_update()
+                    .set(&item)
+                    .execute(conn)
+                    .expect("Failed to insert currency inventory item");
+            }
 
-            // Create temp table to hold votes
-            // We sort by votes ascending so that items with higher votes overwrite lower ones.
-            let sql = format!(
+            // Create temp table to hold votes, promotion goes by the weighted total
+            // so lots of colonies or binds on one account don't count for more.
+            conn.batch_execute(
                 r#"DROP TABLE IF EXISTS "temporary_vote_data";
             DROP SEQUENCE IF EXISTS "temporary_vote_data_position_seq";
             CREATE TEMP TABLE "temporary_vote_data"( position bigserial primary key, item_code
             varchar(32) not null, thing_def varchar(200) not null, quality integer, minified boolean
             default false not null, base_value numeric(10, 2) default 0.0 not null,
             stuff varchar(200), weight numeric(10, 2) default 0.0 not null, version varchar(32) not
-            null, votes integer) ON COMMIT DROP; INSERT INTO "temporary_vote_data"
+            null, votes integer, market varchar(16) not null) ON COMMIT DROP;"#,
+            )
+            .expect("Unable to create vote temp data table!");
+
+            // We sort by votes ascending so that items with higher votes overwrite lower ones.
+            sql_query(format!(
+                r#"INSERT INTO "temporary_vote_data"
             SELECT nextval('temporary_vote_data_position_seq'::regclass) as position, item_code,
-            thing_def, quality, minified, base_value, stuff, weight, version, votes FROM
-            summary_inventory_votes siv WHERE siv.votes > {}
-            ORDER BY siv.votes ASC, siv.base_value ASC;"#,
+            thing_def, quality, minified, base_value, stuff, weight, version, votes, market FROM
+            summary_inventory_votes siv WHERE siv.market = $1 AND siv.weighted_votes > {}
+            ORDER BY siv.weighted_votes ASC, siv.base_value ASC;"#,
                 config.config_data.inventory.vote_promotion_threshold as i64
-            );
-
-            conn.batch_execute(&*sql)
-                .expect("Unable to create vote temp data table!");
+            ))
+            .bind::<Text, _>(market)
+            .execute(conn)
+            .expect("Unable to fill vote temp data table!");
 
             let mut last_position: i64 = 0;
             loop {
@@ -444,6 +753,12 @@ fn process_votes(conn: &mut Ppc, config: &ApiConfig) {
                 debug!("Updated position to {}", last_position);
                 debug!("Processing {} votes", voted_items.len());
 
+                // The moderation queue shows how many votes an item had
+                let vote_counts: HashMap<String, i32> = voted_items
+                    .iter()
+                    .map(|v| (v.version.clone(), v.votes))
+                    .collect();
+
                 let mut voted_items: Vec<InventoryNoQuantity> = voted_items
                     .drain(..)
                     .map_into::<InventoryNoQuantity>()
@@ -456,7 +771,7 @@ fn process_votes(conn: &mut Ppc, config: &ApiConfig) {
                 // We iterate backwards to get the version of an item with the most votes,
                 // if there are two versions with the same number of votes pick the one
                 // that is the most expensive and discard all other versions of that item.
-                let items_to_insert = voted_items
+                let mut items_to_insert = voted_items
                     .drain(..)
                     .rev()
                     .map(|i| {
@@ -464,6 +779,7 @@ fn process_votes(conn: &mut Ppc, config: &ApiConfig) {
                         i
                     })
                     .filter(|item| known_keys.insert(item.item_code.clone()))
+                    .filter(|item| !blacklist.contains(item))
                     .collect::<Vec<InventoryNoQuantity>>();
 
                 info!(
@@ -474,29 +790,102 @@ fn process_votes(conn: &mut Ppc, config: &ApiConfig) {
 
                 drop(known_keys);
 
-                // Upsert the new Inventory and update existing rows
-                if let Err(e) = diesel::insert_into(inventory_schema::table)
-                    .values(&items_to_insert)
-                    .on_conflict(inventory_schema::item_code)
-                    .do_update()
-                    .set((
-                        inventory_schema::version.eq(excluded(inventory_schema::version)),
-                        inventory_schema::minified.eq(excluded(inventory_schema::minified)),
-                        inventory_schema::base_value.eq(excluded(inventory_schema::base_value)),
-                        inventory_schema::weight.eq(excluded(inventory_schema::weight)),
-                    ))
-                    .execute(conn)
-                {
-                    error!("Database error adding new inventory: {:?}", e);
-                    return Err(diesel::result::Error::RollbackTransaction);
+                // Tag items with the mod they came from, this only sticks to new rows,
+                // the upsert below leaves the tag on existing ones alone.
+                if let Some((popularity, colonies)) = mod_popularity.as_ref() {
+                    let versions: Vec<&String> =
+                        items_to_insert.iter().map(|item| &item.version).collect();
+                    match get_voter_mods(market, &versions, conn) {
+                        Ok(voter_mods) => {
+                            for item in items_to_insert.iter_mut() {
+                                item.mod_name = voter_mods
+                                    .get(&item.version)
+                                    .and_then(|mods| infer_item_mod(mods, popularity, *colonies));
+                            }
+                        }
+                        Err(e) => {
+                            error!("Database error loading mod lists for voters: {:?}", e);
+                            return Err(diesel::result::Error::RollbackTransaction);
+                        }
+                    }
+                }
+
+                // When moderating, items we don't stock yet wait for an admin to approve them,
+                // so does a new base value for one we do. Anything else is updated as usual.
+                let items_to_insert = if config.config_data.inventory.moderation {
+                    let stocked: HashMap<String, BigDecimal> = match inventory_schema::table
+                        .select((inventory_schema::item_code, inventory_schema::base_value))
+                        .filter(inventory_schema::market.eq(market))
+                        .filter(
+                            inventory_schema::item_code.eq(any(items_to_insert
+                                .iter()
+                                .map(|item| &item.item_code)
+                                .collect::<Vec<&String>>())),
+                        )
+                        .load::<(String, BigDecimal)>(conn)
+                    {
+                        Ok(rows) => rows.into_iter().collect(),
+                        Err(e) => {
+                            error!("Database error checking for new inventory: {:?}", e);
+                            return Err(diesel::result::Error::RollbackTransaction);
+                        }
+                    };
+
+                    let (items_to_insert, items_to_queue): (Vec<_>, Vec<_>) =
+                        items_to_insert.into_iter().partition(|item| {
+                            stocked
+                                .get(&item.item_code)
+                                .map_or(false, |base_value| *base_value == item.base_value)
+                        });
+                    let items_to_queue: Vec<PendingInventory> = items_to_queue
+                        .into_iter()
+                        .map(|item| {
+                            let votes = vote_counts.get(&item.version).copied().unwrap_or(0);
+                            PendingInventory::from_vote(item, votes)
+                        })
+                        .collect();
+
+                    info!(
+                        "Queueing {} new or re-valued items for moderation",
+                        items_to_queue.len()
+                    );
+                    for chunk in items_to_queue.chunks(PendingInventory::batch_size()) {
+                        if let Err(e) = queue_pending_inventory(chunk, conn) {
+                            error!("Database error queueing new inventory: {:?}", e);
+                            return Err(diesel::result::Error::RollbackTransaction);
+                        }
+                    }
+                    items_to_insert
+                } else {
+                    items_to_insert
                 };
 
+                // Upsert the new Inventory and update existing rows
+                if !items_to_insert.is_empty() {
+                    if let Err(e) = diesel::insert_into(inventory_schema::table)
+                        .values(&items_to_insert)
+                        .on_conflict((inventory_schema::market, inventory_schema::item_code))
+                        .do_update()
+                        .set((
+                            inventory_schema::version.eq(excluded(inventory_schema::version)),
+                            inventory_schema::minified.eq(excluded(inventory_schema::minified)),
+                            inventory_schema::base_value.eq(excluded(inventory_schema::base_value)),
+                            inventory_schema::weight.eq(excluded(inventory_schema::weight)),
+                        ))
+                        .execute(conn)
+                    {
+                        error!("Database error adding new inventory: {:?}", e);
+                        return Err(diesel::result::Error::RollbackTransaction);
+                    };
+                }
+
                 // Delete votes that were processed, we can't do this in the same block above
                 // Because deleting results will affect our page position.
                 // When the votes are deleted, delete the new inventory row too.
                 debug!("{} items to clear", votes_to_delete.len());
 
                 if let Err(e) = diesel::delete(vote_tracker::table)
+                    .filter(vote_tracker::market.eq(market))
                     .filter(vote_tracker::version.eq(any(&votes_to_delete)))
                     .execute(conn)
                 {
@@ -504,6 +893,7 @@ fn process_votes(conn: &mut Ppc, config: &ApiConfig) {
                     return Err(diesel::result::Error::RollbackTransaction);
                 };
                 if let Err(e) = diesel::delete(new_inventory_schema::table)
+                    .filter(new_inventory_schema::market.eq(market))
                     .filter(new_inventory_schema::version.eq(any(votes_to_delete)))
                     .execute(conn)
                 {
@@ -519,6 +909,7 @@ fn process_votes(conn: &mut Ppc, config: &ApiConfig) {
             loop {
                 // Delete all items that haven't been voted in after a period.
                 if let Ok(batch) = new_inventory_schema::table
+                    .filter(new_inventory_schema::market.eq(market))
                     .filter(new_inventory_schema::date_added.lt(vote_age_limit))
                     .select(new_inventory_schema::version)
                     .limit(65_000)
@@ -530,6 +921,7 @@ fn process_votes(conn: &mut Ppc, config: &ApiConfig) {
 
                     // Delete the votes first
                     if let Err(e) = diesel::delete(vote_tracker::table)
+                        .filter(vote_tracker::market.eq(market))
                         .filter(vote_tracker::version.eq_any(&batch))
                         .execute(conn)
                     {
@@ -539,6 +931,7 @@ fn process_votes(conn: &mut Ppc, config: &ApiConfig) {
 
                     // Then the inventory items
                     if let Err(e) = diesel::delete(new_inventory_schema::table)
+                        .filter(new_inventory_schema::market.eq(market))
                         .filter(new_inventory_schema::version.eq_any(batch))
                         .execute(conn)
                     {
@@ -552,12 +945,15 @@ fn process_votes(conn: &mut Ppc, config: &ApiConfig) {
         });
 }
 
-fn process_market_data(conn: &mut Ppc, _: &ApiConfig) {
-    // Fetch inventory from DB, chunk it up and process it in parallel.
+fn process_market_data(conn: &mut Ppc, _: &ApiConfig, market: &str) {
+    // Fetch the market's inventory from DB, chunk it up and process it in parallel.
     use crate::db::schema::inventory as schema;
 
     let _page = 0;
     let stock_config = get_stock_configuration(conn);
+    if let Err(e) = clear_threshold_misses(market, conn) {
+        warn!("Unable to clear threshold misses from the last run, {}", e);
+    }
     let thread_count = (stock_config.threading.parallelism as usize)
         .min(num_cpus::get()) // No more threads than logical cores
         .max(1); // Make sure there's at least 1 thread.
@@ -574,6 +970,7 @@ fn process_market_data(conn: &mut Ppc, _: &ApiConfig) {
         .run::<_, diesel::result::Error, _>(|| {
             // Count how many items are in inventory table
             num_items = schema::table
+                .filter(schema::market.eq(market))
                 .count()
                 .get_result(conn)
                 .expect("Unable to count inventory");
@@ -584,13 +981,14 @@ fn process_market_data(conn: &mut Ppc, _: &ApiConfig) {
                 // If the thread pool has no capacity, it will wait
                 // here until it can queue it.
                 let thread_config = stock_config.clone();
+                let thread_market = market.to_string();
                 pool.execute(move || {
                     // This closure is run on a separate thread
                     debug!(
                         "Processing market data batch starting at {}",
                         current_offset
                     );
-                    process_market_batch(thread_config, current_offset, page_size);
+                    process_market_batch(thread_config, thread_market, current_offset, page_size);
                     debug!("Completed market data batch");
                 });
                 current_offset += page_size;
@@ -611,14 +1009,51 @@ fn process_market_data(conn: &mut Ppc, _: &ApiConfig) {
     if pool.panic_count() > 0 {
         error!("{} of {} batches failed", pool.panic_count(), num_batches)
     }
+
+    // Now the market has restocked, fill any backorders we can.
+    if let Ok(count) = fill_pending_backorders(market, conn) {
+        info!("Filled {} backorders in {}", count, market);
+    }
 }
 
-fn process_statistics_rollup(conn: &Ppc) {
-    let _date = Utc::today().naive_utc();
+/// Roll the daily trade statistics for every finished month up into
+/// `trade_statistics_monthly`, then prune daily rows older than the retention window.
+fn process_statistics_rollup(conn: &Ppc, api_config: &ApiConfig) -> Result<StatisticsRollup, ()> {
+    use crate::db::schema::trade_statistics as schema;
+
+    let today = Utc::today().naive_utc();
+    let month_start = NaiveDate::from_ymd(today.year(), today.month(), 1);
+
+    // Never prune anything from the current month, it hasn't been rolled up yet.
+    let retention = api_config.config_data.maintenance.statistics_retention_days as i64;
+    let prune_before = (today - Duration::days(retention)).min(month_start);
+
     conn.build_transaction()
+        .read_committed()
         .run::<_, diesel::result::Error, _>(|| {
-            // pass
-
-            Ok(())
-        });
+            // Finished months never change, so anything already rolled up is left
+            // alone, otherwise a partially pruned month would lose its totals.
+            let monthly_rows_inserted = sql_query(
+                r#"INSERT INTO trade_statistics_monthly (market, item_code, buy, quantity, date)
+                SELECT market, item_code, buy, sum(quantity)::bigint,
+                date_trunc('month', date)::date FROM trade_statistics WHERE date < $1
+                GROUP BY market, item_code, buy, date_trunc('month', date)
+                ON CONFLICT DO NOTHING"#,
+            )
+            .bind::<Date, _>(month_start)
+            .execute(conn)?;
+
+            let daily_rows_pruned =
+                diesel::delete(schema::table.filter(schema::date.lt(prune_before)))
+                    .execute(conn)?;
+
+            Ok(StatisticsRollup {
+                monthly_rows_inserted,
+                daily_rows_pruned,
+                prune_before: Some(prune_before),
+            })
+        })
+        .map_err(|e| {
+            error!("Failed to roll up trade statistics: {:?}", e);
+        })
 }
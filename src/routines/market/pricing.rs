@@ -0,0 +1,164 @@
+use bigdecimal::BigDecimal;
+use fastrand::Rng;
+
+use crate::db::models::inventory::Inventory;
+use crate::jtd::stock_config::{PriceStrategy, PriceThreshold};
+use crate::routines::market::TradeStatsPair;
+use crate::traits::numerical::{CanRound, Percentage};
+
+/// What a pricing strategy gets to know about how an item has been trading
+#[derive(Default)]
+pub struct MarketActivity {
+    /// Units traded today
+    pub today: TradeStatsPair,
+    /// Units traded over the threshold's demand window, including today
+    pub rolling: TradeStatsPair,
+    /// The most stock we want to hold, if there's a restock threshold for the item
+    pub max_quantity: Option<u32>,
+}
+
+pub trait PricingStrategy {
+    fn threshold(&self) -> &PriceThreshold;
+
+    /// Move the buy and sell prices, they don't need to be kept in range here
+    fn adjust(&self, item: Inventory, activity: &MarketActivity, rng: &Rng) -> Inventory;
+
+    fn update_price(&self, item: Inventory, activity: &MarketActivity, rng: &Rng) -> Inventory {
+        let item = self.adjust(item, activity, rng);
+        clamp_prices(self.threshold(), item)
+    }
+}
+
+/// Pick the strategy the threshold asks for, step pricing if it doesn't say
+pub fn strategy_for(config: PriceThreshold) -> Box<dyn PricingStrategy> {
+    match config.strategy {
+        Some(PriceStrategy::Demand) => Box::new(DemandPricing { config }),
+        Some(PriceStrategy::Step) | None => Box::new(StepPricing { config }),
+    }
+}
+
+/// Clamp the values to make sure they don't go out of range,
+/// It also fixes the values if the base value changes due to a new version.
+fn clamp_prices(config: &PriceThreshold, mut item: Inventory) -> Inventory {
+    item.sell_at = item
+        .sell_at
+        .clamp(
+            &item.base_value
+                - item
+                    .base_value
+                    .percent_fraction(config.selling.max_price_decrease_pct),
+            &item.base_value
+                + item
+                    .base_value
+                    .percent_fraction(config.selling.max_price_increase_pct),
+        )
+        .round_2dp();
+
+    item.buy_at = item
+        .buy_at
+        .clamp(
+            &item.base_value
+                - item
+                    .base_value
+                    .percent_fraction(config.buying.max_price_decrease_pct),
+            &item.base_value
+                + item
+                    .base_value
+                    .percent_fraction(config.buying.max_price_increase_pct),
+        )
+        .round_2dp();
+    item
+}
+
+/// Nudges prices a number of steps away from the base value depending on
+/// whether anything was traded today.
+pub struct StepPricing {
+    pub config: PriceThreshold,
+}
+
+impl PricingStrategy for StepPricing {
+    fn threshold(&self) -> &PriceThreshold {
+        &self.config
+    }
+
+    fn adjust(&self, mut item: Inventory, activity: &MarketActivity, _rng: &Rng) -> Inventory {
+        let config = &self.config;
+        let stock_stats = &activity.today;
+
+        // Sale price adjust
+        if stock_stats.bought > 0 {
+            // There was trade, we increase the price we sell at by a number of steps up to max
+            let step = item.base_value.percent_fraction(config.selling.step_size);
+            let units = stock_stats.bought / (config.selling.unit_to_stock_ratio as i64);
+            item.sell_at = &item.base_value
+                + (step * BigDecimal::from(units.clamp(0, config.selling.max_steps as i64)));
+        } else {
+            // We didn't sell any today, decrease the price a step down to min.
+            let step = item.base_value.percent_fraction(config.selling.step_size);
+            item.sell_at = &item.base_value - step;
+        }
+        // Buy price adjust
+        if stock_stats.sold > 0 {
+            // There was trade, we decrease the price we buy at
+            let step = item.base_value.percent_fraction(config.buying.step_size);
+            let units = stock_stats.sold / (config.buying.unit_to_stock_ratio as i64);
+            item.buy_at = &item.base_value
+                - (step * BigDecimal::from(units.clamp(0, config.buying.max_steps as i64)));
+        } else {
+            // We didn't buy any today, increase the price a step.
+            let step = item.base_value.percent_fraction(config.selling.step_size);
+            item.buy_at = &item.base_value + step;
+        }
+        item
+    }
+}
+
+/// Sets prices from how the item has been trading over the demand window and how
+/// full our stock is, scarce items that colonies keep buying get more expensive
+/// and items we're flooded with get cheaper, both to buy and to sell.
+/// We sell a step above the base value and buy a step below it, so there's always a margin.
+pub struct DemandPricing {
+    pub config: PriceThreshold,
+}
+
+impl DemandPricing {
+    /// Between -1 and 1, how much the market wants the item, 0 is balanced
+    pub fn pressure(item: &Inventory, activity: &MarketActivity) -> f32 {
+        let rolling = &activity.rolling;
+        let volume = rolling.bought + rolling.sold;
+
+        // 1 when colonies only buy it from us, -1 when they only sell it to us
+        let flow = if volume > 0 {
+            (rolling.bought - rolling.sold) as f32 / volume as f32
+        } else {
+            0f32
+        };
+
+        // 1 when we have none, -1 when we're at the max we want to hold
+        let scarcity = match activity.max_quantity {
+            Some(max) if max > 0 => {
+                1f32 - 2f32 * (item.quantity as f32 / max as f32).clamp(0f32, 1f32)
+            }
+            _ => 0f32,
+        };
+
+        (flow + scarcity) / 2f32
+    }
+}
+
+impl PricingStrategy for DemandPricing {
+    fn threshold(&self) -> &PriceThreshold {
+        &self.config
+    }
+
+    fn adjust(&self, mut item: Inventory, activity: &MarketActivity, _rng: &Rng) -> Inventory {
+        let config = &self.config;
+        let change = DemandPricing::pressure(&item, activity) * config.demand.elasticity;
+        let change = BigDecimal::from(1f32 + change);
+        let sell_at = &item.base_value + item.base_value.percent_fraction(config.selling.step_size);
+        let buy_at = &item.base_value - item.base_value.percent_fraction(config.buying.step_size);
+        item.sell_at = sell_at * &change;
+        item.buy_at = buy_at * change;
+        item
+    }
+}
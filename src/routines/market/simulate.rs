@@ -0,0 +1,132 @@
+use bigdecimal::BigDecimal;
+use diesel::prelude::*;
+use fastrand::Rng;
+
+use crate::db::models::inventory::Inventory;
+use crate::db::Ppc;
+use crate::jtd::stock_config::StockConfig;
+use crate::routines::market::{
+    get_batch_trade_stats, get_item_override, get_price_threshold, get_stock_threshold,
+    has_fixed_prices, price_items, update_stock,
+};
+use crate::structs::currency::is_currency_item;
+
+/// How one item would change over the simulated days
+#[derive(Debug)]
+pub struct SimulatedItem {
+    pub item_code: String,
+    pub thing_def: String,
+    pub quantity_before: i32,
+    pub quantity_after: i32,
+    pub buy_at_before: BigDecimal,
+    pub buy_at_after: BigDecimal,
+    pub sell_at_before: BigDecimal,
+    pub sell_at_after: BigDecimal,
+}
+
+impl SimulatedItem {
+    pub fn changed(&self) -> bool {
+        self.quantity_before != self.quantity_after
+            || self.buy_at_before != self.buy_at_after
+            || self.sell_at_before != self.sell_at_after
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SimulationReport {
+    pub days: u32,
+    pub items: Vec<SimulatedItem>,
+    /// Items no pricing threshold covers, their prices never move
+    pub no_price_threshold: usize,
+    /// Items no restock threshold covers, their stock never moves
+    pub no_stock_threshold: usize,
+    /// Everything in stock at the price we sell it at
+    pub stock_value_before: BigDecimal,
+    pub stock_value_after: BigDecimal,
+    /// Everything in stock at the price we'd pay for it
+    pub buy_value_before: BigDecimal,
+    pub buy_value_after: BigDecimal,
+}
+
+fn stock_value(item: &Inventory, price: &BigDecimal) -> BigDecimal {
+    price * BigDecimal::from(item.quantity)
+}
+
+/// Run the maintenance pricing and restocking against a market's current inventory for a
+/// number of days without saving anything, so a stock configuration can be tried out
+/// before it's published. Trade is assumed to carry on each day as it has recently.
+pub fn simulate_market(
+    config: &StockConfig,
+    market: &str,
+    days: u32,
+    seed: u64,
+    conn: &Ppc,
+) -> QueryResult<SimulationReport> {
+    use crate::db::schema::inventory as schema;
+
+    let rng = Rng::with_seed(seed);
+    let page_size = (Inventory::batch_size() as i64)
+        .min(config.threading.batch_size as i64)
+        .max(1);
+    let mut report = SimulationReport {
+        days,
+        ..Default::default()
+    };
+
+    let mut offset = 0;
+    loop {
+        let rows: Vec<Inventory> = schema::table
+            .filter(schema::market.eq(market))
+            .order(schema::item_code)
+            .offset(offset)
+            .limit(page_size)
+            .get_results(conn)?;
+        if rows.is_empty() {
+            break;
+        }
+        offset += page_size;
+
+        let before: Vec<Inventory> = rows
+            .into_iter()
+            .filter(|item| !is_currency_item(&item.item_code))
+            .collect();
+
+        for item in before.iter() {
+            if get_price_threshold(&config.pricing, item).is_none()
+                && !get_item_override(config, item).map_or(false, has_fixed_prices)
+            {
+                report.no_price_threshold += 1;
+            }
+            if get_stock_threshold(&config.restock, item).is_none() {
+                report.no_stock_threshold += 1;
+            }
+        }
+
+        let stats = get_batch_trade_stats(config, conn, market, &before);
+        let mut after = before.clone();
+        for _ in 0..days {
+            after = price_items(config, &stats, after, &rng);
+            after = update_stock(config, conn, after, &rng);
+        }
+
+        // Neither step adds, drops or reorders items
+        for (old, new) in before.into_iter().zip(after.into_iter()) {
+            report.stock_value_before += stock_value(&old, &old.sell_at);
+            report.stock_value_after += stock_value(&new, &new.sell_at);
+            report.buy_value_before += stock_value(&old, &old.buy_at);
+            report.buy_value_after += stock_value(&new, &new.buy_at);
+            report.items.push(SimulatedItem {
+                item_code: old.item_code,
+                thing_def: old.thing_def,
+                quantity_before: old.quantity,
+                quantity_after: new.quantity,
+                buy_at_before: old.buy_at,
+                buy_at_after: new.buy_at,
+                sell_at_before: old.sell_at,
+                sell_at_after: new.sell_at,
+            });
+        }
+    }
+
+    Ok(report)
+}
@@ -1,2 +1,3 @@
 pub mod market;
+pub mod orders;
 pub mod system;
@@ -0,0 +1,99 @@
+use actix_web::rt::time;
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::db::{get_pg_connection, Ppc};
+use crate::jtd::api_config::structure::ApiConfigDataOrders;
+use crate::packets::order::OrderStatusEnum;
+use crate::structs::api_config::LockedApiConfig;
+use crate::structs::bank_transfer::expire_pending_transfers;
+use crate::structs::general::ONE_DAY_TICKS;
+use crate::structs::order::{close_stale_order, invalidate_order_inventory};
+
+/// How often to look for orders that need closing
+const STALE_ORDER_INTERVAL_SECS: u64 = 600;
+
+/// Close orders that the client is never going to move on, either because
+/// the colony has been abandoned or delivery is long overdue.
+/// Otherwise the stock they took is held forever.
+/// Bank transfers the recipient never accepted are sent back here too.
+pub async fn poll_stale_orders(config_lock: LockedApiConfig) {
+    let mut interval = time::interval(core::time::Duration::from_secs(STALE_ORDER_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+
+        let (config, transfer_expiry_days) = match &*config_lock.read() {
+            Some(c) if !c.config_data.api.force_offline => (
+                c.config_data.orders.clone(),
+                c.config_data.bank.transfer_expiry_days,
+            ),
+            // Leave it alone during maintenance or if we're offline
+            _ => continue,
+        };
+
+        let conn = &get_pg_connection();
+        match close_stale_orders(&config, conn) {
+            Ok(0) => debug!("No stale orders to close"),
+            Ok(count) => info!("Closed {} stale orders", count),
+            Err(e) => warn!("Failed to look for stale orders, {}", e),
+        }
+        match expire_pending_transfers(transfer_expiry_days, conn) {
+            Ok(0) => debug!("No bank transfers to expire"),
+            Ok(count) => info!("Expired {} bank transfers", count),
+            Err(e) => warn!("Failed to look for expired bank transfers, {}", e),
+        }
+    }
+}
+
+/// Returns how many orders were closed
+pub fn close_stale_orders(config: &ApiConfigDataOrders, conn: &Ppc) -> QueryResult<usize> {
+    use crate::db::schema::colonies as colony_schema;
+    use crate::db::schema::orders as schema;
+
+    let stale_before = Utc::now().naive_utc() - Duration::days(config.stale_colony_days as i64);
+    let overdue_ticks = config.overdue_days as i32 * ONE_DAY_TICKS;
+
+    let order_ids: Vec<Uuid> = schema::table
+        .inner_join(colony_schema::table.on(colony_schema::colony_id.eq(schema::colony_id)))
+        .select(schema::order_id)
+        .filter(
+            schema::status
+                .eq(i32::from(OrderStatusEnum::Placed))
+                .or(schema::status.eq(i32::from(OrderStatusEnum::OutForDelivery))),
+        )
+        .filter(
+            colony_schema::update_date
+                .lt(stale_before)
+                .or(colony_schema::tick.gt(schema::end_tick + overdue_ticks)),
+        )
+        .load(conn)?;
+
+    let mut count = 0;
+    for order_id in order_ids {
+        // Each order gets its own transaction so one bad order doesn't hold up the rest.
+        match conn
+            .build_transaction()
+            .read_committed()
+            .run::<_, diesel::result::Error, _>(|| {
+                close_stale_order(
+                    order_id,
+                    config.stale_order_policy,
+                    config.insurance_pct,
+                    conn,
+                )
+                .map_err(|_| diesel::result::Error::RollbackTransaction)
+            }) {
+            Ok((order, refund)) => {
+                invalidate_order_inventory(&order);
+                debug!(
+                    "Closed stale order {} for colony {}, refunded {}",
+                    order.order_id, order.colony_id, refund
+                );
+                count += 1;
+            }
+            Err(_) => warn!("Unable to close stale order {}", order_id),
+        }
+    }
+    Ok(count)
+}
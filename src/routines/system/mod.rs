@@ -2,6 +2,7 @@ use actix_web::rt::time;
 use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
 use diesel::prelude::*;
 
+use crate::cache::config::get_latest_api_config;
 use crate::db::get_pg_connection;
 use crate::db::models::api_config::ApiConfig;
 use crate::db::models::maintenance::Maintenance;
@@ -9,7 +10,6 @@ use crate::routines::market::perform_maintenance;
 use crate::structs::api_config::LockedApiConfig;
 
 pub async fn poll_api_online_status(config_lock: LockedApiConfig) {
-    use crate::db::schema::api_config as schema;
     use crate::db::schema::maintenance as m_schema;
 
     let mut interval = time::interval(core::time::Duration::from_secs(60));
@@ -33,10 +33,7 @@ pub async fn poll_api_online_status(config_lock: LockedApiConfig) {
         {
             debug!("Checking API status");
             let conn = &get_pg_connection();
-            db_api_config = match schema::table
-                .order_by(schema::version.desc())
-                .first::<ApiConfig>(conn)
-            {
+            db_api_config = match get_latest_api_config(conn) {
                 Ok(row) => Some(row),
                 _ => None,
             };
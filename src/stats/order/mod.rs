@@ -17,6 +17,7 @@ pub fn update_trade_stats_for_order(order_id: Uuid) {
     conn.build_transaction()
         .run::<_, diesel::result::Error, _>(|| {
             let mut order = Order::load_pk(&order_id).unwrap();
+            let market = order.market.clone();
             if diesel::insert_into(ts::table)
                 .values(
                     order
@@ -28,16 +29,18 @@ pub fn update_trade_stats_for_order(order_id: Uuid) {
                             buy: true,
                             quantity: oi.quantity as i64,
                             date,
+                            market: market.clone(),
                         })
                         .chain(order.manifest.wts.drain(..).map(|oi| TradeStatistic {
                             item_code: oi.item_code,
                             buy: false,
                             quantity: oi.quantity as i64,
                             date,
+                            market: market.clone(),
                         }))
                         .collect::<Vec<TradeStatistic>>(),
                 )
-                .on_conflict((ts::item_code, ts::buy, ts::date))
+                .on_conflict((ts::market, ts::item_code, ts::buy, ts::date))
                 .do_update()
                 .set(ts::quantity.eq(ts::quantity + excluded(ts::quantity)))
                 .execute(conn)
@@ -1,14 +1,37 @@
-use chrono::Utc;
+use actix_http::error::PayloadError;
+use actix_http::{BoxedPayloadStream, Error, HttpMessage, Payload};
+use actix_web::guard::{Guard, GuardContext};
+use actix_web::{FromRequest, HttpRequest};
+use chrono::{Duration, NaiveDateTime, Utc};
 use diesel::prelude::*;
+use diesel::sql_types::{Nullable, Text};
+use futures::future::{err, ok, Ready};
+use std::io;
+use std::io::ErrorKind;
 use steamid_ng::SteamID;
 
-use crate::db::models::account::{Account, NewAccount};
+use crate::crypto::{generate_secure_token, hash_password, totp_step};
+use crate::db::models::account::{Account, AccountSession, NewAccount};
 use crate::db::schema::accounts as schema;
-use crate::db::{get_pg_connection, insert_db_object_dyn};
+use crate::db::{get_pg_connection, insert_db_object, insert_db_object_dyn, Ppc};
 use crate::structs::general::DbPkLoadable;
 
 make_pk_loadable!(Account, i32, crate::db::schema::accounts);
 
+sql_function!(fn lower(x: Nullable<Text>) -> Nullable<Text>);
+
+/// How long a player stays logged in for
+pub const SESSION_DURATION_HOURS: i64 = 24;
+
+/// Failed logins allowed in a row before the account is locked
+pub const FREE_LOGIN_ATTEMPTS: i32 = 5;
+
+/// The first lockout, it doubles with every failure after that
+pub const LOGIN_LOCKOUT_SECONDS: i64 = 30;
+
+/// Longest an account is ever locked for
+pub const MAX_LOGIN_LOCKOUT_SECONDS: i64 = 3600;
+
 pub fn find_account_for_steam_id(steam_id: &SteamID) -> Account {
     let conn = get_pg_connection();
     let result: Result<Option<Account>, diesel::result::Error> = schema::table
@@ -43,3 +66,220 @@ pub fn create_account_for_steam_id(steam_id: &SteamID) -> Account {
         insert_db_object_dyn(&conn, account, schema::dsl::accounts);
     result.expect("Failed to create new account for Steam ID")
 }
+
+/// Find an account by username, usernames are not case sensitive.
+pub fn find_account_for_username(username: &str) -> Option<Account> {
+    let conn = get_pg_connection();
+    schema::table
+        .filter(lower(schema::username).eq(username.to_lowercase()))
+        .first(&conn)
+        .optional()
+        .unwrap_or(None)
+}
+
+/// Create an account that logs in with a username and password
+pub fn create_account_for_username(
+    username: &str,
+    password: &str,
+    e_mail: Option<String>,
+) -> Result<Account, String> {
+    if find_account_for_username(username).is_some() {
+        return Err("Username is already taken".to_owned());
+    }
+
+    let conn = get_pg_connection();
+    let account = NewAccount {
+        date_added: Utc::now().naive_utc(),
+        username: Some(username.to_owned()),
+        password: Some(hash_password(password)),
+        e_mail,
+        mfa_code: None,
+        active: true,
+        steam_id: None,
+    };
+    insert_db_object_dyn(&conn, account, schema::dsl::accounts)
+}
+
+pub fn create_session(account: &Account) -> Result<AccountSession, ()> {
+    use crate::db::schema::account_sessions as session_schema;
+    let conn = get_pg_connection();
+    let time_now = Utc::now().naive_utc();
+
+    // Clean up any sessions for this account that have expired
+    diesel::delete(
+        session_schema::table
+            .filter(session_schema::account_fk.eq(account.account_id))
+            .filter(session_schema::date_expire.lt(time_now)),
+    )
+    .execute(&conn)
+    .map_err(|_| ())?;
+
+    let session = AccountSession {
+        session_id: generate_secure_token(64),
+        account_fk: account.account_id,
+        date_added: time_now,
+        date_expire: time_now + Duration::hours(SESSION_DURATION_HOURS),
+    };
+    insert_db_object(&conn, session, session_schema::table).map_err(|_| ())
+}
+
+pub fn delete_session(session_id: &str) -> QueryResult<usize> {
+    use crate::db::schema::account_sessions as session_schema;
+    let conn = get_pg_connection();
+    diesel::delete(session_schema::table.filter(session_schema::session_id.eq(session_id)))
+        .execute(&conn)
+}
+
+/// Log the account out everywhere except the session making the request
+pub fn delete_other_sessions(
+    account: &Account,
+    keep_session_id: Option<&str>,
+    conn: &Ppc,
+) -> QueryResult<usize> {
+    use crate::db::schema::account_sessions as session_schema;
+    diesel::delete(
+        session_schema::table
+            .filter(session_schema::account_fk.eq(account.account_id))
+            .filter(session_schema::session_id.ne(keep_session_id.unwrap_or_default())),
+    )
+    .execute(conn)
+}
+
+/// How long to lock an account for after this many failed logins in a row
+pub fn login_lockout(failed_logins: i32) -> Option<Duration> {
+    if failed_logins < FREE_LOGIN_ATTEMPTS {
+        return None;
+    }
+    let doublings = (failed_logins - FREE_LOGIN_ATTEMPTS).min(16) as u32;
+    Some(Duration::seconds(
+        (LOGIN_LOCKOUT_SECONDS << doublings).min(MAX_LOGIN_LOCKOUT_SECONDS),
+    ))
+}
+
+pub fn is_locked(account: &Account) -> bool {
+    account
+        .locked_until
+        .map_or(false, |until| until > Utc::now().naive_utc())
+}
+
+/// Count a wrong password or MFA code against the account, locking it if there's been too many
+pub fn record_failed_login(account: &Account) -> QueryResult<()> {
+    let conn = get_pg_connection();
+    // Counted by the database so attempts made in parallel all add up
+    let failed_logins: i32 = diesel::update(account)
+        .set(schema::failed_logins.eq(schema::failed_logins + 1))
+        .returning(schema::failed_logins)
+        .get_result(&conn)?;
+    if let Some(lockout) = login_lockout(failed_logins) {
+        diesel::update(account)
+            .set(schema::locked_until.eq(Utc::now().naive_utc() + lockout))
+            .execute(&conn)?;
+    }
+    Ok(())
+}
+
+pub fn clear_failed_logins(account: &Account) -> QueryResult<usize> {
+    let conn = get_pg_connection();
+    diesel::update(account)
+        .set((
+            schema::failed_logins.eq(0),
+            schema::locked_until.eq(None::<NaiveDateTime>),
+        ))
+        .execute(&conn)
+}
+
+/// Check a TOTP code for the account, each code is only accepted once
+/// so one that's been seen can't be replayed while it's still valid.
+pub fn accept_totp(account: &Account, secret: &str, code: &str) -> QueryResult<bool> {
+    let step = match totp_step(secret, code, Utc::now().timestamp() as u64) {
+        Some(step) => step as i64,
+        None => return Ok(false),
+    };
+    let conn = get_pg_connection();
+    // Only moves forwards, if two requests race with the same code just one gets in
+    let updated = diesel::update(account)
+        .filter(
+            schema::last_totp_step
+                .is_null()
+                .or(schema::last_totp_step.lt(step)),
+        )
+        .set(schema::last_totp_step.eq(step))
+        .execute(&conn)?;
+    Ok(updated == 1)
+}
+
+/// Load the account for a session, as long as the session hasn't expired
+/// and the account is active.
+pub fn get_account_for_session(session_id: &str) -> Result<Account, ()> {
+    use crate::db::schema::account_sessions as session_schema;
+    let conn = get_pg_connection();
+    let (_, account): (AccountSession, Account) = session_schema::table
+        .inner_join(schema::table)
+        .filter(session_schema::session_id.eq(session_id))
+        .filter(session_schema::date_expire.gt(Utc::now().naive_utc()))
+        .first(&conn)
+        .map_err(|_| ())?;
+
+    if account.active {
+        Ok(account)
+    } else {
+        Err(())
+    }
+}
+
+/// Get the session token from the `Authorization: Bearer <token>` header
+pub fn get_session_token(headers: &actix_http::header::HeaderMap) -> Option<&str> {
+    headers
+        .get(actix_http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+}
+
+pub struct SessionGuard();
+
+impl Guard for SessionGuard {
+    fn check(&self, ctx: &GuardContext<'_>) -> bool {
+        if let Some(token) = get_session_token(&ctx.head().headers) {
+            if let Ok(account) = get_account_for_session(token) {
+                ctx.req_data_mut().insert(account);
+                return true;
+            };
+        };
+        false
+    }
+}
+
+/// Same as the `SessionGuard` but the account must also be an admin
+pub struct AdminGuard();
+
+impl Guard for AdminGuard {
+    fn check(&self, ctx: &GuardContext<'_>) -> bool {
+        if let Some(token) = get_session_token(&ctx.head().headers) {
+            if let Ok(account) = get_account_for_session(token) {
+                if account.admin {
+                    ctx.req_data_mut().insert(account);
+                    return true;
+                }
+            };
+        };
+        false
+    }
+}
+
+impl FromRequest for Account {
+    type Error = actix_http::error::Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload<BoxedPayloadStream>) -> Self::Future {
+        if req.extensions().contains::<Self>() {
+            ok(req.extensions_mut().remove::<Self>().unwrap())
+        } else {
+            err(Error::from(PayloadError::Incomplete(Some(io::Error::new(
+                ErrorKind::Other,
+                "No session in request",
+            )))))
+        }
+    }
+}
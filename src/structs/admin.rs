@@ -0,0 +1,187 @@
+use diesel::prelude::*;
+use itertools::Itertools;
+use serde_json::Value;
+
+use crate::cache::config::invalidate_config;
+use crate::db::Ppc;
+use crate::jtd::api_config::structure::ApiConfigData;
+use crate::jtd::diff::DiffEntry;
+use crate::jtd::stock_config::ranges::{find_range_problems, RangeProblem};
+use crate::jtd::stock_config::StockConfig;
+use crate::jtd::validate::{validate, ValidationError, API_CONFIG_SCHEMA, STOCK_CONFIG_SCHEMA};
+
+/// The two versioned configuration tables
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigKind {
+    Api,
+    Stock,
+}
+
+#[derive(Serialize)]
+pub struct ConfigVersionList {
+    pub latest: Option<i32>,
+    pub versions: Vec<i32>,
+}
+
+#[derive(Serialize)]
+pub struct ConfigDocument {
+    pub version: i32,
+    pub config_data: Value,
+}
+
+#[derive(Serialize)]
+pub struct ConfigValidationResponse {
+    pub valid: bool,
+    pub errors: Vec<ValidationError>,
+    /// Set if the document matches the schema but the server still can't load it
+    pub message: Option<String>,
+    /// Gaps and overlaps in the stock thresholds' price ranges
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub range_problems: Vec<RangeProblem>,
+}
+
+#[derive(Deserialize)]
+pub struct ConfigDiffQuery {
+    pub from: i32,
+    /// Defaults to the latest version
+    pub to: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct ConfigDiffResponse {
+    pub from: i32,
+    pub to: i32,
+    pub changes: Vec<DiffEntry>,
+}
+
+impl ConfigKind {
+    fn schema(&self) -> &'static str {
+        match self {
+            ConfigKind::Api => API_CONFIG_SCHEMA,
+            ConfigKind::Stock => STOCK_CONFIG_SCHEMA,
+        }
+    }
+}
+
+/// Check a configuration document against the JTD schema, then make sure
+/// it will actually deserialize into the structure the server uses.
+pub fn validate_config(kind: ConfigKind, config_data: &Value) -> ConfigValidationResponse {
+    let schema: Value = serde_json::from_str(kind.schema()).expect("Invalid JTD schema");
+    let errors = validate(&schema, config_data);
+    let mut range_problems = Vec::new();
+
+    let message = if errors.is_empty() {
+        let result = match kind {
+            ConfigKind::Api => {
+                serde_json::from_value::<ApiConfigData>(config_data.clone()).map(|_| ())
+            }
+            ConfigKind::Stock => serde_json::from_value::<StockConfig>(config_data.clone())
+                .map(|config| range_problems = find_range_problems(&config)),
+        };
+        result.err().map(|e| format!("{}", e)).or_else(|| {
+            if range_problems.is_empty() {
+                None
+            } else {
+                Some(format!(
+                    "The threshold price ranges don't line up, {}",
+                    range_problems.iter().join("; ")
+                ))
+            }
+        })
+    } else {
+        None
+    };
+
+    ConfigValidationResponse {
+        valid: errors.is_empty() && message.is_none(),
+        errors,
+        message,
+        range_problems,
+    }
+}
+
+/// All the versions of a configuration, newest first
+pub fn list_config_versions(kind: ConfigKind, conn: &Ppc) -> QueryResult<Vec<i32>> {
+    match kind {
+        ConfigKind::Api => {
+            use crate::db::schema::api_config as schema;
+            schema::table
+                .select(schema::version)
+                .order_by(schema::version.desc())
+                .load(conn)
+        }
+        ConfigKind::Stock => {
+            use crate::db::schema::stock_config as schema;
+            schema::table
+                .select(schema::version)
+                .order_by(schema::version.desc())
+                .load(conn)
+        }
+    }
+}
+
+/// Load a configuration version as plain JSON, or the latest if no version is given.
+/// We don't use the models here so that nothing is lost if the document
+/// has fields the server doesn't know about.
+pub fn load_config_version(
+    kind: ConfigKind,
+    version: Option<i32>,
+    conn: &Ppc,
+) -> QueryResult<Option<ConfigDocument>> {
+    let row: Option<(i32, Value)> = match kind {
+        ConfigKind::Api => {
+            use crate::db::schema::api_config as schema;
+            let mut query = schema::table
+                .select((schema::version, schema::config_data))
+                .order_by(schema::version.desc())
+                .into_boxed();
+            if let Some(version) = version {
+                query = query.filter(schema::version.eq(version));
+            }
+            query.first(conn).optional()?
+        }
+        ConfigKind::Stock => {
+            use crate::db::schema::stock_config as schema;
+            let mut query = schema::table
+                .select((schema::version, schema::config_data))
+                .order_by(schema::version.desc())
+                .into_boxed();
+            if let Some(version) = version {
+                query = query.filter(schema::version.eq(version));
+            }
+            query.first(conn).optional()?
+        }
+    };
+    Ok(row.map(|(version, config_data)| ConfigDocument {
+        version,
+        config_data,
+    }))
+}
+
+/// Insert a document as the next version, the caller must validate it first.
+/// Nodes pick up the new version the next time they poll the database.
+pub fn insert_config_version(
+    kind: ConfigKind,
+    config_data: &Value,
+    conn: &Ppc,
+) -> QueryResult<i32> {
+    let version = match kind {
+        ConfigKind::Api => {
+            use crate::db::schema::api_config as schema;
+            diesel::insert_into(schema::table)
+                .values(schema::config_data.eq(config_data))
+                .returning(schema::version)
+                .get_result(conn)?
+        }
+        ConfigKind::Stock => {
+            use crate::db::schema::stock_config as schema;
+            diesel::insert_into(schema::table)
+                .values(schema::config_data.eq(config_data))
+                .returning(schema::version)
+                .get_result(conn)?
+        }
+    };
+    invalidate_config();
+    Ok(version)
+}
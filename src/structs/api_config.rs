@@ -17,6 +17,10 @@ use actix_web::web::Data;
 
 pub(crate) type LockedApiConfig = Arc<RwLock<Option<ApiConfig>>>;
 
+/// Requests under this path are still served while the API is offline,
+/// otherwise there's no way to bring it back online.
+pub const ADMIN_PATH_PREFIX: &'static str = "/api/v1/admin/";
+
 lazy_static! {
     pub static ref API_CONFIG_ARC: LockedApiConfig = Arc::new(RwLock::new(None));
 }
@@ -68,13 +72,14 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let service = Rc::clone(&self.service);
-        let online = req.app_data::<Data<LockedApiConfig>>().map_or_else(
-            || false,
-            |c| {
-                let config = c.read();
-                !config.as_ref().unwrap().config_data.api.force_offline
-            },
-        );
+        let online = req.path().starts_with(ADMIN_PATH_PREFIX)
+            || req.app_data::<Data<LockedApiConfig>>().map_or_else(
+                || false,
+                |c| {
+                    let config = c.read();
+                    !config.as_ref().unwrap().config_data.api.force_offline
+                },
+            );
         Box::pin(async move {
             if online {
                 Ok(service.call(req).await?)
@@ -0,0 +1,238 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::ops::Deref;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::result::Error::RollbackTransaction;
+use diesel::{ExpressionMethods, QueryDsl};
+use itertools::Itertools;
+use uuid::Uuid;
+
+use crate::cache::inventory::invalidate_inventory;
+use crate::crypto::generate_v4_uuid;
+use crate::db::models::backorder::Backorder;
+use crate::db::models::bank::BankBalance;
+use crate::db::models::colony::Colony;
+use crate::db::models::inventory::Inventory;
+use crate::db::schema::backorders as schema;
+use crate::db::{insert_db_object, Ppc};
+use crate::packets::backorder::{BackorderData, BackorderStatusEnum};
+use crate::packets::bank::BankLedgerKindEnum;
+use crate::packets::common::CurrencyEnum;
+use crate::packets::order::OrderItem;
+use crate::structs::bank_balance::refund_value;
+use crate::structs::bank_ledger::apply_ledger_entry;
+use crate::structs::general::DbPkLoadable;
+use crate::structs::inventory::get_inventory_for_update;
+use crate::structs::order::{create_order, OrderManifest, OrderStats};
+use crate::traits::numerical::CanRound;
+use bigdecimal::{BigDecimal, ToPrimitive};
+
+make_pk_loadable!(Backorder, Uuid, crate::db::schema::backorders);
+
+impl From<Backorder> for BackorderData {
+    fn from(b: Backorder) -> Self {
+        BackorderData {
+            backorder_id: b.backorder_id.to_string(),
+            item_code: b.item_code,
+            quantity: b.quantity,
+            health: b.health,
+            paid: b.paid,
+            currency: b.currency,
+            status: b.status,
+            order_id: b.order_id.map_or(String::new(), |id| id.to_string()),
+            placed_tick: b.placed_tick,
+        }
+    }
+}
+
+/// Create a backorder for each item that was out of stock, the colony pays for
+/// the items now and they are delivered once the market restocks.
+/// Returns the backorders and the total amount taken from the bank,
+/// `rate` is the exchange rate of the bank balance's currency.
+pub fn create_backorders(
+    colony: &Colony,
+    market: &str,
+    items: &Vec<OrderItem>,
+    db_inventory: &HashMap<String, Inventory>,
+    rate: &BigDecimal,
+    bank_balance: &mut BankBalance,
+    conn: &Ppc,
+) -> Result<(Vec<Backorder>, i32), ()> {
+    let now = Utc::now().naive_utc();
+    let mut backorders = Vec::<Backorder>::with_capacity(items.len());
+    let mut total_paid = 0;
+
+    for item in items {
+        let stock = db_inventory.get(&item.item_code).ok_or(())?;
+        let paid = refund_value(stock, item, rate)
+            .round_2dp()
+            .to_i32()
+            .ok_or(())?;
+
+        let backorder = Backorder {
+            backorder_id: generate_v4_uuid(),
+            colony_id: colony.colony_id,
+            item_code: item.item_code.clone(),
+            quantity: item.quantity,
+            health: item.health,
+            paid,
+            currency: bank_balance.currency,
+            status: BackorderStatusEnum::Pending.into(),
+            order_id: None,
+            placed_tick: colony.tick,
+            create_date: now,
+            update_date: now,
+            market: market.to_string(),
+        };
+
+        let backorder = insert_db_object(conn, backorder, schema::table).map_err(|_| ())?;
+        apply_ledger_entry(
+            bank_balance,
+            paid * -1,
+            BankLedgerKindEnum::Backorder,
+            Some(backorder.backorder_id),
+            conn,
+        )?;
+        backorders.push(backorder);
+        total_paid += paid;
+    }
+
+    Ok((backorders, total_paid))
+}
+
+/// Get the backorders for a colony, only pending ones unless `any` is set.
+pub fn get_backorders_for_colony(colony_id: Uuid, any: bool, conn: &Ppc) -> Vec<Backorder> {
+    let mut query = schema::table
+        .filter(schema::colony_id.eq(colony_id))
+        .order(schema::create_date.asc())
+        .into_boxed();
+
+    if !any {
+        query = query.filter(schema::status.eq(i32::from(BackorderStatusEnum::Pending)));
+    }
+
+    query.get_results(conn).unwrap_or_else(|e| {
+        warn!("Failed to load backorders for colony {}, {}", colony_id, e);
+        Vec::new()
+    })
+}
+
+/// Fill as many of the market's pending backorders as its stock allows, oldest first.
+/// Filled backorders are grouped into a single order per colony for delivery.
+pub fn fill_pending_backorders(market: &str, conn: &Ppc) -> Result<usize, ()> {
+    use crate::db::schema::colonies as colony_schema;
+
+    let (count, item_codes) = conn
+        .build_transaction()
+        .read_committed()
+        .run::<_, diesel::result::Error, _>(|| {
+            let pending: Vec<Backorder> = schema::table
+                .filter(schema::status.eq(i32::from(BackorderStatusEnum::Pending)))
+                .filter(schema::market.eq(market))
+                .order(schema::create_date.asc())
+                .for_update()
+                .get_results(conn)?;
+
+            if pending.is_empty() {
+                return Ok((0, vec![]));
+            }
+
+            let item_codes: HashSet<&String> = pending.iter().map(|b| &b.item_code).collect();
+            let mut db_inventory = get_inventory_for_update(item_codes, market, conn)?;
+
+            // Take the stock for each backorder we can fill
+            let mut filled = HashMap::<(Uuid, i32), Vec<&Backorder>>::new();
+            for backorder in pending.iter() {
+                let stock = match db_inventory.get_mut(&backorder.item_code) {
+                    None => continue,
+                    Some(v) => v,
+                };
+                if stock.quantity < backorder.quantity {
+                    continue;
+                }
+                stock.quantity -= backorder.quantity;
+                filled
+                    .entry((backorder.colony_id, backorder.currency))
+                    .or_default()
+                    .push(backorder);
+            }
+
+            for value in db_inventory.values() {
+                value.save_changes::<Inventory>(conn.deref())?;
+            }
+
+            let now = Utc::now().naive_utc();
+            let mut count = 0usize;
+            for ((colony_id, currency), backorders) in filled {
+                let colony: Colony = colony_schema::table.find(colony_id).get_result(conn)?;
+
+                let mut os = OrderStats::default();
+                let mut paid = 0;
+                for backorder in backorders.iter() {
+                    let stock = db_inventory.get(&backorder.item_code).unwrap();
+                    os.total_buy_weight += &stock.weight * BigDecimal::from(backorder.quantity);
+                    paid += backorder.paid;
+                }
+                os.total_buy_weight = os.total_buy_weight.round_2dp();
+                os.total_buy_cost = BigDecimal::from(paid);
+                os.total_buy_item_cost = Some(os.total_buy_cost.clone());
+
+                // The colony already paid when the backorder was placed,
+                // so rolling back this order should refund them.
+                let manifest = OrderManifest {
+                    wts: vec![],
+                    wtb: backorders
+                        .iter()
+                        .map(|b| OrderItem {
+                            item_code: b.item_code.clone(),
+                            quantity: b.quantity,
+                            health: b.health,
+                        })
+                        .collect_vec(),
+                    balance_adjustment: paid * -1,
+                    currency: CurrencyEnum::try_from(currency).unwrap(),
+                };
+
+                let order = match create_order(
+                    generate_v4_uuid(),
+                    &os,
+                    &colony,
+                    market,
+                    manifest,
+                    colony.tick,
+                    None,
+                    conn,
+                ) {
+                    Err(_) => return Err(RollbackTransaction),
+                    Ok(v) => v,
+                };
+
+                let backorder_ids = backorders.iter().map(|b| b.backorder_id).collect_vec();
+                diesel::update(schema::table.filter(schema::backorder_id.eq_any(backorder_ids)))
+                    .set((
+                        schema::status.eq(i32::from(BackorderStatusEnum::Filled)),
+                        schema::order_id.eq(Some(order.order_id)),
+                        schema::update_date.eq(now),
+                    ))
+                    .execute(conn)?;
+
+                info!(
+                    "Filled {} backorders for colony {} with order {}",
+                    backorders.len(),
+                    colony_id,
+                    order.order_id
+                );
+                count += backorders.len();
+            }
+            Ok((count, db_inventory.into_keys().collect_vec()))
+        })
+        .map_err(|e| {
+            error!("Failed to fill backorders: {:?}", e);
+        })?;
+
+    // Only once the stock changes are visible to everyone else
+    invalidate_inventory(market, &item_codes);
+    Ok(count)
+}
@@ -7,12 +7,17 @@ use uuid::Uuid;
 use crate::db::models::bank::BankBalance;
 use crate::db::models::inventory::Inventory;
 use crate::db::Ppc;
+use crate::packets::bank::BankLedgerKindEnum;
 use crate::packets::common::CurrencyEnum;
 use crate::packets::order::OrderItem;
+use crate::structs::bank_ledger::apply_ledger_entry;
+use crate::structs::currency::from_utc;
 use crate::structs::order::OrderStats;
 use crate::traits::numerical::CanRound;
 use bigdecimal::{BigDecimal, ToPrimitive};
 
+/// The row is locked until the transaction ends, so the balance can't change between
+/// checking it and applying a ledger entry. Call it inside a transaction before changing it.
 pub fn get_bank_balance(colony_id: Uuid, currency: i32, conn: &Ppc) -> Result<BankBalance, ()> {
     use crate::db::schema::bank_balances as schema;
 
@@ -21,34 +26,46 @@ pub fn get_bank_balance(colony_id: Uuid, currency: i32, conn: &Ppc) -> Result<Ba
         return Err(());
     }
 
-    match schema::table
+    let query = schema::table
         .filter(schema::colony_id.eq(colony_id))
         .filter(schema::currency.eq(currency))
-        .get_result::<BankBalance>(conn)
-    {
+        .for_update();
+
+    match query.clone().get_result::<BankBalance>(conn) {
         Err(diesel::NotFound) => {
-            match diesel::insert_into(schema::table)
+            // Another request may be creating it at the same time, theirs is just as good
+            diesel::insert_into(schema::table)
                 .values(BankBalance {
                     colony_id: colony_id.clone(),
                     currency,
                     balance: 0,
                 })
-                .get_result(conn)
-            {
-                Err(_) => Err(()),
-                Ok(v) => Ok(v),
-            }
+                .on_conflict_do_nothing()
+                .execute(conn)
+                .map_err(|_| ())?;
+            query.get_result::<BankBalance>(conn).map_err(|_| ())
         }
         Err(_) => Err(()),
         Ok(a) => Ok(a),
     }
 }
 
+/// The amount owed back to the colony for an item we couldn't supply,
+/// `rate` is the exchange rate of the currency they paid with.
+pub fn refund_value(stock: &Inventory, item: &OrderItem, rate: &BigDecimal) -> BigDecimal {
+    from_utc(&(&stock.sell_at * BigDecimal::from(item.quantity)), rate)
+}
+
+/// The order stats must already be in the bank balance's currency,
+/// `rate` is only used for refunds.
+/// Each change is recorded on the ledger against the order.
 pub fn update_bank(
+    order_id: Uuid,
     os: &OrderStats,
     db_inventory: &HashMap<String, Inventory>,
     additional_funds: i32,
     refund: Option<&Vec<OrderItem>>,
+    rate: &BigDecimal,
     bank_balance: &mut BankBalance,
     conn: &Ppc,
 ) -> Result<(i32, i32), ()> {
@@ -59,28 +76,33 @@ pub fn update_bank(
     if let Some(refund) = refund {
         for item in refund {
             // Add what ever we refunded to the account
-            let item = db_inventory.get(&item.item_code).unwrap();
-            refunded += (&item.sell_at * BigDecimal::from(item.quantity)).round_2dp();
+            let stock = db_inventory.get(&item.item_code).unwrap();
+            refunded += refund_value(stock, item, rate);
         }
     }
 
     // Round then truncate, any partial amount or < 1, will be lost.
     let total_refund = refunded.round_2dp().to_i32().unwrap();
-    bank_balance.balance += total_refund;
+    apply_ledger_entry(
+        bank_balance,
+        total_refund,
+        BankLedgerKindEnum::Refund,
+        Some(order_id),
+        conn,
+    )?;
 
-    // Add any funds they sent with the order
-    bank_balance.balance += additional_funds;
+    // Add any funds they sent with the order and what they sold,
+    // then deduct expenditures
+    let order_total = additional_funds + os.total_sell_cost.round_2dp().to_i32().unwrap()
+        - os.total_buy_cost.round_2dp().to_i32().unwrap();
+    apply_ledger_entry(
+        bank_balance,
+        order_total,
+        BankLedgerKindEnum::Order,
+        Some(order_id),
+        conn,
+    )?;
 
-    // Add what they sold to the balance
-    bank_balance.balance += os.total_sell_cost.round_2dp().to_i32().unwrap();
-
-    // Deduct expenditures
-    bank_balance.balance -= os.total_buy_cost.round_2dp().to_i32().unwrap();
-
-    if bank_balance.save_changes::<BankBalance>(&**conn).is_err() {
-        Err(())
-    } else {
-        // Return the difference between the new and old balance
-        Ok((total_refund, bank_balance.balance - starting_balance))
-    }
+    // Return the difference between the new and old balance
+    Ok((total_refund, bank_balance.balance - starting_balance))
 }
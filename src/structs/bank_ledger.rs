@@ -0,0 +1,132 @@
+use std::ops::Deref;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{BigInt, Integer, Uuid as SqlUuid};
+use uuid::Uuid;
+
+use crate::db::models::bank::{BankBalance, BankLedgerEntry, NewBankLedgerEntry};
+use crate::db::schema::bank_ledger as schema;
+use crate::db::Ppc;
+use crate::packets::bank::{BankLedgerEntryData, BankLedgerKindEnum};
+
+/// The most entries that can be requested at once
+pub const MAX_HISTORY_ENTRIES: i64 = 100;
+
+impl From<BankLedgerEntry> for BankLedgerEntryData {
+    fn from(e: BankLedgerEntry) -> Self {
+        BankLedgerEntryData {
+            entry_id: e.entry_id,
+            currency: e.currency,
+            amount: e.amount,
+            balance: e.balance,
+            kind: e.kind,
+            reference_id: e.reference_id.map_or(String::new(), |id| id.to_string()),
+            create_date: e.create_date.timestamp(),
+        }
+    }
+}
+
+/// A balance that doesn't match the sum of its ledger entries
+#[derive(QueryableByName, Debug)]
+pub struct LedgerMismatch {
+    #[sql_type = "SqlUuid"]
+    pub colony_id: Uuid,
+    #[sql_type = "Integer"]
+    pub currency: i32,
+    #[sql_type = "Integer"]
+    pub balance: i32,
+    #[sql_type = "BigInt"]
+    pub ledger_total: i64,
+}
+
+/// The only way a bank balance should change, adds the amount to the balance,
+/// saves it and records the entry. Must be called inside a transaction so the
+/// two can't get out of step.
+pub fn apply_ledger_entry(
+    bank_balance: &mut BankBalance,
+    amount: i32,
+    kind: BankLedgerKindEnum,
+    reference_id: Option<Uuid>,
+    conn: &Ppc,
+) -> Result<(), ()> {
+    // Nothing moved, don't clutter the history
+    if amount == 0 {
+        return Ok(());
+    }
+
+    // Add to what's in the database rather than writing back our copy,
+    // so a change we haven't seen can't be lost.
+    let balance: i32 = {
+        use crate::db::schema::bank_balances as balance_schema;
+        diesel::update(&*bank_balance)
+            .set(balance_schema::balance.eq(balance_schema::balance + amount))
+            .returning(balance_schema::balance)
+            .get_result(conn)
+            .map_err(|_| ())?
+    };
+    bank_balance.balance = balance;
+
+    diesel::insert_into(schema::table)
+        .values(NewBankLedgerEntry {
+            colony_id: bank_balance.colony_id,
+            currency: bank_balance.currency,
+            amount,
+            balance: bank_balance.balance,
+            kind: kind.into(),
+            reference_id,
+            create_date: Utc::now().naive_utc(),
+        })
+        .execute(conn)
+        .map(|_| ())
+        .map_err(|_| ())
+}
+
+/// Ledger entries for one of a colony's balances, newest first.
+/// `before` is the entry ID to page back from, 0 for the newest entries.
+pub fn get_ledger_for_colony(
+    colony_id: Uuid,
+    currency: i32,
+    before: i64,
+    limit: i64,
+    conn: &Ppc,
+) -> Vec<BankLedgerEntry> {
+    let mut query = schema::table
+        .filter(schema::colony_id.eq(colony_id))
+        .filter(schema::currency.eq(currency))
+        .order(schema::entry_id.desc())
+        .limit(limit.max(1).min(MAX_HISTORY_ENTRIES))
+        .into_boxed();
+
+    if before > 0 {
+        query = query.filter(schema::entry_id.lt(before));
+    }
+
+    query.get_results(conn).unwrap_or_else(|e| {
+        warn!("Failed to load bank ledger for colony {}, {}", colony_id, e);
+        Vec::new()
+    })
+}
+
+/// Find every balance that doesn't add up to the sum of its ledger entries,
+/// these need investigating by hand, nothing is changed here.
+pub fn reconcile_bank_balances(conn: &Ppc) -> QueryResult<Vec<LedgerMismatch>> {
+    let mismatches: Vec<LedgerMismatch> = sql_query(
+        r#"SELECT b.colony_id, b.currency, b.balance,
+                  COALESCE(SUM(l.amount), 0)::bigint AS ledger_total
+           FROM bank_balances b
+           LEFT JOIN bank_ledger l ON l.colony_id = b.colony_id AND l.currency = b.currency
+           GROUP BY b.colony_id, b.currency, b.balance
+           HAVING b.balance != COALESCE(SUM(l.amount), 0)"#,
+    )
+    .load(conn.deref())?;
+
+    for m in mismatches.iter() {
+        warn!(
+            "Bank balance for colony {} currency {} is {} but the ledger totals {}",
+            m.colony_id, m.currency, m.balance, m.ledger_total
+        );
+    }
+    Ok(mismatches)
+}
@@ -0,0 +1,223 @@
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::crypto::generate_v4_uuid;
+use crate::db::models::bank::BankTransfer;
+use crate::db::models::bind::ClientBind;
+use crate::db::models::colony::Colony;
+use crate::db::schema::bank_transfers as schema;
+use crate::db::{insert_db_object, Ppc};
+use crate::packets::bank::{BankLedgerKindEnum, BankTransferData, BankTransferStatusEnum};
+use crate::structs::bank_balance::get_bank_balance;
+use crate::structs::bank_ledger::apply_ledger_entry;
+use crate::structs::binds::load_active_client_bind;
+use crate::structs::general::DbPkLoadable;
+
+make_pk_loadable!(BankTransfer, Uuid, crate::db::schema::bank_transfers);
+
+impl From<BankTransfer> for BankTransferData {
+    fn from(t: BankTransfer) -> Self {
+        BankTransferData {
+            transfer_id: t.transfer_id.to_string(),
+            from_colony_id: t.from_colony_id.to_string(),
+            to_colony_id: t.to_colony_id.to_string(),
+            currency: t.currency,
+            amount: t.amount,
+            status: t.status,
+            create_date: t.create_date.timestamp(),
+        }
+    }
+}
+
+/// Check if both colonies belong to the same account, they may be on different binds.
+pub fn same_account(from: &Colony, to: &Colony) -> bool {
+    if from.client_bind_fk == to.client_bind_fk {
+        return true;
+    }
+    match (
+        ClientBind::load_pk(&from.client_bind_fk),
+        ClientBind::load_pk(&to.client_bind_fk),
+    ) {
+        (Ok(f), Ok(t)) => f.account_fk == t.account_fk,
+        _ => false,
+    }
+}
+
+/// A colony can only be sent money if it's been seen in the last `stale_colony_days`
+/// and it's still on a confirmed bind of an active account.
+pub fn can_receive_transfers(colony: &Colony, stale_colony_days: u32) -> bool {
+    let stale_before = Utc::now().naive_utc() - Duration::days(stale_colony_days as i64);
+    colony.update_date >= stale_before && load_active_client_bind(&colony.client_bind_fk).is_ok()
+}
+
+/// Take the money from the sender and record the transfer, it's paid in straight away
+/// if both colonies are on the same account, otherwise it's held until the recipient
+/// accepts it. Must be called inside a transaction.
+/// Returns the transfer and the sender's new balance.
+pub fn create_transfer(
+    from: &Colony,
+    to: &Colony,
+    currency: i32,
+    amount: i32,
+    confirmed: bool,
+    conn: &Ppc,
+) -> Result<(BankTransfer, i32), ()> {
+    if amount <= 0 || from.colony_id == to.colony_id {
+        return Err(());
+    }
+
+    let mut from_balance = get_bank_balance(from.colony_id, currency, conn)?;
+    if amount > from_balance.balance {
+        return Err(());
+    }
+
+    let now = Utc::now().naive_utc();
+    let mut transfer = BankTransfer {
+        transfer_id: generate_v4_uuid(),
+        from_colony_id: from.colony_id,
+        to_colony_id: to.colony_id,
+        currency,
+        amount,
+        status: BankTransferStatusEnum::Pending.into(),
+        create_date: now,
+        update_date: now,
+    };
+
+    apply_ledger_entry(
+        &mut from_balance,
+        amount * -1,
+        BankLedgerKindEnum::Transfer,
+        Some(transfer.transfer_id),
+        conn,
+    )?;
+
+    if confirmed {
+        pay_recipient(&transfer, conn)?;
+        transfer.status = BankTransferStatusEnum::Completed.into();
+    }
+
+    let transfer = insert_db_object(conn, transfer, schema::table).map_err(|_| ())?;
+    Ok((transfer, from_balance.balance))
+}
+
+/// Accept or decline a pending transfer, declined transfers go back to the sender.
+/// Must be called inside a transaction.
+/// Returns the recipient's new balance.
+pub fn confirm_transfer(transfer: &mut BankTransfer, accept: bool, conn: &Ppc) -> Result<i32, ()> {
+    if accept {
+        close_transfer(transfer, BankTransferStatusEnum::Completed, conn)?;
+        pay_recipient(transfer, conn)
+    } else {
+        close_transfer(transfer, BankTransferStatusEnum::Declined, conn)?;
+        refund_sender(transfer, conn)?;
+        get_bank_balance(transfer.to_colony_id, transfer.currency, conn).map(|b| b.balance)
+    }
+}
+
+/// Call off a pending transfer, the money goes back to the sender.
+/// Must be called inside a transaction.
+/// Returns the sender's new balance.
+pub fn cancel_transfer(transfer: &mut BankTransfer, conn: &Ppc) -> Result<i32, ()> {
+    close_transfer(transfer, BankTransferStatusEnum::Cancelled, conn)?;
+    refund_sender(transfer, conn)
+}
+
+/// Send back transfers that have been waiting on the recipient for longer than
+/// `expiry_days`, each one in its own transaction.
+/// Returns how many were expired.
+pub fn expire_pending_transfers(expiry_days: u32, conn: &Ppc) -> QueryResult<usize> {
+    let expire_before = Utc::now().naive_utc() - Duration::days(expiry_days as i64);
+    let pending: Vec<BankTransfer> = schema::table
+        .filter(schema::status.eq(i32::from(BankTransferStatusEnum::Pending)))
+        .filter(schema::create_date.lt(expire_before))
+        .get_results(conn)?;
+
+    let mut count = 0;
+    for mut transfer in pending {
+        match conn
+            .build_transaction()
+            .read_committed()
+            .run::<_, diesel::result::Error, _>(|| {
+                close_transfer(&mut transfer, BankTransferStatusEnum::Expired, conn)
+                    .and_then(|_| refund_sender(&transfer, conn))
+                    .map_err(|_| diesel::result::Error::RollbackTransaction)
+            }) {
+            Ok(_) => count += 1,
+            // Most likely accepted or cancelled since we loaded it
+            Err(_) => warn!("Unable to expire bank transfer {}", transfer.transfer_id),
+        }
+    }
+    Ok(count)
+}
+
+/// Only moves it on if it's still pending, the other side may have got there first.
+fn close_transfer(
+    transfer: &mut BankTransfer,
+    status: BankTransferStatusEnum,
+    conn: &Ppc,
+) -> Result<(), ()> {
+    transfer.status = status.into();
+    transfer.update_date = Utc::now().naive_utc();
+    let updated = diesel::update(
+        schema::table
+            .filter(schema::transfer_id.eq(transfer.transfer_id))
+            .filter(schema::status.eq(i32::from(BankTransferStatusEnum::Pending))),
+    )
+    .set(&*transfer)
+    .execute(conn)
+    .map_err(|_| ())?;
+
+    if updated != 1 {
+        return Err(());
+    }
+    Ok(())
+}
+
+fn refund_sender(transfer: &BankTransfer, conn: &Ppc) -> Result<i32, ()> {
+    let mut from_balance = get_bank_balance(transfer.from_colony_id, transfer.currency, conn)?;
+    apply_ledger_entry(
+        &mut from_balance,
+        transfer.amount,
+        BankLedgerKindEnum::Transfer,
+        Some(transfer.transfer_id),
+        conn,
+    )?;
+    Ok(from_balance.balance)
+}
+
+fn pay_recipient(transfer: &BankTransfer, conn: &Ppc) -> Result<i32, ()> {
+    let mut to_balance = get_bank_balance(transfer.to_colony_id, transfer.currency, conn)?;
+    apply_ledger_entry(
+        &mut to_balance,
+        transfer.amount,
+        BankLedgerKindEnum::Transfer,
+        Some(transfer.transfer_id),
+        conn,
+    )?;
+    Ok(to_balance.balance)
+}
+
+/// Transfers sent or received by a colony, newest first, only pending ones unless `any` is set.
+pub fn get_transfers_for_colony(colony_id: Uuid, any: bool, conn: &Ppc) -> Vec<BankTransfer> {
+    let mut query = schema::table
+        .filter(
+            schema::from_colony_id
+                .eq(colony_id)
+                .or(schema::to_colony_id.eq(colony_id)),
+        )
+        .order(schema::create_date.desc())
+        .into_boxed();
+
+    if !any {
+        query = query.filter(schema::status.eq(i32::from(BankTransferStatusEnum::Pending)));
+    }
+
+    query.get_results(conn).unwrap_or_else(|e| {
+        warn!(
+            "Failed to load bank transfers for colony {}, {}",
+            colony_id, e
+        );
+        Vec::new()
+    })
+}
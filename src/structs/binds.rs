@@ -13,10 +13,11 @@ use futures::future::{err, ok, Ready};
 use steamid_ng::SteamID;
 use uuid::Uuid;
 
-use crate::crypto::generate_v4_uuid;
+use crate::cache::bind::{get_active_client_bind, invalidate_client_bind};
+use crate::crypto::{generate_v4_uuid, parse_uuid};
 use crate::db::models::account::Account;
 use crate::db::models::bind::{AccountBind, ClientBind};
-use crate::db::{get_pg_connection, insert_db_object, parse_and_load_uuid_pk};
+use crate::db::{get_pg_connection, insert_db_object};
 use crate::structs::account::find_account_for_steam_id;
 use crate::structs::general::DbPkLoadable;
 
@@ -62,6 +63,7 @@ pub fn delete_client_bind_id(bind_id: Uuid) {
     diesel::delete(schema::table.filter(schema::client_bind_id.eq(bind_id)))
         .execute(&conn)
         .expect("Failed to delete client bind ID");
+    invalidate_client_bind(&bind_id);
 }
 
 pub fn account_bind_valid(current_time: &NaiveDateTime, bind: &AccountBind) -> bool {
@@ -107,6 +109,7 @@ pub fn steam_autogenerate_client_bind_id(
                 account_fk: account.account_id,
                 confirmed: false,
                 date_added: time_now.clone(),
+                account_bind_fk: Some(account_bind.bind_id),
             };
 
             let result: Result<ClientBind, String> =
@@ -131,18 +134,59 @@ pub fn steam_autogenerate_client_bind_id(
     }
 }
 
+/// The Client Bind created when the account bind was claimed, Err if it hasn't been claimed.
 pub fn get_non_steam_client_bind_id(account_bind: &AccountBind) -> Result<ClientBind, ()> {
     let conn = get_pg_connection();
     use crate::db::schema::client_binds as cb_schema;
 
-    // Check if the Client ID has already been generated.
-    let result: Result<ClientBind, diesel::result::Error> = cb_schema::table
-        .filter(cb_schema::account_fk.eq(account_bind.account_fk.unwrap()))
+    let account_fk = account_bind.account_fk.ok_or(())?;
+    cb_schema::table
+        .filter(cb_schema::account_bind_fk.eq(account_bind.bind_id))
+        .filter(cb_schema::account_fk.eq(account_fk))
         .filter(cb_schema::confirmed.eq(false))
-        .order(cb_schema::date_added.desc())
-        .first(&conn);
+        .first(&conn)
+        .map_err(|_| ())
+}
 
-    result.map_or(Err(()), |v| Ok(v))
+/// Link a DRM-free account bind to an account that has logged in, this creates
+/// the Client Bind that the game collects when it next polls the bind,
+/// the account bind is confirmed at that point.
+pub fn claim_account_bind(account: &Account, account_bind: &AccountBind) -> Result<ClientBind, ()> {
+    let conn = get_pg_connection();
+    use crate::db::schema::account_binds as ab_schema;
+    use crate::db::schema::client_binds as cb_schema;
+
+    conn.build_transaction()
+        .read_committed()
+        .run::<_, diesel::result::Error, _>(|| {
+            // Only claim it if nobody else got there first
+            let updated = diesel::update(
+                ab_schema::table
+                    .filter(ab_schema::bind_id.eq(&account_bind.bind_id))
+                    .filter(ab_schema::confirmed.eq(false))
+                    .filter(ab_schema::account_fk.is_null()),
+            )
+            .set(ab_schema::account_fk.eq(account.account_id))
+            .execute(&conn)?;
+
+            if updated != 1 {
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+
+            let time_now = Utc::now().naive_utc();
+            let cb = ClientBind {
+                client_bind_id: generate_v4_uuid(),
+                account_fk: account.account_id,
+                confirmed: false,
+                date_added: time_now,
+                account_bind_fk: Some(account_bind.bind_id),
+            };
+
+            diesel::insert_into(cb_schema::table)
+                .values(cb)
+                .get_result::<ClientBind>(&conn)
+        })
+        .map_err(|_| ())
 }
 
 pub struct ClientIdGuard();
@@ -152,12 +196,12 @@ pub const ISE_CLIENT_HEADER_NAME: &'static str = "x-ise-client-id";
 impl Guard for ClientIdGuard {
     fn check(&self, ctx: &GuardContext<'_>) -> bool {
         if let Some(val) = ctx.head().headers().get(ISE_CLIENT_HEADER_NAME) {
-            if let Ok(bind) = parse_and_load_uuid_pk::<ClientBind>(val.to_str().unwrap_or("")) {
-                if let Ok(account) = Account::load_pk(&bind.account_fk) {
-                    if account.active && bind.confirmed {
-                        ctx.req_data_mut().insert(bind);
-                        return true;
-                    };
+            let client_bind_id = val.to_str().unwrap_or("");
+
+            if let Ok(client_bind_id) = parse_uuid(client_bind_id) {
+                if let Ok(bind) = get_active_client_bind(&client_bind_id) {
+                    ctx.req_data_mut().insert(bind);
+                    return true;
                 };
             };
         };
@@ -165,6 +209,21 @@ impl Guard for ClientIdGuard {
     }
 }
 
+/// A confirmed Client Bind, as long as the account it belongs to is active
+pub fn load_active_client_bind(client_bind_id: &Uuid) -> QueryResult<ClientBind> {
+    use crate::db::schema::accounts as a_schema;
+    use crate::db::schema::client_binds as cb_schema;
+
+    let conn = get_pg_connection();
+    cb_schema::table
+        .inner_join(a_schema::table)
+        .filter(cb_schema::client_bind_id.eq(client_bind_id))
+        .filter(cb_schema::confirmed.eq(true))
+        .filter(a_schema::active.eq(true))
+        .select(cb_schema::all_columns)
+        .first(&conn)
+}
+
 impl FromRequest for ClientBind {
     type Error = actix_http::error::Error;
     type Future = Ready<Result<Self, Error>>;
@@ -0,0 +1,179 @@
+use std::str::FromStr;
+
+use chrono::Utc;
+use diesel::prelude::*;
+
+use crate::cache::blacklist::invalidate_blacklist;
+use crate::db::models::blacklist::{BlacklistEntry, NewBlacklistEntry};
+use crate::db::models::inventory::Inventory;
+use crate::db::schema::item_blacklist as schema;
+use crate::db::Ppc;
+use crate::traits::item::{HasItemCode, HasStuff, HasThingDef};
+
+/// Every blacklist entry, loaded once and checked against many items
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Blacklist {
+    pub entries: Vec<BlacklistEntry>,
+}
+
+impl Blacklist {
+    /// The first entry that matches the item, if any
+    pub fn find<T>(&self, item: &T) -> Option<&BlacklistEntry>
+    where
+        T: HasItemCode + HasThingDef + HasStuff,
+    {
+        self.entries.iter().find(|entry| {
+            entry.matches(item.get_item_code(), item.get_thing_def(), item.get_stuff())
+        })
+    }
+
+    pub fn contains<T>(&self, item: &T) -> bool
+    where
+        T: HasItemCode + HasThingDef + HasStuff,
+    {
+        self.find(item).is_some()
+    }
+
+    /// Used stock lines go along with the item they were split from
+    pub fn contains_inventory(&self, item: &Inventory) -> bool {
+        self.contains(item)
+            || item.parent_item_code.as_ref().map_or(false, |parent| {
+                self.entries
+                    .iter()
+                    .any(|entry| entry.matches(parent, &item.thing_def, item.stuff.as_ref()))
+            })
+    }
+}
+
+impl BlacklistEntry {
+    /// Fields that aren't set match anything
+    pub fn matches(&self, item_code: &String, thing_def: &String, stuff: Option<&String>) -> bool {
+        self.item_code
+            .as_ref()
+            .map_or(true, |code| code == item_code)
+            && self
+                .thing_def
+                .as_ref()
+                .map_or(true, |pattern| matches_pattern(pattern, thing_def))
+            && self.stuff.as_ref().map_or(true, |s| Some(s) == stuff)
+    }
+}
+
+/// Match a value against a pattern where `*` stands in for any run of characters,
+/// without one the whole value has to match.
+pub fn matches_pattern(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // There's always at least one part, even for an empty pattern
+    let prefix = parts.next().unwrap();
+    if !value.starts_with(prefix) {
+        return false;
+    }
+    let mut rest = &value[prefix.len()..];
+
+    let parts: Vec<&str> = parts.collect();
+    match parts.split_last() {
+        None => rest.is_empty(),
+        Some((suffix, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(i) => rest = &rest[i + part.len()..],
+                    None => return false,
+                }
+            }
+            rest.ends_with(suffix)
+        }
+    }
+}
+
+/// A new entry as an admin sends it
+#[derive(Deserialize, Debug, Default, PartialEq)]
+pub struct BlacklistEntryRequest {
+    #[serde(default)]
+    pub item_code: Option<String>,
+    #[serde(default)]
+    pub thing_def: Option<String>,
+    #[serde(default)]
+    pub stuff: Option<String>,
+    pub reason: String,
+}
+
+impl BlacklistEntryRequest {
+    /// Blank fields are treated as not set
+    fn normalise(self) -> Self {
+        let not_blank = |v: Option<String>| v.filter(|v| !v.trim().is_empty());
+        BlacklistEntryRequest {
+            item_code: not_blank(self.item_code),
+            thing_def: not_blank(self.thing_def),
+            stuff: not_blank(self.stuff),
+            reason: self.reason.trim().to_string(),
+        }
+    }
+
+    /// An entry without anything to match on would match every item
+    pub fn is_valid(&self) -> bool {
+        (self.item_code.is_some() || self.thing_def.is_some() || self.stuff.is_some())
+            && !self.reason.is_empty()
+    }
+}
+
+/// Parse `item_code=`, `thing_def=` and `stuff=` followed by the reason,
+/// e.g. `thing_def=Exploit_* stuff=Gold duplicates gold`
+impl FromStr for BlacklistEntryRequest {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut request = BlacklistEntryRequest::default();
+        let mut words = s.split_whitespace().peekable();
+        while let Some((key, value)) = words.peek().copied().and_then(|word| word.split_once('=')) {
+            let field = match key {
+                "item_code" => &mut request.item_code,
+                "thing_def" => &mut request.thing_def,
+                "stuff" => &mut request.stuff,
+                _ => return Err(format!("Unknown field {}", key)),
+            };
+            *field = Some(value.to_string());
+            words.next();
+        }
+        request.reason = words.collect::<Vec<&str>>().join(" ");
+
+        let request = request.normalise();
+        if request.is_valid() {
+            Ok(request)
+        } else {
+            Err("An entry needs an item_code, thing_def or stuff and a reason".to_owned())
+        }
+    }
+}
+
+pub fn get_blacklist_entries(conn: &Ppc) -> QueryResult<Vec<BlacklistEntry>> {
+    schema::table.order(schema::entry_id.asc()).load(conn)
+}
+
+/// Returns None if the request doesn't have anything to match on or a reason
+pub fn add_blacklist_entry(
+    request: BlacklistEntryRequest,
+    conn: &Ppc,
+) -> QueryResult<Option<BlacklistEntry>> {
+    let request = request.normalise();
+    if !request.is_valid() {
+        return Ok(None);
+    }
+    let entry = diesel::insert_into(schema::table)
+        .values(NewBlacklistEntry {
+            item_code: request.item_code,
+            thing_def: request.thing_def,
+            stuff: request.stuff,
+            reason: request.reason,
+            date_added: Utc::now().naive_utc(),
+        })
+        .get_result(conn)?;
+    invalidate_blacklist();
+    Ok(Some(entry))
+}
+
+/// Anything it was hiding that's still in the inventory is served again straight away
+pub fn remove_blacklist_entry(entry_id: i32, conn: &Ppc) -> QueryResult<usize> {
+    let count = diesel::delete(schema::table.find(entry_id)).execute(conn)?;
+    invalidate_blacklist();
+    Ok(count)
+}
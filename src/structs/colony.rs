@@ -2,6 +2,7 @@ use chrono::NaiveDateTime;
 use diesel::result::Error::RollbackTransaction;
 use uuid::Uuid;
 
+use crate::cache;
 use crate::crypto::{generate_v4_uuid, parse_uuid};
 use crate::db::get_pg_connection;
 use crate::db::models::bind::ClientBind;
@@ -10,6 +11,8 @@ use crate::db::models::order::Order;
 use crate::packets::colony::ColonyData;
 use crate::packets::order::OrderStatusEnum;
 use crate::structs::general::DbPkLoadable;
+use crate::structs::market::market_for_game_version;
+use crate::structs::order::invalidate_order_inventory;
 use crate::traits::item::Rollback;
 
 impl From<Colony> for ColonyData {
@@ -35,6 +38,7 @@ impl From<ColonyData> for Colony {
     /// Convert from Protobuf message data to ColonyData
     /// client_bind_fk is set to a random UUID since it's never used but must be populated
     fn from(c: ColonyData) -> Self {
+        let market = market_for_game_version(&c.game_version);
         Colony {
             colony_id: Uuid::parse_str(&*c.colony_id).unwrap(),
             name: c.name,
@@ -49,11 +53,32 @@ impl From<ColonyData> for Colony {
             update_date: NaiveDateTime::from_timestamp(0, 0),
             seed: c.seed,
             location: c.location,
+            market,
         }
     }
 }
 
-make_pk_loadable!(Colony, Uuid, crate::db::schema::colonies);
+impl Colony {
+    /// The market the colony trades in, None if we can't read its game version
+    pub fn market(&self) -> Option<String> {
+        self.market.clone()
+    }
+}
+
+impl DbPkLoadable for Colony {
+    type Output = Result<Self, ()>;
+    type PkType = Uuid;
+
+    /// Colonies are loaded on nearly every request, so read them through the cache.
+    fn load_pk(pk: &Self::PkType) -> Self::Output {
+        use crate::db::get_db_object_by_id;
+        use crate::db::schema::colonies as schema;
+        cache::get_or_load(&cache::colony_key(pk), cache::COLONY_TTL, || {
+            let conn = get_pg_connection();
+            get_db_object_by_id(&conn, schema::table, pk).map_err(|_| ())
+        })
+    }
+}
 
 /// Check if the colony requested is equal to the packet and is owned by the current bind
 pub fn validate_ownership_and_fetch(
@@ -114,14 +139,16 @@ impl Anticheat for Colony {
             .filter(schema::colony_id.eq(&self.colony_id))
             .filter(schema::start_tick.ge(&new_tick))
             .filter(schema::status.ne(i32::from(OrderStatusEnum::Reversed)))
-            .filter(schema::status.ne(i32::from(OrderStatusEnum::Failed)));
+            .filter(schema::status.ne(i32::from(OrderStatusEnum::Failed)))
+            .filter(schema::status.ne(i32::from(OrderStatusEnum::Cancelled)))
+            .filter(schema::status.ne(i32::from(OrderStatusEnum::Expired)));
 
-        let orders: Vec<Order> = query.get_results(conn).map_err(|_| ())?;
+        let mut orders: Vec<Order> = query.get_results(conn).map_err(|_| ())?;
 
         conn.build_transaction()
             .read_committed()
             .run::<(), diesel::result::Error, _>(|| {
-                for mut order in orders {
+                for order in orders.iter_mut() {
                     info!(
                         "Rolling back order {} placed @ {}",
                         &order.order_id, &order.start_tick
@@ -132,6 +159,11 @@ impl Anticheat for Colony {
                 }
                 Ok(())
             })
-            .map_err(|_| ())
+            .map_err(|_| ())?;
+
+        for order in orders.iter() {
+            invalidate_order_inventory(order);
+        }
+        Ok(())
     }
 }
@@ -1,4 +1,175 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Deref;
+
+use diesel::dsl::any;
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{BigInt, Text};
+use itertools::Itertools;
+use uuid::Uuid;
+
 use crate::db::models::colony_mod::ColonyMods;
+use crate::db::models::inventory::Inventory;
+use crate::db::schema::colony_mods as schema;
+use crate::db::Ppc;
 use crate::structs::general::DbPkLoadable;
-use uuid::Uuid;
+
 make_pk_loadable!(ColonyMods, Uuid, crate::db::schema::colony_mods);
+
+/// Mods loaded by at least this fraction of colonies are treated as part of the base game,
+/// items are never tagged with them.
+pub const BASE_GAME_MOD_FRACTION: f64 = 0.9;
+
+/// An item needs votes from at least this many colonies with a mod list before it's tagged,
+/// one colony's mod list says nothing about which mod the item came from.
+pub const MIN_TAGGING_COLONIES: usize = 2;
+
+#[derive(QueryableByName, serde::Serialize, Debug, Clone)]
+pub struct ModPopularity {
+    #[sql_type = "Text"]
+    pub mod_name: String,
+    /// Colonies that have the mod loaded
+    #[sql_type = "BigInt"]
+    pub colonies: i64,
+    /// Inventory items tagged with the mod
+    #[sql_type = "BigInt"]
+    pub items: i64,
+}
+
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct ModStatistics {
+    /// Colonies that have sent us a mod list
+    pub colonies: i64,
+    /// Most popular first
+    pub mods: Vec<ModPopularity>,
+}
+
+impl ModStatistics {
+    pub fn popularity_map(&self) -> HashMap<String, i64> {
+        self.mods
+            .iter()
+            .map(|m| (m.mod_name.clone(), m.colonies))
+            .collect()
+    }
+}
+
+pub fn get_mod_statistics(conn: &Ppc) -> QueryResult<ModStatistics> {
+    use diesel::dsl::count_star;
+
+    let colonies: i64 = schema::table.select(count_star()).first(conn)?;
+    let mods: Vec<ModPopularity> = sql_query(
+        r#"SELECT m.mod_name,
+                  count(DISTINCT cm.colony_id) AS colonies,
+                  (SELECT count(*) FROM inventory i WHERE i.mod_name = m.mod_name) AS items
+           FROM colony_mods cm, jsonb_array_elements_text(cm.mods) AS m(mod_name)
+           GROUP BY m.mod_name
+           ORDER BY colonies DESC, m.mod_name ASC"#,
+    )
+    .load(conn.deref())?;
+
+    Ok(ModStatistics { colonies, mods })
+}
+
+/// Work out which mod added an item from the mod lists of the colonies that voted for it.
+/// The mod has to be loaded by every one of them, of those we pick the least popular
+/// since a mod everyone has is more likely to be a library than the source of the item.
+/// Returns None for base game items or if there isn't enough to go on.
+pub fn infer_item_mod(
+    voter_mods: &[Vec<String>],
+    popularity: &HashMap<String, i64>,
+    colonies: i64,
+) -> Option<String> {
+    if voter_mods.len() < MIN_TAGGING_COLONIES || colonies <= 0 {
+        return None;
+    }
+
+    let mut common: HashSet<&String> = voter_mods[0].iter().collect();
+    for mods in voter_mods.iter().skip(1) {
+        let mods: HashSet<&String> = mods.iter().collect();
+        common.retain(|m| mods.contains(m));
+    }
+
+    common
+        .into_iter()
+        .map(|m| (m, popularity.get(m).copied().unwrap_or(0)))
+        .filter(|(_, count)| (*count as f64) < colonies as f64 * BASE_GAME_MOD_FRACTION)
+        .min_by(|(a_name, a_count), (b_name, b_count)| {
+            a_count.cmp(b_count).then_with(|| a_name.cmp(b_name))
+        })
+        .map(|(m, _)| m.clone())
+}
+
+/// The mod lists of the colonies that voted for each version in the market,
+/// colonies that haven't sent a mod list are left out.
+pub fn get_voter_mods(
+    market: &str,
+    versions: &[&String],
+    conn: &Ppc,
+) -> QueryResult<HashMap<String, Vec<Vec<String>>>> {
+    use crate::db::schema::new_inventory_vote_tracker as vote_tracker;
+
+    let votes: Vec<(String, Uuid)> = vote_tracker::table
+        .select((vote_tracker::version, vote_tracker::colony_id))
+        .filter(vote_tracker::market.eq(market))
+        .filter(vote_tracker::version.eq(any(versions)))
+        .distinct()
+        .load(conn)?;
+
+    let colony_ids: Vec<Uuid> = votes.iter().map(|(_, id)| *id).unique().collect();
+    let colony_mods: HashMap<Uuid, Vec<String>> = schema::table
+        .filter(schema::colony_id.eq(any(colony_ids)))
+        .load::<ColonyMods>(conn)?
+        .into_iter()
+        .map(|cm| (cm.colony_id, cm.mods.to_vec()))
+        .collect();
+
+    let mut voter_mods = HashMap::<String, Vec<Vec<String>>>::with_capacity(versions.len());
+    for (version, colony_id) in votes {
+        if let Some(mods) = colony_mods.get(&colony_id) {
+            voter_mods
+                .entry(version)
+                .or_insert_with(Vec::new)
+                .push(mods.clone());
+        }
+    }
+    Ok(voter_mods)
+}
+
+/// None if the colony hasn't sent a mod list
+pub fn get_loaded_mods(colony_id: &Uuid, conn: &Ppc) -> QueryResult<Option<HashSet<String>>> {
+    schema::table
+        .find(*colony_id)
+        .first::<ColonyMods>(conn)
+        .optional()
+        .map(|cm| cm.map(|cm| cm.mods.iter().cloned().collect()))
+}
+
+/// Items that aren't tagged are offered to everyone,
+/// as are all items if we don't know what the colony has loaded.
+pub fn is_mod_loaded(loaded_mods: &Option<HashSet<String>>, item: &Inventory) -> bool {
+    match (loaded_mods, &item.mod_name) {
+        (Some(loaded), Some(mod_name)) => loaded.contains(mod_name),
+        _ => true,
+    }
+}
+
+/// A colony that can trade an item but doesn't have its mod loaded means we guessed wrong,
+/// remove the tag so the item is offered to everyone again.
+/// Only colonies trading in the same market count.
+pub fn clear_contradicted_mod_tags(market: &str, conn: &Ppc) -> QueryResult<usize> {
+    sql_query(
+        r#"UPDATE inventory i SET mod_name = NULL
+           WHERE i.market = $1
+           AND i.mod_name IS NOT NULL
+           AND EXISTS (
+               SELECT 1 FROM colony_tradables ct
+               INNER JOIN colony_mods cm ON cm.colony_id = ct.colony_id
+               INNER JOIN colonies c ON c.colony_id = ct.colony_id
+               WHERE c.market = i.market
+               AND ct.tradables ? coalesce(i.parent_item_code, i.item_code)
+               AND NOT cm.mods ? i.mod_name
+           )"#,
+    )
+    .bind::<Text, _>(market)
+    .execute(conn.deref())
+}
@@ -0,0 +1,227 @@
+use std::collections::HashSet;
+use std::iter::FromIterator;
+use std::ops::Deref;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::{ExpressionMethods, QueryDsl};
+use itertools::Itertools;
+use itsdangerous::{default_builder, Signer};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use strum::IntoEnumIterator;
+use uuid::Uuid;
+
+use crate::cache::inventory::get_colony_tradables;
+use crate::crypto::{generate_secure_token, generate_v4_uuid, parse_uuid, sign_string};
+use crate::db::models::colony::Colony;
+use crate::db::models::contract::Contract;
+use crate::db::models::inventory::Inventory;
+use crate::db::schema::contracts as schema;
+use crate::db::{insert_db_object, Ppc};
+use crate::jtd::api_config::structure::ApiConfigDataContracts;
+use crate::packets::common::CurrencyEnum;
+use crate::packets::contract::{ContractData, ContractStatusEnum};
+use crate::structs::currency::currency_item;
+use crate::structs::general::{DbPkLoadable, ONE_DAY_TICKS};
+use crate::structs::inventory::get_inventory;
+use crate::traits::numerical::CanRound;
+use bigdecimal::{BigDecimal, ToPrimitive};
+
+make_pk_loadable!(Contract, Uuid, crate::db::schema::contracts);
+
+/// Build the Protobuf message for a contract, signing the Contract ID with the
+/// contract's private key so that it can't be forged by the client.
+pub fn make_contract_data(contract: Contract, item: &Inventory) -> ContractData {
+    let signer = default_builder(contract.private_key).build();
+    ContractData {
+        contract_id: sign_string(contract.contract_id.to_string(), &signer),
+        item_code: contract.item_code,
+        thing_def: item.thing_def.clone(),
+        quality: item.quality.unwrap_or(0),
+        stuff: item.stuff.clone().unwrap_or_default(),
+        minified: item.minified,
+        quantity: contract.quantity,
+        reward: contract.reward,
+        currency: contract.currency,
+        deadline_tick: contract.deadline_tick,
+        status: contract.status,
+    }
+}
+
+/// Load a contract from the signed Contract ID that was sent to the colony,
+/// the contract must belong to the colony provided.
+pub fn load_signed_contract(signed_id: &str, colony_id: Uuid) -> Result<Contract, ()> {
+    // The ID is in the clear, the signature follows it.
+    let contract_id = signed_id.split('.').next().ok_or(()).and_then(parse_uuid)?;
+    let contract = Contract::load_pk(&contract_id).map_err(|_| ())?;
+
+    if contract.colony_id != colony_id {
+        return Err(());
+    }
+
+    let signer = default_builder(contract.private_key.clone()).build();
+    match signer.unsign(signed_id) {
+        Ok(value) if value == contract_id.to_string() => Ok(contract),
+        _ => Err(()),
+    }
+}
+
+/// Move the colony forward to the tick the client sent, save it and expire any contracts
+/// that have passed their deadline. Run this inside the caller's transaction, the caller
+/// invalidates the cached colony once it commits.
+pub fn sync_colony_tick(colony: &mut Colony, tick: i32, conn: &Ppc) -> Result<(), ()> {
+    if tick < colony.tick {
+        return Err(());
+    }
+    colony.tick = tick;
+    colony
+        .save_changes::<Colony>(conn.deref())
+        .map_err(|_| ())?;
+    expire_contracts(colony, conn)?;
+    Ok(())
+}
+
+/// Mark any open contracts for the colony that are past their deadline as expired.
+pub fn expire_contracts(colony: &Colony, conn: &Ppc) -> Result<usize, ()> {
+    diesel::update(
+        schema::table
+            .filter(schema::colony_id.eq(colony.colony_id))
+            .filter(schema::status.eq_any(vec![
+                i32::from(ContractStatusEnum::Offered),
+                i32::from(ContractStatusEnum::Accepted),
+            ]))
+            .filter(schema::deadline_tick.lt(colony.tick)),
+    )
+    .set((
+        schema::status.eq(i32::from(ContractStatusEnum::Expired)),
+        schema::update_date.eq(Utc::now().naive_utc()),
+    ))
+    .execute(conn)
+    .map_err(|_| ())
+}
+
+/// Get the contracts for a colony, only open ones unless `any` is set.
+pub fn get_contracts_for_colony(colony_id: Uuid, any: bool, conn: &Ppc) -> Vec<Contract> {
+    let mut query = schema::table
+        .filter(schema::colony_id.eq(colony_id))
+        .order(schema::create_date.desc())
+        .into_boxed();
+
+    if !any {
+        query = query.filter(schema::status.eq_any(vec![
+            i32::from(ContractStatusEnum::Offered),
+            i32::from(ContractStatusEnum::Accepted),
+        ]));
+    }
+
+    query.get_results(conn).unwrap_or_else(|e| {
+        warn!("Failed to load contracts for colony {}, {}", colony_id, e);
+        Vec::new()
+    })
+}
+
+/// How many new offers it takes to bring the colony's open contracts up to `max_offers`,
+/// accepted contracts count as they're still open.
+pub fn offers_wanted(existing: &Vec<Contract>, max_offers: u32) -> usize {
+    let open = existing
+        .iter()
+        .filter(|c| {
+            c.status == i32::from(ContractStatusEnum::Offered)
+                || c.status == i32::from(ContractStatusEnum::Accepted)
+        })
+        .count() as u32;
+    max_offers.saturating_sub(open) as usize
+}
+
+/// How many of an item fit in one contract without going over `max_value`,
+/// None if a single one is worth more than that.
+pub fn offer_quantity(base_value: &BigDecimal, max_value: &BigDecimal) -> Option<i32> {
+    if *base_value <= BigDecimal::from(0) || base_value > max_value {
+        return None;
+    }
+    (max_value / base_value)
+        .to_i32()
+        .filter(|quantity| *quantity > 0)
+}
+
+/// Top up the colony's offers with new contracts for items it is able to trade in its market.
+pub fn generate_offers(
+    colony: &Colony,
+    market: &str,
+    existing: &Vec<Contract>,
+    config: &ApiConfigDataContracts,
+    conn: &Ppc,
+) -> Vec<Contract> {
+    let wanted = offers_wanted(existing, config.max_offers);
+    if wanted == 0 {
+        return Vec::new();
+    }
+
+    let colony_tradables = match get_colony_tradables(&colony.colony_id) {
+        Ok(v) => v,
+        Err(_) => return Vec::new(),
+    };
+
+    // Don't offer the same item twice, or ask for currency.
+    let exclude: HashSet<&String> = HashSet::from_iter(
+        existing
+            .iter()
+            .map(|c| &c.item_code)
+            .chain(CurrencyEnum::iter().map(|c| &currency_item(c).item_code)),
+    );
+
+    let candidates: HashSet<&String> = colony_tradables
+        .tradables
+        .iter()
+        .filter(|code| !exclude.contains(code))
+        .collect();
+
+    // Anything worth more than the cap on its own can't be asked for at all.
+    let max_value = BigDecimal::from(config.max_value);
+    let inventory = get_inventory(candidates, market, conn);
+    let items = inventory
+        .values()
+        .filter_map(|i| Some((i, offer_quantity(&i.base_value, &max_value)?)))
+        .collect_vec();
+
+    let mut rng = rand::thread_rng();
+    let bonus = BigDecimal::from(1f32 + config.reward_bonus_pct);
+    let min_days = config.min_days.min(config.max_days) as i32;
+    let max_days = config.max_days.max(config.min_days) as i32;
+    let now = Utc::now().naive_utc();
+
+    let chosen = items
+        .choose_multiple(&mut rng, wanted)
+        .cloned()
+        .collect_vec();
+
+    chosen
+        .into_iter()
+        .filter_map(|(item, quantity)| {
+            let reward = (&item.base_value * BigDecimal::from(quantity) * &bonus)
+                .round_2dp()
+                .to_i32()
+                .unwrap_or(0);
+            let days = rng.gen_range(min_days..=max_days);
+
+            let contract = Contract {
+                contract_id: generate_v4_uuid(),
+                colony_id: colony.colony_id,
+                item_code: item.item_code.clone(),
+                quantity,
+                reward,
+                currency: CurrencyEnum::Utc.into(),
+                status: ContractStatusEnum::Offered.into(),
+                start_tick: colony.tick,
+                deadline_tick: colony.tick + (days * ONE_DAY_TICKS),
+                private_key: generate_secure_token(32),
+                create_date: now,
+                update_date: now,
+                market: market.to_string(),
+            };
+
+            insert_db_object(conn, contract, schema::table).ok()
+        })
+        .collect_vec()
+}
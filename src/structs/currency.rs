@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use bigdecimal::{BigDecimal, ToPrimitive};
+use diesel::prelude::*;
+use strum::IntoEnumIterator;
+
+use crate::cache::inventory::invalidate_inventory;
+use crate::db::models::inventory::Inventory;
+use crate::db::Ppc;
+use crate::jtd::api_config::structure::ApiConfigDataCurrency;
+use crate::packets::common::CurrencyEnum;
+use crate::structs::inventory::{GOLD_ITEM, SILVER_ITEM};
+use crate::traits::numerical::CanRound;
+
+/// The name used for the currency in the exchange rates config
+pub fn currency_key(currency: CurrencyEnum) -> &'static str {
+    match currency {
+        CurrencyEnum::Utc => "utc",
+        CurrencyEnum::Gold => "gold",
+    }
+}
+
+/// The item given to the colony when they withdraw the currency
+pub fn currency_item(currency: CurrencyEnum) -> &'static Inventory {
+    match currency {
+        CurrencyEnum::Utc => &SILVER_ITEM,
+        CurrencyEnum::Gold => &GOLD_ITEM,
+    }
+}
+
+/// Every market stocks its own copy of the currency items
+pub fn currency_item_for_market(currency: CurrencyEnum, market: &str) -> Inventory {
+    Inventory {
+        market: market.to_string(),
+        ..currency_item(currency).clone()
+    }
+}
+
+/// Maintenance only stocks the currency items in markets that have stock or votes,
+/// colonies in a new market can withdraw before then. Existing rows are left alone.
+pub fn stock_currency_item(currency: CurrencyEnum, market: &str, conn: &Ppc) -> QueryResult<()> {
+    use crate::db::schema::inventory as schema;
+
+    let item = currency_item_for_market(currency, market);
+    let inserted = diesel::insert_into(schema::table)
+        .values(&item)
+        .on_conflict((schema::market, schema::item_code))
+        .do_nothing()
+        .execute(conn)?;
+    if inserted > 0 {
+        invalidate_inventory(market, &[item.item_code]);
+    }
+    Ok(())
+}
+
+/// Currency items can't be traded on the market, only withdrawn from the bank.
+pub fn is_currency_item(item_code: &str) -> bool {
+    CurrencyEnum::iter().any(|c| currency_item(c).item_code == item_code)
+}
+
+/// How many UTC one unit of the currency is worth, UTC is always 1.
+/// Returns None if the currency doesn't have a usable rate.
+pub fn get_exchange_rate(
+    config: &ApiConfigDataCurrency,
+    currency: CurrencyEnum,
+) -> Option<BigDecimal> {
+    if currency == CurrencyEnum::Utc {
+        return Some(BigDecimal::from(1));
+    }
+    config
+        .rates
+        .get(currency_key(currency))
+        .filter(|rate| rate.is_finite() && **rate > 0f32)
+        .map(|rate| BigDecimal::from(*rate))
+}
+
+/// Every currency that can currently be used, for sending to the client
+pub fn get_exchange_rates(config: &ApiConfigDataCurrency) -> HashMap<i32, f32> {
+    CurrencyEnum::iter()
+        .filter_map(|c| {
+            get_exchange_rate(config, c)
+                .and_then(|rate| rate.to_f32())
+                .map(|rate| (i32::from(c), rate))
+        })
+        .collect()
+}
+
+/// Convert an amount in UTC into the currency with the rate given
+pub fn from_utc(amount: &BigDecimal, rate: &BigDecimal) -> BigDecimal {
+    (amount / rate).round_2dp()
+}
@@ -4,13 +4,15 @@ use std::ops::Deref;
 use diesel::dsl::any;
 use diesel::prelude::*;
 
+use crate::crypto::hash_short_identity_string;
 use crate::db::models::inventory::Inventory;
 use crate::db::Ppc;
+use crate::jtd::stock_config::StockConfigCondition;
 use crate::packets::order::OrderItem;
-use crate::packets::tradable::ColonyTradable;
+use crate::packets::tradable::{ColonyTradable, ItemConditionEnum};
 use crate::structs::api_config::API_CONFIG_ARC;
 use crate::structs::order::OrderStats;
-use crate::traits::item::ItemCodeComputable;
+use crate::traits::item::{make_version_string, ItemCodeComputable};
 
 impl From<ColonyTradable> for Inventory {
     fn from(ct: ColonyTradable) -> Self {
@@ -34,6 +36,10 @@ impl From<ColonyTradable> for Inventory {
             },
             weight: BigDecimal::from(ct.weight),
             version: ct.get_version_code(),
+            condition: ItemConditionEnum::New.into(),
+            parent_item_code: None,
+            mod_name: None,
+            market: String::new(),
         }
     }
 }
@@ -51,21 +57,55 @@ pub fn create_silver_inventory_item() -> Inventory {
         stuff: None,
         weight: BigDecimal::from(0.008),
         version: "".to_string(),
+        condition: ItemConditionEnum::New.into(),
+        parent_item_code: None,
+        mod_name: None,
+        // Stocked in every market, see `currency_item_for_market`
+        market: "".to_string(),
     };
     silver.populate_identity_values();
     silver
 }
 
+pub fn create_gold_inventory_item() -> Inventory {
+    let mut gold = Inventory {
+        item_code: "".to_string(),
+        thing_def: "Gold".to_string(),
+        quality: None,
+        quantity: 0,
+        minified: false,
+        base_value: BigDecimal::from(10),
+        buy_at: BigDecimal::from(10),
+        sell_at: BigDecimal::from(10),
+        stuff: None,
+        weight: BigDecimal::from(0.008),
+        version: "".to_string(),
+        condition: ItemConditionEnum::New.into(),
+        parent_item_code: None,
+        mod_name: None,
+        // Stocked in every market, see `currency_item_for_market`
+        market: "".to_string(),
+    };
+    gold.populate_identity_values();
+    gold
+}
+
 use crate::traits::numerical::CanRound;
-use bigdecimal::BigDecimal;
+use bigdecimal::{BigDecimal, ToPrimitive};
 use lazy_static::lazy_static;
 lazy_static! {
     pub static ref SILVER_ITEM: Inventory = create_silver_inventory_item();
+    pub static ref GOLD_ITEM: Inventory = create_gold_inventory_item();
 }
 
-pub fn get_inventory(mut item_codes: HashSet<&String>, conn: &Ppc) -> HashMap<String, Inventory> {
+pub fn get_inventory(
+    mut item_codes: HashSet<&String>,
+    market: &str,
+    conn: &Ppc,
+) -> HashMap<String, Inventory> {
     use crate::db::schema::inventory as schema;
     let mut results: Vec<Inventory> = schema::table
+        .filter(schema::market.eq(market))
         .filter(schema::item_code.eq(any(item_codes.drain().collect::<Vec<&String>>())))
         .get_results(conn)
         .expect("Failed colony<->inventory load query");
@@ -76,6 +116,104 @@ pub fn get_inventory(mut item_codes: HashSet<&String>, conn: &Ppc) -> HashMap<St
     hash
 }
 
+/// Same as `get_inventory` but the rows are locked until the transaction ends,
+/// so nobody else can take the stock while we're deciding what to do with it.
+pub fn get_inventory_for_update(
+    item_codes: HashSet<&String>,
+    market: &str,
+    conn: &Ppc,
+) -> QueryResult<HashMap<String, Inventory>> {
+    use crate::db::schema::inventory as schema;
+    let results: Vec<Inventory> = schema::table
+        .filter(schema::market.eq(market))
+        .filter(schema::item_code.eq(any(item_codes.into_iter().collect::<Vec<&String>>())))
+        .for_update()
+        .get_results(conn)?;
+    Ok(results
+        .into_iter()
+        .map(|item| (item.item_code.clone(), item))
+        .collect())
+}
+
+/// Health is sent by the client, it has to be a percentage
+/// and something with none left isn't worth anything.
+pub fn is_valid_health(health: f32) -> bool {
+    health.is_finite() && health > 0f32 && health <= 100f32
+}
+
+/// The used stock line for an item always has the same code so it can be found again.
+pub fn used_item_code(item_code: &String) -> String {
+    hash_short_identity_string(format!("{}used", item_code))
+}
+
+fn make_used_line(parent: &Inventory, value_pct: f32) -> Inventory {
+    let item_code = used_item_code(&parent.item_code);
+    let base_value = (&parent.base_value * BigDecimal::from(value_pct)).round_2dp();
+    Inventory {
+        version: make_version_string(&item_code, base_value.to_f32().unwrap_or(0f32)),
+        item_code,
+        thing_def: parent.thing_def.clone(),
+        quality: parent.quality,
+        quantity: 0,
+        minified: parent.minified,
+        buy_at: base_value.clone(),
+        sell_at: base_value.clone(),
+        base_value,
+        stuff: parent.stuff.clone(),
+        weight: parent.weight.clone(),
+        condition: ItemConditionEnum::Used.into(),
+        parent_item_code: Some(parent.item_code.clone()),
+        mod_name: parent.mod_name.clone(),
+        market: parent.market.clone(),
+    }
+}
+
+/// Items sold to us below the health threshold go into the used stock line for that item
+/// instead of being restocked as new, the line is created the first time it's needed.
+/// The item codes are rewritten so the order, stats and any rollback all point at the used line.
+/// Returns true if any lines were created, once the transaction commits the caller
+/// has to bump the inventory generation so cached inventory lists pick them up.
+pub fn route_used_items(
+    wts: &mut Vec<OrderItem>,
+    market: &str,
+    config: &StockConfigCondition,
+    conn: &Ppc,
+) -> Result<bool, ()> {
+    use crate::db::schema::inventory as schema;
+
+    let worn: HashSet<&String> = wts
+        .iter()
+        .filter(|item| item.health < config.used_health_threshold)
+        .map(|item| &item.item_code)
+        .collect();
+    if worn.is_empty() {
+        return Ok(false);
+    }
+
+    // Anything that's already a used line stays where it is
+    let parents: HashMap<String, Inventory> = get_inventory(worn, market, conn)
+        .into_iter()
+        .filter(|(_, item)| item.condition == i32::from(ItemConditionEnum::New))
+        .collect();
+
+    let used_lines: Vec<Inventory> = parents
+        .values()
+        .map(|parent| make_used_line(parent, config.used_value_pct))
+        .collect();
+    let created = diesel::insert_into(schema::table)
+        .values(&used_lines)
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .map_err(|_| ())?;
+
+    for item in wts.iter_mut() {
+        if item.health < config.used_health_threshold && parents.contains_key(&item.item_code) {
+            item.item_code = used_item_code(&item.item_code);
+        }
+    }
+    Ok(created > 0)
+}
+
 pub fn update_stock(
     wts: &Vec<OrderItem>,
     wtb: &Vec<OrderItem>,
@@ -89,7 +227,13 @@ pub fn update_stock(
         let quantity = BigDecimal::from(item.quantity);
         val.quantity += item.quantity;
         os.total_sell_weight += &val.weight * &quantity;
-        os.total_sell_cost += ((&val.buy_at / 100f32) * BigDecimal::from(item.health)) * &quantity;
+        let price = if val.condition == i32::from(ItemConditionEnum::Used) {
+            // The used line is already priced for its wear, don't discount it again
+            val.buy_at.clone()
+        } else {
+            (&val.buy_at / 100f32) * BigDecimal::from(item.health)
+        };
+        os.total_sell_cost += price * &quantity;
     }
     for item in wtb {
         let stock = db_inventory.get_mut(&item.item_code).unwrap();
@@ -118,6 +262,7 @@ pub fn update_stock(
     // Add delivery/collection fees whilst rounding up/down to nearest integer
     os.total_sell_weight = os.total_sell_weight.round_2dp();
     os.total_buy_weight = os.total_buy_weight.round_2dp();
+    os.total_buy_item_cost = Some(os.total_buy_cost.round_2dp());
     os.total_buy_cost +=
         &os.total_buy_weight * BigDecimal::from(config.config_data.delivery.delivery_cost_per_kg);
     os.total_buy_cost +=
@@ -1,7 +1,9 @@
 use crate::db::models::inventory_staging::ColonyInventoryStaging;
 use crate::packets::tradable::ColonyTradable;
 use crate::traits::from::FromWithColonyUuid;
-use crate::traits::item::{make_version_string, HasItemCode, HasThingDef, ItemCodeComputable};
+use crate::traits::item::{
+    make_version_string, HasItemCode, HasStuff, HasThingDef, ItemCodeComputable,
+};
 use bigdecimal::BigDecimal;
 use uuid::Uuid;
 
@@ -42,6 +44,12 @@ impl HasItemCode for ColonyInventoryStaging {
     }
 }
 
+impl HasStuff for ColonyInventoryStaging {
+    fn get_stuff(&self) -> Option<&String> {
+        self.stuff.as_ref()
+    }
+}
+
 impl<'a> HasThingDef for &'a ColonyInventoryStaging {
     fn get_thing_def(&self) -> &String {
         &self.thing_def
@@ -53,3 +61,9 @@ impl<'a> HasItemCode for &'a ColonyInventoryStaging {
         &self.item_code
     }
 }
+
+impl<'a> HasStuff for &'a ColonyInventoryStaging {
+    fn get_stuff(&self) -> Option<&String> {
+        self.stuff.as_ref()
+    }
+}
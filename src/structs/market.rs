@@ -0,0 +1,43 @@
+use std::collections::BTreeSet;
+
+use diesel::prelude::*;
+
+use crate::db::Ppc;
+use crate::jtd::api_config::structure::ApiConfigDataMarkets;
+
+/// Item definitions and base values change between major versions of the game,
+/// so each one trades in a market of its own.
+/// "1.3.3200 rev726" trades in "1.3", returns None if the version can't be read.
+pub fn market_for_game_version(game_version: &str) -> Option<String> {
+    let mut parts = game_version
+        .trim()
+        .split(|c: char| c == '.' || c.is_whitespace());
+    let major = parts.next()?.parse::<u32>().ok()?;
+    let minor = parts.next()?.parse::<u32>().ok()?;
+    Some(format!("{}.{}", major, minor))
+}
+
+/// An empty list means every market is open
+pub fn is_market_live(config: &ApiConfigDataMarkets, market: &str) -> bool {
+    config.live.is_empty() || config.live.iter().any(|live| live == market)
+}
+
+/// Every market that has stock or votes, maintenance runs a pass for each of them
+pub fn get_markets(conn: &Ppc) -> QueryResult<Vec<String>> {
+    use crate::db::schema::inventory as inventory_schema;
+    use crate::db::schema::new_inventory as new_inventory_schema;
+
+    let mut markets: BTreeSet<String> = inventory_schema::table
+        .select(inventory_schema::market)
+        .distinct()
+        .load::<String>(conn)?
+        .into_iter()
+        .collect();
+    markets.extend(
+        new_inventory_schema::table
+            .select(new_inventory_schema::market)
+            .distinct()
+            .load::<String>(conn)?,
+    );
+    Ok(markets.into_iter().collect())
+}
@@ -2,21 +2,31 @@
 pub mod general;
 
 pub mod account;
+pub mod admin;
 pub mod api_config;
+pub mod backorder;
 pub mod bank_balance;
+pub mod bank_ledger;
+pub mod bank_transfer;
 pub mod binds;
+pub mod blacklist;
 pub mod colony;
 pub mod colony_mods;
 pub mod colony_tradable;
+pub mod contract;
+pub mod currency;
 pub mod hello;
 pub mod inventory;
 pub mod inventory_promise;
 pub mod inventory_staging;
+pub mod market;
 pub mod new_inventory;
 pub mod new_inventory_vote;
 pub mod order;
 pub mod order_item;
+pub mod pending_inventory;
 pub mod player;
 pub mod price_tracker;
+pub mod threshold_miss;
 pub mod tradable;
 pub mod trade_stats;
@@ -4,8 +4,9 @@ use crate::traits::from::FromWithTime;
 use crate::traits::item::{HasItemCode, HasThingDef};
 use chrono::NaiveDateTime;
 
-impl FromWithTime<ColonyInventoryStaging> for NewInventory {
-    fn from_wt(ct: ColonyInventoryStaging, date: NaiveDateTime) -> Self {
+/// Staged items are voted into the market of the colony that sent them
+impl FromWithTime<(ColonyInventoryStaging, String)> for NewInventory {
+    fn from_wt((ct, market): (ColonyInventoryStaging, String), date: NaiveDateTime) -> Self {
         NewInventory {
             version: ct.version,
             item_code: ct.item_code,
@@ -16,6 +17,7 @@ impl FromWithTime<ColonyInventoryStaging> for NewInventory {
             stuff: ct.stuff,
             weight: ct.weight,
             date_added: date,
+            market,
         }
     }
 }
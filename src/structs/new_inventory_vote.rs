@@ -14,6 +14,7 @@ pub struct InventoryVote {
     pub weight: BigDecimal,
     pub version: String,
     pub votes: i64,
+    pub market: String,
 }
 
 impl From<InventoryVote> for InventoryNoQuantity {
@@ -29,6 +30,8 @@ impl From<InventoryVote> for InventoryNoQuantity {
             stuff: iv.stuff,
             weight: iv.weight,
             version: iv.version,
+            mod_name: None,
+            market: iv.market,
         }
     }
 }
@@ -46,6 +49,8 @@ impl From<TempInventoryVote> for InventoryNoQuantity {
             stuff: iv.stuff,
             weight: iv.weight,
             version: iv.version,
+            mod_name: None,
+            market: iv.market,
         }
     }
 }
@@ -59,6 +64,7 @@ impl From<InventoryNoQuantity> for InventoryOnlyStats {
             base_value: iv.base_value,
             weight: iv.weight,
             version: iv.version,
+            market: iv.market,
         }
     }
 }
@@ -10,16 +10,21 @@ use diesel::sql_types::Jsonb;
 use itertools::Itertools;
 use uuid::Uuid;
 
+use crate::cache::inventory::invalidate_inventory;
 use crate::db::models::colony::Colony;
 use crate::db::models::inventory::Inventory;
 use crate::db::models::order::{Order, OrderNoManifest};
 use crate::db::{get_pg_connection, Ppc};
+use crate::jtd::api_config::structure::ApiConfigDataOrdersStaleOrderPolicy;
 
+use crate::packets::bank::BankLedgerKindEnum;
 use crate::packets::common::CurrencyEnum;
 use crate::packets::order::{
-    DeliveryItem, OrderItem, OrderManifestReply, OrderStatusEnum, OrderStatusReply,
+    DeliveryItem, OrderFailureReasonEnum, OrderItem, OrderManifestReply, OrderStatusEnum,
+    OrderStatusReply,
 };
 
+use crate::structs::currency::from_utc;
 use crate::structs::general::DbPkLoadable;
 use crate::structs::inventory::get_inventory;
 use bigdecimal::BigDecimal;
@@ -35,6 +40,10 @@ pub struct OrderStats {
     pub total_sell_cost: BigDecimal,
     /// Total cash taken from the colony
     pub total_buy_cost: BigDecimal,
+    /// What the items sent to the colony cost, without the delivery and collection fees.
+    /// Missing on orders placed before it was recorded.
+    #[serde(default)]
+    pub total_buy_item_cost: Option<BigDecimal>,
 }
 
 #[derive(FromSqlRow, AsExpression, serde::Serialize, serde::Deserialize, Debug, Default)]
@@ -48,6 +57,30 @@ pub struct OrderManifest {
     pub currency: CurrencyEnum,
 }
 
+impl OrderStats {
+    /// Stock is priced in UTC, convert the costs to the currency the order is paid in.
+    pub fn in_currency(&self, rate: &BigDecimal) -> OrderStats {
+        OrderStats {
+            total_sell_weight: self.total_sell_weight.clone(),
+            total_buy_weight: self.total_buy_weight.clone(),
+            total_sell_cost: from_utc(&self.total_sell_cost, rate),
+            total_buy_cost: from_utc(&self.total_buy_cost, rate),
+            total_buy_item_cost: self
+                .total_buy_item_cost
+                .as_ref()
+                .map(|cost| from_utc(cost, rate)),
+        }
+    }
+
+    /// Fees and refunds are a share of what the items cost, not the shipping.
+    /// Older orders only have the total, fees included.
+    pub fn buy_item_cost(&self) -> &BigDecimal {
+        self.total_buy_item_cost
+            .as_ref()
+            .unwrap_or(&self.total_buy_cost)
+    }
+}
+
 impl_to_sql!(for OrderStats, OrderManifest);
 impl_from_sql!(for OrderStats, OrderManifest);
 
@@ -108,7 +141,7 @@ impl From<Order> for OrderManifestReply {
             HashSet::from_iter(o.manifest.wtb.iter().map(|item| &item.item_code));
 
         // Get inventory from DB
-        let inv = get_inventory(items, &get_pg_connection());
+        let inv = get_inventory(items, &o.market, &get_pg_connection());
 
         // Build Delivery item list using inventory data and fill in manifest quantities.
         let delivery_items: Vec<DeliveryItem> = o
@@ -129,14 +162,15 @@ impl From<Order> for OrderManifestReply {
 }
 
 pub fn create_order(
+    order_id: Uuid,
     order_stats: &OrderStats,
     colony: &Colony,
+    market: &str,
     manifest: OrderManifest,
     tick: i32,
     delivery_tick: Option<i32>,
     conn: &Ppc,
 ) -> Result<Order, ()> {
-    use crate::crypto::generate_v4_uuid;
     use crate::db::insert_db_object;
     use crate::db::schema::orders as schema;
     use crate::structs::general::ONE_DAY_TICKS;
@@ -154,7 +188,7 @@ pub fn create_order(
     };
 
     let order = Order {
-        order_id: generate_v4_uuid(),
+        order_id,
         colony_id: colony.colony_id.clone(),
         manifest,
         status,
@@ -163,6 +197,8 @@ pub fn create_order(
         order_stats: order_stats.clone(),
         create_date: now,
         update_date: now,
+        failure_reason: None,
+        market: market.to_string(),
     };
     if let Ok(order) = insert_db_object(&conn, order, schema::table) {
         Ok(order)
@@ -170,3 +206,146 @@ pub fn create_order(
         Err(())
     }
 }
+
+/// Drop the cached stock of every item on the order, call it once the transaction
+/// that changed the stock has committed or another request could cache the old rows.
+pub fn invalidate_order_inventory(order: &Order) {
+    invalidate_inventory(
+        &order.market,
+        order
+            .manifest
+            .wts
+            .iter()
+            .chain(order.manifest.wtb.iter())
+            .map(|item| &item.item_code),
+    );
+}
+
+/// Cancel an order that hasn't been dispatched yet, the stock and bank changes for
+/// both sides of the order are rolled back and a restocking fee is taken from the
+/// cost of the items bought.
+/// The order is locked and checked again here, so it must be called inside a transaction.
+/// Returns the cancelled order and the fee taken.
+pub fn cancel_order(
+    order_id: Uuid,
+    restocking_fee_pct: f32,
+    conn: &Ppc,
+) -> Result<(Order, i32), ()> {
+    use crate::db::schema::orders as schema;
+    use crate::structs::bank_balance::get_bank_balance;
+    use crate::structs::bank_ledger::apply_ledger_entry;
+    use crate::traits::item::Rollback;
+    use bigdecimal::ToPrimitive;
+    use diesel::prelude::*;
+
+    let mut order: Order = schema::table
+        .find(order_id)
+        .for_update()
+        .get_result(conn)
+        .map_err(|_| ())?;
+
+    if order.status != i32::from(OrderStatusEnum::Placed) {
+        return Err(());
+    }
+
+    order.rollback(conn)?;
+    order.status = OrderStatusEnum::Cancelled.into();
+    order.update_date = chrono::Utc::now().naive_utc();
+    order.save_changes::<Order>(&**conn).map_err(|_| ())?;
+
+    let fee_pct = BigDecimal::from(restocking_fee_pct.max(0f32).min(1f32));
+    let fee = (order.order_stats.buy_item_cost() * fee_pct)
+        .with_scale(0)
+        .to_i32()
+        .unwrap_or(0);
+    if fee > 0 {
+        let mut bank_balance =
+            get_bank_balance(order.colony_id, order.manifest.currency.into(), conn)?;
+        apply_ledger_entry(
+            &mut bank_balance,
+            fee * -1,
+            BankLedgerKindEnum::RestockingFee,
+            Some(order.order_id),
+            conn,
+        )?;
+    }
+
+    Ok((order, fee))
+}
+
+/// Mark an order that's out for delivery as failed, the items are returned to stock
+/// and `insurance_pct` of what the colony paid for them is refunded.
+/// The order is locked and checked again here, so it must be called inside a transaction.
+/// Returns the failed order and the amount refunded.
+pub fn fail_order(
+    order_id: Uuid,
+    reason: OrderFailureReasonEnum,
+    insurance_pct: f32,
+    conn: &Ppc,
+) -> Result<(Order, i32), ()> {
+    use crate::db::schema::orders as schema;
+    use crate::traits::item::Rollback;
+    use diesel::prelude::*;
+
+    let mut order: Order = schema::table
+        .find(order_id)
+        .for_update()
+        .get_result(conn)
+        .map_err(|_| ())?;
+
+    if order.status != i32::from(OrderStatusEnum::OutForDelivery) {
+        return Err(());
+    }
+
+    let refund = order.rollback_purchases(insurance_pct, BankLedgerKindEnum::Insurance, conn)?;
+    order.status = OrderStatusEnum::Failed.into();
+    order.failure_reason = Some(reason.into());
+    order.update_date = chrono::Utc::now().naive_utc();
+    order.save_changes::<Order>(&**conn).map_err(|_| ())?;
+
+    Ok((order, refund))
+}
+
+/// Close an order the colony has stopped updating, the items are returned to stock.
+/// Expired orders are refunded in full, failed ones only get what the insurance covers.
+/// The order is locked and checked again here, so it must be called inside a transaction.
+/// Returns the closed order and the amount refunded.
+pub fn close_stale_order(
+    order_id: Uuid,
+    policy: ApiConfigDataOrdersStaleOrderPolicy,
+    insurance_pct: f32,
+    conn: &Ppc,
+) -> Result<(Order, i32), ()> {
+    use crate::db::schema::orders as schema;
+    use crate::traits::item::Rollback;
+    use diesel::prelude::*;
+
+    let mut order: Order = schema::table
+        .find(order_id)
+        .for_update()
+        .get_result(conn)
+        .map_err(|_| ())?;
+
+    // The colony may have caught up since we found it
+    if order.status != i32::from(OrderStatusEnum::Placed)
+        && order.status != i32::from(OrderStatusEnum::OutForDelivery)
+    {
+        return Err(());
+    }
+
+    let refund = match policy {
+        ApiConfigDataOrdersStaleOrderPolicy::Expire => {
+            order.status = OrderStatusEnum::Expired.into();
+            order.rollback_purchases(1f32, BankLedgerKindEnum::Refund, conn)?
+        }
+        ApiConfigDataOrdersStaleOrderPolicy::Fail => {
+            order.status = OrderStatusEnum::Failed.into();
+            order.failure_reason = Some(OrderFailureReasonEnum::Abandoned.into());
+            order.rollback_purchases(insurance_pct, BankLedgerKindEnum::Insurance, conn)?
+        }
+    };
+    order.update_date = chrono::Utc::now().naive_utc();
+    order.save_changes::<Order>(&**conn).map_err(|_| ())?;
+
+    Ok((order, refund))
+}
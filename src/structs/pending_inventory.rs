@@ -0,0 +1,191 @@
+use bigdecimal::BigDecimal;
+use chrono::Utc;
+use diesel::pg::upsert::excluded;
+use diesel::prelude::*;
+
+use crate::cache::inventory::invalidate_inventory;
+use crate::db::models::inventory::InventoryNoQuantity;
+use crate::db::models::pending_inventory::PendingInventory;
+use crate::db::schema::pending_inventory as schema;
+use crate::db::Ppc;
+use crate::packets::inventory::ModerationStatusEnum;
+use crate::traits::numerical::CanRound;
+
+impl PendingInventory {
+    pub fn from_vote(item: InventoryNoQuantity, votes: i32) -> Self {
+        PendingInventory {
+            version: item.version,
+            item_code: item.item_code,
+            thing_def: item.thing_def,
+            quality: item.quality,
+            minified: item.minified,
+            base_value: item.base_value,
+            stuff: item.stuff,
+            weight: item.weight,
+            votes,
+            status: ModerationStatusEnum::Pending.into(),
+            date_added: Utc::now().naive_utc(),
+            review_date: None,
+            reviewed_by: None,
+            mod_name: item.mod_name,
+            market: item.market,
+        }
+    }
+}
+
+/// Prices start at the base value, same as items that are voted in directly
+impl From<PendingInventory> for InventoryNoQuantity {
+    fn from(pi: PendingInventory) -> Self {
+        InventoryNoQuantity {
+            item_code: pi.item_code,
+            thing_def: pi.thing_def,
+            quality: pi.quality,
+            minified: pi.minified,
+            base_value: pi.base_value.clone(),
+            buy_at: pi.base_value.clone(),
+            sell_at: pi.base_value,
+            stuff: pi.stuff,
+            weight: pi.weight,
+            version: pi.version,
+            mod_name: pi.mod_name,
+            market: pi.market,
+        }
+    }
+}
+
+/// Versions that were already reviewed are ignored, so a rejected item doesn't come back.
+pub fn queue_pending_inventory(items: &[PendingInventory], conn: &Ppc) -> QueryResult<usize> {
+    if items.is_empty() {
+        return Ok(0);
+    }
+    diesel::insert_into(schema::table)
+        .values(items)
+        .on_conflict((schema::market, schema::version))
+        .do_nothing()
+        .execute(conn)
+}
+
+/// Grouped by market, most voted first
+pub fn get_pending_inventory(
+    status: ModerationStatusEnum,
+    conn: &Ppc,
+) -> QueryResult<Vec<PendingInventory>> {
+    schema::table
+        .filter(schema::status.eq(status as i32))
+        .order((
+            schema::market.asc(),
+            schema::votes.desc(),
+            schema::date_added.asc(),
+        ))
+        .load(conn)
+}
+
+/// Correct the base value before approving an item, returns None if it isn't pending
+pub fn set_pending_base_value(
+    market: &String,
+    version: &String,
+    base_value: BigDecimal,
+    conn: &Ppc,
+) -> QueryResult<Option<PendingInventory>> {
+    diesel::update(
+        schema::table
+            .filter(schema::market.eq(market))
+            .filter(schema::version.eq(version))
+            .filter(schema::status.eq(ModerationStatusEnum::Pending as i32)),
+    )
+    .set(schema::base_value.eq(base_value.round_2dp()))
+    .get_result(conn)
+    .optional()
+}
+
+fn review_pending_inventory(
+    market: &String,
+    version: &String,
+    status: ModerationStatusEnum,
+    reviewed_by: &str,
+    conn: &Ppc,
+) -> QueryResult<Option<PendingInventory>> {
+    diesel::update(
+        schema::table
+            .filter(schema::market.eq(market))
+            .filter(schema::version.eq(version))
+            .filter(schema::status.eq(ModerationStatusEnum::Pending as i32)),
+    )
+    .set((
+        schema::status.eq(status as i32),
+        schema::review_date.eq(Utc::now().naive_utc()),
+        schema::reviewed_by.eq(reviewed_by),
+    ))
+    .get_result(conn)
+    .optional()
+}
+
+/// Add the item to the market's inventory, any other version of it still waiting there
+/// is dropped. Returns None if it isn't pending.
+pub fn approve_pending_inventory(
+    market: &String,
+    version: &String,
+    reviewed_by: &str,
+    conn: &Ppc,
+) -> QueryResult<Option<PendingInventory>> {
+    use crate::db::schema::inventory as inventory_schema;
+
+    let approved = conn
+        .build_transaction()
+        .read_committed()
+        .run::<_, diesel::result::Error, _>(|| {
+            let pending = match review_pending_inventory(
+                market,
+                version,
+                ModerationStatusEnum::Approved,
+                reviewed_by,
+                conn,
+            )? {
+                Some(p) => p,
+                None => return Ok(None),
+            };
+
+            diesel::insert_into(inventory_schema::table)
+                .values(InventoryNoQuantity::from(pending.clone()))
+                .on_conflict((inventory_schema::market, inventory_schema::item_code))
+                .do_update()
+                .set((
+                    inventory_schema::version.eq(excluded(inventory_schema::version)),
+                    inventory_schema::minified.eq(excluded(inventory_schema::minified)),
+                    inventory_schema::base_value.eq(excluded(inventory_schema::base_value)),
+                    inventory_schema::weight.eq(excluded(inventory_schema::weight)),
+                ))
+                .execute(conn)?;
+
+            diesel::delete(
+                schema::table
+                    .filter(schema::market.eq(&pending.market))
+                    .filter(schema::item_code.eq(&pending.item_code))
+                    .filter(schema::status.eq(ModerationStatusEnum::Pending as i32)),
+            )
+            .execute(conn)?;
+
+            Ok(Some(pending))
+        })?;
+
+    if let Some(pending) = approved.as_ref() {
+        invalidate_inventory(&pending.market, &[pending.item_code.clone()]);
+    }
+    Ok(approved)
+}
+
+/// Returns None if it isn't pending
+pub fn reject_pending_inventory(
+    market: &String,
+    version: &String,
+    reviewed_by: &str,
+    conn: &Ppc,
+) -> QueryResult<Option<PendingInventory>> {
+    review_pending_inventory(
+        market,
+        version,
+        ModerationStatusEnum::Rejected,
+        reviewed_by,
+        conn,
+    )
+}
@@ -1,23 +1,104 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
+use crate::db::models::account::Account;
+
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct NewPlayerRequest {
-    pub(crate) player_link_type: u8,
-    pub(crate) player_link_id: Option<String>,
-    pub(crate) player_name: String,
+    pub(crate) username: String,
+    pub(crate) password: String,
+    pub(crate) e_mail: Option<String>,
 }
+
 #[derive(Debug, PartialEq, Serialize)]
 pub struct NewPlayerResponse {
-    pub(crate) player_id: String,
+    pub(crate) player_id: i32,
 }
 
 #[derive(Deserialize)]
 pub struct AuthRequest {
-    pub(crate) player_id: String,
+    pub(crate) username: String,
+    pub(crate) password: String,
+    /// Required if the account has MFA enabled
+    pub(crate) mfa_code: Option<String>,
+}
+
+/// Result of a login attempt, anything but `Ok` has an empty token
+#[derive(Serialize, Debug, PartialEq, Copy, Clone)]
+#[repr(u8)]
+pub enum AuthResponseCode {
+    Ok = 0,
+    InvalidCredentials = 1,
+    MfaRequired = 2,
+    MfaInvalid = 3,
+    /// Too many failed attempts, try again later
+    Locked = 4,
 }
 
 #[derive(Serialize)]
 pub struct AuthResponse {
+    pub(crate) player_id: i32,
     pub(crate) token_id: String,
+    pub(crate) token_expires: i64,
     pub(crate) auth_response_code: u8,
 }
+
+#[derive(Serialize)]
+pub struct PlayerProfile {
+    pub(crate) player_id: i32,
+    pub(crate) username: Option<String>,
+    pub(crate) e_mail: Option<String>,
+    pub(crate) steam_id: Option<String>,
+    pub(crate) mfa_enabled: bool,
+    pub(crate) date_added: NaiveDateTime,
+}
+
+impl From<Account> for PlayerProfile {
+    fn from(a: Account) -> Self {
+        PlayerProfile {
+            player_id: a.account_id,
+            username: a.username,
+            e_mail: a.e_mail,
+            steam_id: a.steam_id,
+            mfa_enabled: a.mfa_code.is_some(),
+            date_added: a.date_added,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UpdatePlayerRequest {
+    /// Must always be provided to make changes
+    pub(crate) current_password: String,
+    pub(crate) e_mail: Option<String>,
+    pub(crate) password: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct MfaSecretResponse {
+    /// Base32 secret to add to an authenticator app
+    pub(crate) secret: String,
+}
+
+#[derive(Deserialize)]
+pub struct MfaUpdateRequest {
+    /// The secret from `MfaSecretResponse`, only needed when enabling MFA
+    pub(crate) secret: Option<String>,
+    /// A current code from the authenticator app
+    pub(crate) code: String,
+}
+
+#[derive(Deserialize)]
+pub struct ClaimBindRequest {
+    /// The Bind ID shown to the player in game
+    pub(crate) bind_id: String,
+}
+
+#[derive(Serialize)]
+pub struct ClaimBindResponse {
+    pub(crate) bind_id: String,
+    /// True once the game has collected the bind
+    pub(crate) confirmed: bool,
+    /// Seconds left for the game to collect the bind
+    pub(crate) ttl: i64,
+}
@@ -1 +1,60 @@
+use bigdecimal::ToPrimitive;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::{ExpressionMethods, QueryDsl};
+use itertools::Itertools;
 
+use crate::db::models::inventory::Inventory;
+use crate::db::models::price_tracker::PriceTracker;
+use crate::db::schema::price_tracker as schema;
+use crate::db::Ppc;
+use crate::packets::inventory::PricePoint;
+use crate::traits::from::InventoryToPT;
+use crate::traits::numerical::CanRound;
+
+impl From<PriceTracker> for PricePoint {
+    fn from(pt: PriceTracker) -> Self {
+        PricePoint {
+            date: pt.create_date.timestamp(),
+            base_value: pt.value.round_2dp().to_f32().unwrap_or(0f32),
+            we_buy_at: pt.buy_at.round_2dp().to_f32().unwrap_or(0f32),
+            we_sell_at: pt.sell_at.round_2dp().to_f32().unwrap_or(0f32),
+        }
+    }
+}
+
+/// Record the current prices of each item so we can chart them later.
+pub fn record_price_snapshots(items: &Vec<Inventory>, conn: &Ppc) -> Result<usize, ()> {
+    let now = Utc::now().naive_utc();
+    let snapshots = items
+        .iter()
+        .map(|item| PriceTracker::i2pt(item, &now))
+        .collect_vec();
+
+    diesel::insert_into(schema::table)
+        .values(&snapshots)
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .map_err(|e| {
+            warn!("Failed to record price snapshots, {}", e);
+        })
+}
+
+/// Get the price snapshots for an item in a market since the date given, oldest first.
+pub fn get_price_history(
+    market: &str,
+    item_code: &str,
+    since: NaiveDateTime,
+    conn: &Ppc,
+) -> Vec<PriceTracker> {
+    schema::table
+        .filter(schema::market.eq(market))
+        .filter(schema::item_code.eq(item_code))
+        .filter(schema::create_date.ge(since))
+        .order(schema::create_date.asc())
+        .get_results(conn)
+        .unwrap_or_else(|e| {
+            warn!("Failed to load price history for {}, {}", item_code, e);
+            Vec::new()
+        })
+}
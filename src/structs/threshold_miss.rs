@@ -0,0 +1,50 @@
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+use diesel::ExpressionMethods;
+
+use crate::db::models::maintenance::{ThresholdMiss, ThresholdMissSummary};
+use crate::db::schema::threshold_misses as schema;
+use crate::db::Ppc;
+
+/// Start a market's maintenance pass with a clean list, anything still missing gets found again.
+pub fn clear_threshold_misses(market: &str, conn: &Ppc) -> QueryResult<usize> {
+    diesel::delete(schema::table.filter(schema::market.eq(market))).execute(conn)
+}
+
+pub fn record_threshold_misses(misses: &Vec<ThresholdMiss>, conn: &Ppc) -> Result<usize, ()> {
+    if misses.is_empty() {
+        return Ok(0);
+    }
+    diesel::insert_into(schema::table)
+        .values(misses)
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .map_err(|e| {
+            warn!("Failed to record threshold misses, {}", e);
+        })
+}
+
+pub fn summarise_threshold_misses(conn: &Ppc) -> QueryResult<ThresholdMissSummary> {
+    Ok(ThresholdMissSummary {
+        missing_price: schema::table
+            .select(count_star())
+            .filter(schema::missing_price.eq(true))
+            .first(conn)?,
+        missing_stock: schema::table
+            .select(count_star())
+            .filter(schema::missing_stock.eq(true))
+            .first(conn)?,
+    })
+}
+
+/// Everything the last maintenance run couldn't cover, by market and then cheapest first
+/// so it reads in the same order as the thresholds.
+pub fn get_threshold_misses(conn: &Ppc) -> QueryResult<Vec<ThresholdMiss>> {
+    schema::table
+        .order((
+            schema::market.asc(),
+            schema::base_value.asc(),
+            schema::item_code.asc(),
+        ))
+        .load(conn)
+}
@@ -1,7 +1,7 @@
-use crate::db::models::inventory::Inventory;
+use crate::db::models::inventory::{Inventory, InventoryNoQuantity};
 use crate::packets::tradable::ColonyTradable;
 use crate::structs::general::DbPkLoadable;
-use crate::traits::item::{HasItemCode, HasThingDef};
+use crate::traits::item::{HasItemCode, HasStuff, HasThingDef};
 
 make_pk_loadable!(Inventory, String, crate::db::schema::inventory);
 
@@ -17,6 +17,30 @@ impl HasThingDef for Inventory {
     }
 }
 
+impl HasStuff for Inventory {
+    fn get_stuff(&self) -> Option<&String> {
+        self.stuff.as_ref()
+    }
+}
+
+impl HasItemCode for InventoryNoQuantity {
+    fn get_item_code(&self) -> &String {
+        &self.item_code
+    }
+}
+
+impl HasThingDef for InventoryNoQuantity {
+    fn get_thing_def(&self) -> &String {
+        &self.thing_def
+    }
+}
+
+impl HasStuff for InventoryNoQuantity {
+    fn get_stuff(&self) -> Option<&String> {
+        self.stuff.as_ref()
+    }
+}
+
 impl HasThingDef for ColonyTradable {
     fn get_thing_def(&self) -> &String {
         &self.thing_def
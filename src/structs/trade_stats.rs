@@ -1 +1,12 @@
+use chrono::NaiveDate;
 
+/// Result of rolling the daily trade statistics up into monthly totals
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
+pub struct StatisticsRollup {
+    /// Monthly rows created for months that had finished
+    pub monthly_rows_inserted: usize,
+    /// Daily rows removed because they were older than the retention window
+    pub daily_rows_pruned: usize,
+    /// Daily rows before this date were pruned
+    pub prune_before: Option<NaiveDate>,
+}
@@ -0,0 +1,78 @@
+use crate::jtd::diff::{diff, DiffEntry};
+use serde_json::json;
+
+#[test]
+fn identical_documents_have_no_changes() {
+    let document = json!({"api": {"force_offline": false}, "list": [1, 2]});
+    assert!(diff(&document, &document).is_empty());
+}
+
+#[test]
+fn changed_values_are_reported_by_path() {
+    let old = json!({"api": {"force_offline": false}, "delivery": {"collect_cost_per_kg": 1}});
+    let new = json!({"api": {"force_offline": true}, "delivery": {"collect_cost_per_kg": 1}});
+    assert_eq!(
+        diff(&old, &new),
+        vec![DiffEntry {
+            path: "/api/force_offline".to_string(),
+            old: Some(json!(false)),
+            new: Some(json!(true)),
+        }]
+    );
+}
+
+#[test]
+fn added_and_removed_keys() {
+    let old = json!({"a": 1, "b": 2});
+    let new = json!({"b": 2, "c/d": 3});
+    assert_eq!(
+        diff(&old, &new),
+        vec![
+            DiffEntry {
+                path: "/a".to_string(),
+                old: Some(json!(1)),
+                new: None,
+            },
+            DiffEntry {
+                path: "/c~1d".to_string(),
+                old: None,
+                new: Some(json!(3)),
+            },
+        ]
+    );
+}
+
+#[test]
+fn arrays_are_compared_by_index() {
+    let old = json!({"thresholds": [{"price_start": 0}, {"price_start": 10}]});
+    let new = json!({"thresholds": [{"price_start": 5}]});
+    assert_eq!(
+        diff(&old, &new),
+        vec![
+            DiffEntry {
+                path: "/thresholds/0/price_start".to_string(),
+                old: Some(json!(0)),
+                new: Some(json!(5)),
+            },
+            DiffEntry {
+                path: "/thresholds/1".to_string(),
+                old: Some(json!({"price_start": 10})),
+                new: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn type_changes_replace_the_whole_value() {
+    let old = json!({"a": {"b": 1}});
+    let new = json!({"a": [1]});
+    assert_eq!(
+        diff(&old, &new),
+        vec![DiffEntry {
+            path: "/a".to_string(),
+            old: Some(json!({"b": 1})),
+            new: Some(json!([1])),
+        }]
+    );
+}
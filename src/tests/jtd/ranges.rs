@@ -0,0 +1,108 @@
+use crate::jtd::stock_config::ranges::{find_range_problems, RangeProblem};
+use crate::jtd::stock_config::{ItemCondition, PriceThreshold, StockConfig, StockThreshold};
+
+fn price_threshold(
+    price_start: u32,
+    price_end: u32,
+    condition: Option<ItemCondition>,
+) -> PriceThreshold {
+    let mut threshold = PriceThreshold::default();
+    threshold.price_start = price_start;
+    threshold.price_end = price_end;
+    threshold.condition = condition;
+    threshold
+}
+
+fn stock_threshold(price_start: u32, price_end: u32) -> StockThreshold {
+    let mut threshold = StockThreshold::default();
+    threshold.price_start = price_start;
+    threshold.price_end = price_end;
+    threshold
+}
+
+fn config_with_prices(thresholds: Vec<PriceThreshold>) -> StockConfig {
+    let mut config = StockConfig::default();
+    config.pricing.thresholds = thresholds;
+    config
+}
+
+#[test]
+fn continuous_ranges_are_fine() {
+    let mut config = config_with_prices(vec![
+        price_threshold(100, 1000, None),
+        price_threshold(0, 100, None),
+    ]);
+    config.restock.thresholds = vec![stock_threshold(0, 50), stock_threshold(50, 1000)];
+    assert_eq!(find_range_problems(&config), vec![]);
+}
+
+#[test]
+fn gaps_are_found() {
+    let config = config_with_prices(vec![
+        price_threshold(0, 10, None),
+        price_threshold(11, 100, None),
+    ]);
+    assert_eq!(
+        find_range_problems(&config),
+        vec![RangeProblem::Gap {
+            path: "/pricing/thresholds/1".to_string(),
+            from: 10,
+            to: 11,
+        }]
+    );
+}
+
+#[test]
+fn overlaps_are_found() {
+    let mut config = StockConfig::default();
+    config.restock.thresholds = vec![
+        stock_threshold(0, 100),
+        stock_threshold(10, 20),
+        stock_threshold(100, 200),
+    ];
+    assert_eq!(
+        find_range_problems(&config),
+        vec![RangeProblem::Overlap {
+            path: "/restock/thresholds/1".to_string(),
+            other: "/restock/thresholds/0".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn inverted_ranges_are_found() {
+    let config = config_with_prices(vec![price_threshold(50, 10, None)]);
+    assert_eq!(
+        find_range_problems(&config),
+        vec![RangeProblem::Inverted {
+            path: "/pricing/thresholds/0".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn condition_tiers_can_overlap_the_fallback() {
+    // Used items outside 0..50 fall back to the thresholds without a condition
+    let config = config_with_prices(vec![
+        price_threshold(0, 1000, None),
+        price_threshold(0, 50, Some(ItemCondition::Used)),
+    ]);
+    assert_eq!(find_range_problems(&config), vec![]);
+}
+
+#[test]
+fn condition_tiers_without_a_fallback_must_be_continuous() {
+    let config = config_with_prices(vec![
+        price_threshold(0, 50, Some(ItemCondition::Used)),
+        price_threshold(60, 100, Some(ItemCondition::Used)),
+        price_threshold(0, 100, Some(ItemCondition::New)),
+    ]);
+    assert_eq!(
+        find_range_problems(&config),
+        vec![RangeProblem::Gap {
+            path: "/pricing/thresholds/1".to_string(),
+            from: 50,
+            to: 60,
+        }]
+    );
+}
@@ -0,0 +1,162 @@
+use crate::jtd::api_config::structure::ApiConfigData;
+use crate::jtd::stock_config::{PriceThreshold, StockConfig, StockThreshold};
+use crate::jtd::validate::{validate, ValidationError, API_CONFIG_SCHEMA, STOCK_CONFIG_SCHEMA};
+use serde_json::{json, Value};
+
+fn api_schema() -> Value {
+    serde_json::from_str(API_CONFIG_SCHEMA).unwrap()
+}
+
+fn stock_schema() -> Value {
+    serde_json::from_str(STOCK_CONFIG_SCHEMA).unwrap()
+}
+
+fn error(instance_path: &str, schema_path: &str) -> ValidationError {
+    ValidationError {
+        instance_path: instance_path.to_string(),
+        schema_path: schema_path.to_string(),
+    }
+}
+
+#[test]
+fn default_api_config_is_valid() {
+    let document = serde_json::to_value(ApiConfigData::default()).unwrap();
+    assert_eq!(validate(&api_schema(), &document), vec![]);
+}
+
+#[test]
+fn default_stock_config_is_valid() {
+    let mut config = StockConfig::default();
+    config.pricing.thresholds.push(PriceThreshold::default());
+    config.restock.thresholds.push(StockThreshold::default());
+    let document = serde_json::to_value(config).unwrap();
+    assert_eq!(validate(&stock_schema(), &document), vec![]);
+}
+
+#[test]
+fn optional_properties_can_be_left_out() {
+    let mut document = serde_json::to_value(ApiConfigData::default()).unwrap();
+    document.as_object_mut().unwrap().remove("contracts");
+    assert_eq!(validate(&api_schema(), &document), vec![]);
+}
+
+#[test]
+fn vote_weights_default_when_left_out() {
+    let mut document = serde_json::to_value(ApiConfigData::default()).unwrap();
+    document["inventory"]["vote_weights"] = json!({"drm_free_account": 0.1});
+    assert_eq!(validate(&api_schema(), &document), vec![]);
+
+    let config: ApiConfigData = serde_json::from_value(document).unwrap();
+    let weights = config.inventory.vote_weights;
+    assert_eq!(weights.drm_free_account, 0.1);
+    assert_eq!(weights.steam_account, 1.0);
+    assert_eq!(weights.trusted_account_days, 30);
+}
+
+#[test]
+fn missing_property_is_an_error() {
+    let mut document = serde_json::to_value(ApiConfigData::default()).unwrap();
+    document.as_object_mut().unwrap().remove("api");
+    assert_eq!(
+        validate(&api_schema(), &document),
+        vec![error("", "/properties/api")]
+    );
+}
+
+#[test]
+fn unknown_property_is_an_error() {
+    let mut document = serde_json::to_value(ApiConfigData::default()).unwrap();
+    document["api"]["force_online"] = json!(true);
+    assert_eq!(
+        validate(&api_schema(), &document),
+        vec![error("/api/force_online", "/properties/api")]
+    );
+}
+
+#[test]
+fn wrong_type_is_an_error() {
+    let mut document = serde_json::to_value(ApiConfigData::default()).unwrap();
+    document["api"]["force_offline"] = json!("yes");
+    document["delivery"]["collect_cost_per_kg"] = json!(-1);
+    document["inventory"]["vote_age_threshold"] = json!(1.5);
+
+    let mut errors = validate(&api_schema(), &document);
+    errors.sort_by(|a, b| a.instance_path.cmp(&b.instance_path));
+    assert_eq!(
+        errors,
+        vec![
+            error(
+                "/api/force_offline",
+                "/properties/api/properties/force_offline/type"
+            ),
+            error(
+                "/delivery/collect_cost_per_kg",
+                "/properties/delivery/properties/collect_cost_per_kg/type"
+            ),
+            error(
+                "/inventory/vote_age_threshold",
+                "/properties/inventory/properties/vote_age_threshold/type"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn whole_floats_are_integers() {
+    let schema = json!({"type": "uint32"});
+    assert!(validate(&schema, &json!(5.0)).is_empty());
+    assert!(!validate(&schema, &json!(4_294_967_296u64)).is_empty());
+}
+
+#[test]
+fn errors_inside_refs_use_the_definition_path() {
+    let mut config = StockConfig::default();
+    config.pricing.thresholds.push(PriceThreshold::default());
+    let mut document = serde_json::to_value(config).unwrap();
+    document["pricing"]["thresholds"][0]["price_start"] = json!("cheap");
+
+    let errors = validate(&stock_schema(), &document);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].instance_path, "/pricing/thresholds/0/price_start");
+    assert!(errors[0].schema_path.starts_with("/definitions/"));
+    assert!(errors[0]
+        .schema_path
+        .ends_with("/properties/price_start/type"));
+}
+
+#[test]
+fn nullable_enum_and_values() {
+    let schema = json!({
+        "values": {"enum": ["a", "b"], "nullable": true}
+    });
+    assert!(validate(&schema, &json!({"x": "a", "y": null})).is_empty());
+    assert_eq!(
+        validate(&schema, &json!({"x": "c"})),
+        vec![error("/x", "/values/enum")]
+    );
+    assert_eq!(validate(&schema, &json!([])), vec![error("", "/values")]);
+}
+
+#[test]
+fn discriminator_tag_is_not_an_additional_property() {
+    let schema = json!({
+        "discriminator": "kind",
+        "mapping": {
+            "step": {"properties": {"size": {"type": "float32"}}}
+        }
+    });
+    assert!(validate(&schema, &json!({"kind": "step", "size": 0.1})).is_empty());
+    assert_eq!(
+        validate(&schema, &json!({"kind": "demand"})),
+        vec![error("/kind", "/mapping")]
+    );
+}
+
+#[test]
+fn recursive_refs_stop() {
+    let schema = json!({
+        "definitions": {"loop": {"ref": "loop"}},
+        "ref": "loop"
+    });
+    assert!(!validate(&schema, &json!(1)).is_empty());
+}
@@ -1,2 +1,31 @@
+use bigdecimal::BigDecimal;
+
+use crate::db::models::inventory::Inventory;
+use crate::packets::tradable::ItemConditionEnum;
+
+pub mod jtd;
 pub mod routines;
+pub mod structs;
 pub mod traits;
+
+/// A new item in stock, tests change whatever fields they care about
+pub fn create_inventory_item(quantity: i32, base_value: f32) -> Inventory {
+    let base_value = BigDecimal::from(base_value);
+    Inventory {
+        item_code: "ABCD1234".to_string(),
+        thing_def: "steel".to_string(),
+        quality: None,
+        quantity,
+        minified: false,
+        buy_at: base_value.clone(),
+        sell_at: base_value.clone(),
+        base_value,
+        stuff: None,
+        weight: BigDecimal::from(1.0),
+        version: "EFGH5678".to_string(),
+        condition: ItemConditionEnum::New.into(),
+        parent_item_code: None,
+        mod_name: None,
+        market: "1.3".to_string(),
+    }
+}
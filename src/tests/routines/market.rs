@@ -1,6 +1,14 @@
 use crate::db::models::inventory::Inventory;
-use crate::jtd::stock_config::{PriceThreshold, StockThreshold};
-use crate::routines::market::{update_item_price, update_item_stock, TradeStatsPair};
+use crate::jtd::stock_config::{
+    ItemCondition, ItemOverride, PriceThreshold, StockConfig, StockConfigPricing,
+    StockConfigRestock, StockThreshold,
+};
+use crate::packets::tradable::ItemConditionEnum;
+use crate::routines::market::{
+    apply_price_override, apply_stock_override, get_item_override, get_price_threshold,
+    get_stock_threshold, update_item_price, update_item_stock, TradeStatsPair,
+};
+use crate::tests::create_inventory_item;
 use crate::traits::numerical::Percentage;
 use bigdecimal::BigDecimal;
 
@@ -12,23 +20,6 @@ fn get_rng() -> fastRng {
     fastRng::with_seed(seed)
 }
 
-fn create_inventory_item(quantity: i32, base_value: f32) -> Inventory {
-    let base_value = BigDecimal::from(base_value);
-    Inventory {
-        item_code: "ABCD1234".to_string(),
-        thing_def: "steel".to_string(),
-        quality: None,
-        quantity,
-        minified: false,
-        buy_at: base_value.clone(),
-        sell_at: base_value.clone(),
-        base_value,
-        stuff: None,
-        weight: BigDecimal::from(1.0),
-        version: "EFGH5678".to_string(),
-    }
-}
-
 fn create_config_10_pct_step_per_unit() -> PriceThreshold {
     let mut config = PriceThreshold::default();
     config.buying.step_size = 0.1f32;
@@ -220,6 +211,7 @@ fn test_expensive_item_high_buys_clamps_update_item_price() {
 fn generate_stock_config(max: u32, min: u32) -> StockThreshold {
     StockThreshold {
         chance_to_restock: 0.25,
+        condition: None,
         max_quantity: max,
         max_restock: max / 2u32,
         min_quantity: min,
@@ -283,3 +275,136 @@ fn test_stock_jiggle_large_quantities() {
     assert_ne!(item.quantity, og_qty);
     assert!(item.quantity > 0);
 }
+
+fn create_tiered_price_config() -> StockConfigPricing {
+    let mut any = create_config_10_pct_step_per_unit();
+    any.price_end = 1000;
+    let mut used = any.clone();
+    used.condition = Some(ItemCondition::Used);
+    used.buying.step_size = 0.2f32;
+    StockConfigPricing {
+        thresholds: vec![any, used],
+    }
+}
+
+#[test]
+fn test_used_item_prefers_used_price_threshold() {
+    let config = create_tiered_price_config();
+    let mut item = create_inventory_item(10, 2f32);
+    item.condition = ItemConditionEnum::Used.into();
+
+    let threshold = get_price_threshold(&config, &item).unwrap();
+    assert_eq!(threshold.condition, Some(ItemCondition::Used));
+    assert_eq!(threshold.buying.step_size, 0.2f32);
+}
+
+#[test]
+fn test_new_item_skips_used_price_threshold() {
+    let config = create_tiered_price_config();
+    let item = create_inventory_item(10, 2f32);
+
+    let threshold = get_price_threshold(&config, &item).unwrap();
+    assert_eq!(threshold.condition, None);
+}
+
+#[test]
+fn test_used_item_falls_back_to_any_stock_threshold() {
+    let mut any = generate_stock_config(50, 10);
+    any.price_end = 1000;
+    let mut new = any.clone();
+    new.condition = Some(ItemCondition::New);
+    new.max_quantity = 5;
+    let config = StockConfigRestock {
+        thresholds: vec![new, any],
+    };
+
+    let mut item = create_inventory_item(10, 2f32);
+    item.condition = ItemConditionEnum::Used.into();
+    let threshold = get_stock_threshold(&config, &item).unwrap();
+    assert_eq!(threshold.condition, None);
+    assert_eq!(threshold.max_quantity, 50);
+
+    item.condition = ItemConditionEnum::New.into();
+    let threshold = get_stock_threshold(&config, &item).unwrap();
+    assert_eq!(threshold.max_quantity, 5);
+}
+
+#[test]
+fn test_no_threshold_for_condition_outside_price_range() {
+    let config = create_tiered_price_config();
+    let mut item = create_inventory_item(10, 2000f32);
+    item.condition = ItemConditionEnum::Used.into();
+
+    assert!(get_price_threshold(&config, &item).is_none());
+}
+
+fn create_override_config() -> StockConfig {
+    let mut config = StockConfig::default();
+    config.overrides = vec![
+        ItemOverride {
+            thing_def: Some("steel".to_string()),
+            max_quantity: Some(20),
+            ..Default::default()
+        },
+        ItemOverride {
+            item_code: Some("ABCD1234".to_string()),
+            thing_def: Some("steel".to_string()),
+            never_buy: Some(true),
+            ..Default::default()
+        },
+    ];
+    config
+}
+
+#[test]
+fn test_item_code_override_wins_over_thing_def() {
+    let config = create_override_config();
+    let item = create_inventory_item(10, 2f32);
+
+    let item_override = get_item_override(&config, &item).unwrap();
+    assert_eq!(item_override.item_code, Some("ABCD1234".to_string()));
+    assert_eq!(item_override.never_buy, Some(true));
+}
+
+#[test]
+fn test_thing_def_override_applies_to_other_items() {
+    let config = create_override_config();
+    let mut item = create_inventory_item(10, 2f32);
+    item.item_code = "ZYXW9876".to_string();
+
+    let item_override = get_item_override(&config, &item).unwrap();
+    assert_eq!(item_override.item_code, None);
+    assert_eq!(item_override.max_quantity, Some(20));
+
+    item.thing_def = "plasteel".to_string();
+    assert!(get_item_override(&config, &item).is_none());
+}
+
+#[test]
+fn test_fixed_price_override() {
+    let item_override = ItemOverride {
+        sell_at: Some(5.5f32),
+        ..Default::default()
+    };
+    let item = apply_price_override(Some(&item_override), create_inventory_item(10, 2f32));
+    assert_eq!(item.sell_at, BigDecimal::from(5.5f32));
+    assert_eq!(item.buy_at, BigDecimal::from(2f32));
+}
+
+#[test]
+fn test_stock_override() {
+    let item_override = ItemOverride {
+        min_quantity: Some(5),
+        max_quantity: Some(10),
+        never_restock: Some(true),
+        ..Default::default()
+    };
+    let config = apply_stock_override(Some(&item_override), generate_stock_config(50, 1));
+    assert_eq!(config.min_quantity, 5);
+    assert_eq!(config.max_quantity, 10);
+    assert_eq!(config.max_restock, 10);
+    assert_eq!(config.chance_to_restock, 0f32);
+
+    let item = update_item_stock(config, create_inventory_item(0, 2f32), &get_rng());
+    assert_eq!(item.quantity, 0);
+}
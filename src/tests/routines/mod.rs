@@ -1 +1,2 @@
 pub mod market;
+pub mod pricing;
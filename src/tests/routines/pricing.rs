@@ -0,0 +1,144 @@
+use crate::jtd::stock_config::{PriceStrategy, PriceThreshold};
+use crate::routines::market::pricing::{
+    strategy_for, DemandPricing, MarketActivity, PricingStrategy,
+};
+use crate::routines::market::{update_item_price, TradeStatsPair};
+use crate::tests::create_inventory_item;
+use bigdecimal::BigDecimal;
+use std::str::FromStr;
+
+use fastrand::Rng as fastRng;
+
+fn create_demand_config() -> PriceThreshold {
+    let mut config = PriceThreshold::default();
+    config.strategy = Some(PriceStrategy::Demand);
+    config.demand.elasticity = 0.2f32;
+    config.buying.max_price_decrease_pct = 0.5f32;
+    config.buying.max_price_increase_pct = 0.5f32;
+    config.selling = config.buying.clone();
+    config
+}
+
+fn create_activity(bought: i64, sold: i64, max_quantity: Option<u32>) -> MarketActivity {
+    MarketActivity {
+        today: TradeStatsPair::default(),
+        rolling: TradeStatsPair { bought, sold },
+        max_quantity,
+    }
+}
+
+#[test]
+fn test_step_strategy_is_the_default() {
+    let mut config = PriceThreshold::default();
+    config.buying.step_size = 0.1f32;
+    config.buying.max_steps = 2;
+    config.buying.unit_to_stock_ratio = 1;
+    config.buying.max_price_decrease_pct = 1f32;
+    config.buying.max_price_increase_pct = 1f32;
+    config.selling = config.buying.clone();
+
+    let rng = fastRng::with_seed(1);
+    let activity = MarketActivity {
+        today: TradeStatsPair { bought: 5, sold: 0 },
+        ..Default::default()
+    };
+    let expected = update_item_price(
+        config.clone(),
+        create_inventory_item(10, 10f32),
+        Some(TradeStatsPair { bought: 5, sold: 0 }),
+        &rng,
+    );
+    let item = strategy_for(config).update_price(create_inventory_item(10, 10f32), &activity, &rng);
+    assert_eq!(item.sell_at, expected.sell_at);
+    assert_eq!(item.buy_at, expected.buy_at);
+}
+
+#[test]
+fn test_demand_pressure_is_balanced_without_activity() {
+    let item = create_inventory_item(50, 10f32);
+    assert_eq!(
+        DemandPricing::pressure(&item, &create_activity(0, 0, None)),
+        0f32
+    );
+    assert_eq!(
+        DemandPricing::pressure(&item, &create_activity(10, 10, Some(100))),
+        0f32
+    );
+}
+
+#[test]
+fn test_demand_pressure_limits() {
+    let empty = create_inventory_item(0, 10f32);
+    assert_eq!(
+        DemandPricing::pressure(&empty, &create_activity(10, 0, Some(100))),
+        1f32
+    );
+
+    // More stock than we want is treated the same as being full
+    let flooded = create_inventory_item(500, 10f32);
+    assert_eq!(
+        DemandPricing::pressure(&flooded, &create_activity(0, 10, Some(100))),
+        -1f32
+    );
+}
+
+#[test]
+fn test_demand_scarce_item_gets_more_expensive() {
+    let item = strategy_for(create_demand_config()).update_price(
+        create_inventory_item(0, 10f32),
+        &create_activity(20, 0, Some(100)),
+        &fastRng::with_seed(1),
+    );
+    assert_eq!(item.sell_at, BigDecimal::from(12f32));
+    assert_eq!(item.buy_at, BigDecimal::from(12f32));
+}
+
+#[test]
+fn test_demand_flooded_item_gets_cheaper() {
+    let item = strategy_for(create_demand_config()).update_price(
+        create_inventory_item(100, 10f32),
+        &create_activity(0, 20, Some(100)),
+        &fastRng::with_seed(1),
+    );
+    assert_eq!(item.sell_at, BigDecimal::from(8f32));
+    assert_eq!(item.buy_at, BigDecimal::from(8f32));
+}
+
+#[test]
+fn test_demand_price_is_clamped() {
+    let mut config = create_demand_config();
+    config.demand.elasticity = 1f32;
+    config.selling.max_price_increase_pct = 0.1f32;
+    let item = strategy_for(config).update_price(
+        create_inventory_item(0, 10f32),
+        &create_activity(20, 0, Some(100)),
+        &fastRng::with_seed(1),
+    );
+    assert_eq!(item.sell_at, BigDecimal::from(11f32));
+    assert_eq!(item.buy_at, BigDecimal::from(15f32));
+}
+
+#[test]
+fn test_demand_keeps_buy_price_below_sell_price() {
+    let mut config = create_demand_config();
+    config.buying.step_size = 0.1f32;
+    config.selling.step_size = 0.1f32;
+    let strategy = strategy_for(config);
+
+    for (quantity, bought, sold) in [(0, 20, 0), (50, 10, 10), (100, 0, 20)].iter() {
+        let item = strategy.update_price(
+            create_inventory_item(*quantity, 10f32),
+            &create_activity(*bought, *sold, Some(100)),
+            &fastRng::with_seed(1),
+        );
+        assert!(item.buy_at < item.sell_at);
+    }
+
+    let item = strategy.update_price(
+        create_inventory_item(0, 10f32),
+        &create_activity(20, 0, Some(100)),
+        &fastRng::with_seed(1),
+    );
+    assert_eq!(item.sell_at, BigDecimal::from_str("13.2").unwrap());
+    assert_eq!(item.buy_at, BigDecimal::from_str("10.8").unwrap());
+}
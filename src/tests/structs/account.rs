@@ -0,0 +1,35 @@
+use chrono::Duration;
+
+use crate::structs::account::{
+    login_lockout, FREE_LOGIN_ATTEMPTS, LOGIN_LOCKOUT_SECONDS, MAX_LOGIN_LOCKOUT_SECONDS,
+};
+
+#[test]
+fn free_attempts_do_not_lock() {
+    assert_eq!(login_lockout(0), None);
+    assert_eq!(login_lockout(FREE_LOGIN_ATTEMPTS - 1), None);
+}
+
+#[test]
+fn lockout_doubles_with_each_failure() {
+    assert_eq!(
+        login_lockout(FREE_LOGIN_ATTEMPTS),
+        Some(Duration::seconds(LOGIN_LOCKOUT_SECONDS))
+    );
+    assert_eq!(
+        login_lockout(FREE_LOGIN_ATTEMPTS + 2),
+        Some(Duration::seconds(LOGIN_LOCKOUT_SECONDS * 4))
+    );
+}
+
+#[test]
+fn lockout_is_capped() {
+    assert_eq!(
+        login_lockout(FREE_LOGIN_ATTEMPTS + 100),
+        Some(Duration::seconds(MAX_LOGIN_LOCKOUT_SECONDS))
+    );
+    assert_eq!(
+        login_lockout(i32::MAX),
+        Some(Duration::seconds(MAX_LOGIN_LOCKOUT_SECONDS))
+    );
+}
@@ -0,0 +1,92 @@
+use chrono::Utc;
+
+use crate::db::models::blacklist::BlacklistEntry;
+use crate::db::models::inventory::Inventory;
+use crate::structs::blacklist::{matches_pattern, Blacklist, BlacklistEntryRequest};
+use crate::tests::create_inventory_item;
+
+fn entry(item_code: Option<&str>, thing_def: Option<&str>, stuff: Option<&str>) -> BlacklistEntry {
+    BlacklistEntry {
+        entry_id: 1,
+        item_code: item_code.map(String::from),
+        thing_def: thing_def.map(String::from),
+        stuff: stuff.map(String::from),
+        reason: "test".to_string(),
+        date_added: Utc::now().naive_utc(),
+    }
+}
+
+fn inventory_item(thing_def: &str, stuff: Option<&str>) -> Inventory {
+    Inventory {
+        thing_def: thing_def.to_string(),
+        stuff: stuff.map(String::from),
+        ..create_inventory_item(10, 1f32)
+    }
+}
+
+#[test]
+fn pattern_without_wildcard_is_exact() {
+    assert!(matches_pattern("Steel", "Steel"));
+    assert!(!matches_pattern("Steel", "SteelBar"));
+    assert!(!matches_pattern("Steel", "Stee"));
+}
+
+#[test]
+fn pattern_wildcards() {
+    assert!(matches_pattern("*", "Anything"));
+    assert!(matches_pattern("Exploit_*", "Exploit_Gun"));
+    assert!(matches_pattern("*_Gun", "Exploit_Gun"));
+    assert!(matches_pattern("Ex*Gun", "Exploit_Gun"));
+    assert!(matches_pattern("E*_*n", "Exploit_Gun"));
+    assert!(!matches_pattern("Exploit_*", "Gun"));
+    assert!(!matches_pattern("a*a", "a"));
+}
+
+#[test]
+fn every_set_field_has_to_match() {
+    let blacklist = Blacklist {
+        entries: vec![entry(None, Some("Apparel_*"), Some("Gold"))],
+    };
+    assert!(blacklist.contains(&inventory_item("Apparel_Hat", Some("Gold"))));
+    assert!(!blacklist.contains(&inventory_item("Apparel_Hat", Some("Cloth"))));
+    assert!(!blacklist.contains(&inventory_item("Apparel_Hat", None)));
+    assert!(!blacklist.contains(&inventory_item("Gun_Revolver", Some("Gold"))));
+}
+
+#[test]
+fn used_lines_follow_their_parent() {
+    let blacklist = Blacklist {
+        entries: vec![entry(Some("ABCD1234"), None, None)],
+    };
+    let mut item = inventory_item("Apparel_Hat", None);
+    item.item_code = "USED5678".to_string();
+    assert!(!blacklist.contains_inventory(&item));
+
+    item.parent_item_code = Some("ABCD1234".to_string());
+    assert!(blacklist.contains_inventory(&item));
+}
+
+#[test]
+fn parse_entry_request() {
+    let request: BlacklistEntryRequest = "thing_def=Exploit_* stuff=Gold duplicates gold"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        request,
+        BlacklistEntryRequest {
+            item_code: None,
+            thing_def: Some("Exploit_*".to_string()),
+            stuff: Some("Gold".to_string()),
+            reason: "duplicates gold".to_string(),
+        }
+    );
+}
+
+#[test]
+fn entry_request_needs_a_target_and_reason() {
+    assert!("no target here".parse::<BlacklistEntryRequest>().is_err());
+    assert!("thing_def=Steel".parse::<BlacklistEntryRequest>().is_err());
+    assert!("colour=Red why not"
+        .parse::<BlacklistEntryRequest>()
+        .is_err());
+}
@@ -0,0 +1,88 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::db::models::inventory::Inventory;
+use crate::structs::colony_mods::{infer_item_mod, is_mod_loaded};
+use crate::tests::create_inventory_item;
+
+fn mod_list(mods: &[&str]) -> Vec<String> {
+    mods.iter().map(|m| m.to_string()).collect()
+}
+
+/// 100 colonies, everyone has Core and Harmony
+fn popularity() -> HashMap<String, i64> {
+    vec![
+        ("Core".to_string(), 100),
+        ("Harmony".to_string(), 95),
+        ("HugsLib".to_string(), 60),
+        ("VanillaWeapons".to_string(), 20),
+        ("RimGuns".to_string(), 5),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn inventory_item(mod_name: Option<&str>) -> Inventory {
+    Inventory {
+        thing_def: "Gun_Revolver".to_string(),
+        mod_name: mod_name.map(String::from),
+        ..create_inventory_item(10, 1f32)
+    }
+}
+
+#[test]
+fn least_popular_common_mod_is_picked() {
+    let voters = vec![
+        mod_list(&["Core", "Harmony", "HugsLib", "VanillaWeapons"]),
+        mod_list(&["Core", "Harmony", "VanillaWeapons", "RimGuns"]),
+    ];
+    assert_eq!(
+        infer_item_mod(&voters, &popularity(), 100),
+        Some("VanillaWeapons".to_string())
+    );
+}
+
+#[test]
+fn base_game_items_are_not_tagged() {
+    let voters = vec![
+        mod_list(&["Core", "Harmony", "HugsLib"]),
+        mod_list(&["Core", "Harmony", "RimGuns"]),
+    ];
+    assert_eq!(infer_item_mod(&voters, &popularity(), 100), None);
+}
+
+#[test]
+fn one_voter_is_not_enough() {
+    let voters = vec![mod_list(&["Core", "RimGuns"])];
+    assert_eq!(infer_item_mod(&voters, &popularity(), 100), None);
+    assert_eq!(infer_item_mod(&[], &popularity(), 100), None);
+}
+
+#[test]
+fn unknown_mods_are_the_least_popular() {
+    let voters = vec![
+        mod_list(&["Core", "RimGuns", "BrandNew"]),
+        mod_list(&["Core", "RimGuns", "BrandNew"]),
+    ];
+    assert_eq!(
+        infer_item_mod(&voters, &popularity(), 100),
+        Some("BrandNew".to_string())
+    );
+}
+
+#[test]
+fn untagged_items_are_offered_to_everyone() {
+    let loaded: Option<HashSet<String>> = Some(mod_list(&["Core"]).into_iter().collect());
+    assert!(is_mod_loaded(&loaded, &inventory_item(None)));
+    assert!(is_mod_loaded(&None, &inventory_item(Some("RimGuns"))));
+}
+
+#[test]
+fn tagged_items_need_their_mod_loaded() {
+    let loaded: Option<HashSet<String>> =
+        Some(mod_list(&["Core", "RimGuns"]).into_iter().collect());
+    assert!(is_mod_loaded(&loaded, &inventory_item(Some("RimGuns"))));
+    assert!(!is_mod_loaded(
+        &loaded,
+        &inventory_item(Some("VanillaWeapons"))
+    ));
+}
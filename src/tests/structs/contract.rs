@@ -0,0 +1,70 @@
+use bigdecimal::BigDecimal;
+use chrono::Utc;
+
+use crate::crypto::generate_v4_uuid;
+use crate::db::models::contract::Contract;
+use crate::packets::contract::ContractStatusEnum;
+use crate::structs::contract::{offer_quantity, offers_wanted};
+
+fn contract(status: ContractStatusEnum) -> Contract {
+    let now = Utc::now().naive_utc();
+    Contract {
+        contract_id: generate_v4_uuid(),
+        colony_id: generate_v4_uuid(),
+        item_code: "item".to_string(),
+        quantity: 1,
+        reward: 100,
+        currency: 0,
+        status: status.into(),
+        start_tick: 0,
+        deadline_tick: 60_000,
+        private_key: String::new(),
+        create_date: now,
+        update_date: now,
+        market: "1.3".to_string(),
+    }
+}
+
+#[test]
+fn offers_top_up_to_the_limit() {
+    let existing = vec![contract(ContractStatusEnum::Offered)];
+    assert_eq!(offers_wanted(&existing, 3), 2);
+    assert_eq!(offers_wanted(&vec![], 3), 3);
+}
+
+#[test]
+fn accepted_contracts_count_towards_the_limit() {
+    let existing = vec![
+        contract(ContractStatusEnum::Offered),
+        contract(ContractStatusEnum::Accepted),
+        contract(ContractStatusEnum::Accepted),
+    ];
+    assert_eq!(offers_wanted(&existing, 3), 0);
+    assert_eq!(offers_wanted(&existing, 2), 0);
+}
+
+#[test]
+fn closed_contracts_dont_count_towards_the_limit() {
+    let existing = vec![
+        contract(ContractStatusEnum::Fulfilled),
+        contract(ContractStatusEnum::Expired),
+    ];
+    assert_eq!(offers_wanted(&existing, 3), 3);
+}
+
+#[test]
+fn offer_quantity_stays_under_the_cap() {
+    let max_value = BigDecimal::from(2_000);
+    assert_eq!(offer_quantity(&BigDecimal::from(300), &max_value), Some(6));
+    assert_eq!(
+        offer_quantity(&BigDecimal::from(2_000), &max_value),
+        Some(1)
+    );
+}
+
+#[test]
+fn items_worth_more_than_the_cap_are_not_offered() {
+    let max_value = BigDecimal::from(2_000);
+    assert_eq!(offer_quantity(&BigDecimal::from(2_001), &max_value), None);
+    assert_eq!(offer_quantity(&BigDecimal::from(0), &max_value), None);
+}
@@ -0,0 +1,35 @@
+use crate::jtd::api_config::structure::ApiConfigDataMarkets;
+use crate::structs::market::{is_market_live, market_for_game_version};
+
+#[test]
+fn game_versions_map_to_major_markets() {
+    assert_eq!(
+        market_for_game_version("1.3.3200 rev726"),
+        Some("1.3".to_string())
+    );
+    assert_eq!(market_for_game_version("1.4.3523"), Some("1.4".to_string()));
+    assert_eq!(market_for_game_version("1.2"), Some("1.2".to_string()));
+}
+
+#[test]
+fn unreadable_game_versions_have_no_market() {
+    assert_eq!(market_for_game_version(""), None);
+    assert_eq!(market_for_game_version("1"), None);
+    assert_eq!(market_for_game_version("beta.3"), None);
+}
+
+#[test]
+fn every_market_is_live_when_none_are_listed() {
+    let config = ApiConfigDataMarkets::default();
+    assert!(is_market_live(&config, "1.3"));
+    assert!(is_market_live(&config, "1.4"));
+}
+
+#[test]
+fn only_listed_markets_are_live() {
+    let config = ApiConfigDataMarkets {
+        live: vec!["1.4".to_string()],
+    };
+    assert!(is_market_live(&config, "1.4"));
+    assert!(!is_market_live(&config, "1.3"));
+}
@@ -0,0 +1,6 @@
+pub mod account;
+pub mod blacklist;
+pub mod colony_mods;
+pub mod contract;
+pub mod market;
+pub mod pending_inventory;
@@ -0,0 +1,46 @@
+use bigdecimal::BigDecimal;
+
+use crate::db::models::inventory::InventoryNoQuantity;
+use crate::db::models::pending_inventory::PendingInventory;
+use crate::packets::inventory::ModerationStatusEnum;
+
+fn voted_item(base_value: f32) -> InventoryNoQuantity {
+    let base_value = BigDecimal::from(base_value);
+    InventoryNoQuantity {
+        item_code: "ABCD1234".to_string(),
+        thing_def: "Gun_Revolver".to_string(),
+        quality: Some(3),
+        minified: false,
+        buy_at: base_value.clone(),
+        sell_at: base_value.clone(),
+        base_value,
+        stuff: None,
+        weight: BigDecimal::from(1.5),
+        version: "EFGH5678".to_string(),
+        mod_name: None,
+        market: "1.3".to_string(),
+    }
+}
+
+#[test]
+fn votes_are_queued_as_pending() {
+    let pending = PendingInventory::from_vote(voted_item(10f32), 7);
+    assert_eq!(pending.status, ModerationStatusEnum::Pending as i32);
+    assert_eq!(pending.votes, 7);
+    assert_eq!(pending.version, "EFGH5678");
+    assert_eq!(pending.market, "1.3");
+    assert!(pending.review_date.is_none());
+    assert!(pending.reviewed_by.is_none());
+}
+
+#[test]
+fn approved_items_are_priced_at_edited_base_value() {
+    let mut pending = PendingInventory::from_vote(voted_item(10f32), 7);
+    pending.base_value = BigDecimal::from(25);
+
+    let item = InventoryNoQuantity::from(pending);
+    assert_eq!(item.base_value, BigDecimal::from(25));
+    assert_eq!(item.buy_at, BigDecimal::from(25));
+    assert_eq!(item.sell_at, BigDecimal::from(25));
+    assert_eq!(item.quality, Some(3));
+}
@@ -20,6 +20,7 @@ impl Confirmable for AccountBind {
 
 impl Confirmable for ClientBind {
     fn confirm(&mut self) {
+        use crate::cache::bind::invalidate_client_bind;
         use crate::db::get_pg_connection;
         use crate::db::schema::client_binds as cb_schema;
         use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
@@ -29,5 +30,6 @@ impl Confirmable for ClientBind {
             .execute(&conn)
             .expect("Failed to mark account bind confirmed");
         self.confirmed = true;
+        invalidate_client_bind(&self.client_bind_id);
     }
 }
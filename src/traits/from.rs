@@ -7,16 +7,19 @@ use crate::db::models::new_inventory_vote::NewInventoryVote;
 use crate::db::models::price_tracker::PriceTracker;
 
 pub trait InventoryToPT {
-    fn i2pt(c: Inventory, now: &NaiveDateTime) -> Self;
+    fn i2pt(c: &Inventory, now: &NaiveDateTime) -> Self;
 }
 
 impl InventoryToPT for PriceTracker {
-    /// Convert from Inventory row to Protobuf message data
-    fn i2pt(c: Inventory, now: &NaiveDateTime) -> Self {
+    /// Snapshot the current prices of an Inventory row
+    fn i2pt(c: &Inventory, now: &NaiveDateTime) -> Self {
         PriceTracker {
-            item_code: c.item_code,
-            value: c.base_value,
+            item_code: c.item_code.clone(),
+            value: c.base_value.clone(),
             create_date: *now,
+            buy_at: c.buy_at.clone(),
+            sell_at: c.sell_at.clone(),
+            market: c.market.clone(),
         }
     }
 }
@@ -39,6 +42,7 @@ impl NewInventoryToVote for NewInventoryVote {
             client_bind_id,
             colony_id,
             version: item.version,
+            market: item.market,
         }
     }
 }
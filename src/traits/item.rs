@@ -1,20 +1,22 @@
 use std::borrow::Borrow;
 use std::collections::HashSet;
+use std::iter::FromIterator;
 
 use diesel::SaveChangesDsl;
 use itsdangerous::Signer;
 
 use crate::crypto::{hash_short_identity_string, sign_string};
-use crate::db::models::bank::BankBalance;
 use crate::db::models::inventory::Inventory;
 use crate::db::models::order::Order;
 use crate::db::Ppc;
+use crate::packets::bank::BankLedgerKindEnum;
 use crate::packets::order::{OrderItem, OrderStatusEnum};
 use crate::packets::tradable::{ColonyTradable, Tradable};
 use crate::structs::bank_balance::get_bank_balance;
+use crate::structs::bank_ledger::apply_ledger_entry;
 use crate::structs::inventory::{get_inventory, update_stock};
 use crate::traits::numerical::CanRound;
-use bigdecimal::{Signed, ToPrimitive};
+use bigdecimal::{BigDecimal, Signed, ToPrimitive};
 
 pub trait ItemCodeComputable {
     fn generate_item_code(&self) -> String;
@@ -79,6 +81,10 @@ pub trait HasThingDef {
     fn get_thing_def(&self) -> &String;
 }
 
+pub trait HasStuff {
+    fn get_stuff(&self) -> Option<&String>;
+}
+
 pub trait HasQuantity {
     fn get_quantity(&self) -> i64;
 }
@@ -114,12 +120,23 @@ impl MakeTradable for Inventory {
             },
             stuff: self.stuff.unwrap_or(String::new()),
             weight: self.weight.round_2dp().to_f32().unwrap(),
+            condition: self.condition,
         }
     }
 }
 
-pub(crate) trait Rollback {
+pub trait Rollback {
     fn rollback(&mut self, conn: &Ppc) -> Result<(), ()>;
+
+    /// Only undo the items bought, returning them to stock and refunding
+    /// `refund_pct` of what was paid for them, recorded on the ledger as `kind`.
+    /// Returns the amount refunded. Doesn't change the status, that's up to the caller.
+    fn rollback_purchases(
+        &mut self,
+        refund_pct: f32,
+        kind: BankLedgerKindEnum,
+        conn: &Ppc,
+    ) -> Result<i32, ()>;
 }
 
 impl Rollback for Order {
@@ -131,7 +148,7 @@ impl Rollback for Order {
             inventory_wanted.insert(&item.item_code);
         }
 
-        let mut inventory = get_inventory(inventory_wanted, &conn);
+        let mut inventory = get_inventory(inventory_wanted, &self.market, &conn);
         let mut bank_balance =
             get_bank_balance(self.colony_id, self.manifest.currency.into(), &conn)?;
 
@@ -143,14 +160,15 @@ impl Rollback for Order {
             &conn,
         )?;
 
-        // Add the inverse of what we added last time, clamp to zero.
-        bank_balance.balance =
-            (bank_balance.balance + (self.manifest.balance_adjustment * -1)).max(0);
-
-        // Save changes to bank balance
-        bank_balance
-            .save_changes::<BankBalance>(&**conn)
-            .map_err(|_| ())?;
+        // Add the inverse of what we added last time, this can leave them
+        // in debt if they've already spent it, which is recorded on the ledger.
+        apply_ledger_entry(
+            &mut bank_balance,
+            self.manifest.balance_adjustment * -1,
+            BankLedgerKindEnum::Rollback,
+            Some(self.order_id),
+            conn,
+        )?;
 
         // Update the order itself
         self.status = OrderStatusEnum::Reversed.into();
@@ -158,6 +176,40 @@ impl Rollback for Order {
 
         Ok(())
     }
+
+    fn rollback_purchases(
+        &mut self,
+        refund_pct: f32,
+        kind: BankLedgerKindEnum,
+        conn: &Ppc,
+    ) -> Result<i32, ()> {
+        let inventory_wanted = HashSet::<&String>::from_iter(
+            self.manifest.wtb.iter().map(|item| &item.item_code),
+        );
+
+        let mut inventory = get_inventory(inventory_wanted, &self.market, &conn);
+        let mut bank_balance =
+            get_bank_balance(self.colony_id, self.manifest.currency.into(), &conn)?;
+
+        // Put what they bought back in stock, as if we'd bought it from them
+        update_stock(&self.manifest.wtb, &vec![], &mut inventory, &conn)?;
+
+        let refund_pct = BigDecimal::from(refund_pct.max(0f32).min(1f32));
+        let refund = (self.order_stats.buy_item_cost() * refund_pct)
+            .with_scale(0)
+            .to_i32()
+            .unwrap_or(0);
+
+        apply_ledger_entry(
+            &mut bank_balance,
+            refund,
+            kind,
+            Some(self.order_id),
+            conn,
+        )?;
+
+        Ok(refund)
+    }
 }
 
 pub trait ValidateItemSignature {